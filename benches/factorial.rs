@@ -0,0 +1,50 @@
+use std::time::Instant;
+
+use kuon::{analyzer, compiler, lexer::Lexer, parser, vm};
+
+const ITERATIONS: u32 = 2000;
+
+fn main() {
+    let source = r#"
+    fun factorial(n int) int {
+        if n == 1 {
+            1
+        } else {
+            n * factorial(n - 1)
+        }
+    }
+
+    fun sumFactorials(n int) int {
+        val f = factorial(20);
+
+        if n == 0 {
+            f
+        } else {
+            f - factorial(19) * 20 + sumFactorials(n - 1)
+        }
+    }
+
+    sumFactorials(20)
+    "#
+    .to_string();
+
+    let mut lexer = Lexer::from_string(&source);
+    let mut ast = parser::parse_source(&mut lexer).unwrap();
+    analyzer::validate(&mut ast).unwrap();
+    let chunk = compiler::compile_source(&ast).unwrap();
+
+    let start = Instant::now();
+
+    for _ in 0..ITERATIONS {
+        vm::execute(&chunk).unwrap();
+    }
+
+    let elapsed = start.elapsed();
+
+    println!(
+        "factorial: {} iterations in {:?} ({:?} per iteration)",
+        ITERATIONS,
+        elapsed,
+        elapsed / ITERATIONS
+    );
+}
@@ -1,6 +1,9 @@
-use std::collections::BTreeMap;
+use std::{cell::RefCell, collections::BTreeMap};
 
-use crate::parser::r#type::Type;
+use crate::parser::{
+    expression::{Expression, InterfaceFunction},
+    r#type::Type,
+};
 
 use super::util::types_equal;
 
@@ -15,14 +18,95 @@ pub struct EnvironmentVariable {
 pub struct EnvironmentFunction {
     pub name: String,
     pub pre_param_type: Option<Type>,
+
+    // true if the pre-parameter is declared with var
+    pub pre_param_var: bool,
+
     pub param_types: Vec<Type>,
+    pub var_params: Vec<bool>,
     pub return_type: Type,
+
+    // names of the parameters for keyword arguments, this is empty for
+    // native functions since their signatures don't have names
+    pub param_names: Vec<String>,
+
+    // one for each parameter, true if it has a default value and can be left
+    // out of a call
+    pub default_params: Vec<bool>,
+
+    // whether the last parameter is a rest parameter
+    pub rest_param: bool,
+}
+
+impl EnvironmentFunction {
+    /// A function registered by the host program, which only has positional
+    /// parameters
+    pub fn native(
+        name: String,
+        param_types: Vec<Type>,
+        return_type: Type,
+    ) -> Self {
+        Self {
+            name,
+            pre_param_type: None,
+            pre_param_var: false,
+            var_params: vec![false; param_types.len()],
+            default_params: vec![false; param_types.len()],
+            param_types,
+            return_type,
+            param_names: vec![],
+            rest_param: false,
+        }
+    }
+
+    /// The type of the function when it's used as a value
+    pub fn type_(&self) -> Type {
+        Type::Function {
+            param_types: self.param_types.clone(),
+            var_params: self.var_params.clone(),
+            return_type: Box::new(self.return_type.clone()),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct EnvironmentStruct {
     pub name: String,
     pub fields: BTreeMap<String, Type>,
+
+    // already validated default values of the fields which have them
+    pub defaults: BTreeMap<String, Expression>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvironmentEnum {
+    pub name: String,
+
+    // variants without fields have an empty map of fields
+    pub variants: BTreeMap<String, BTreeMap<String, Type>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvironmentAlias {
+    pub name: String,
+    pub type_: Type,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvironmentInterface {
+    pub name: String,
+    pub functions: Vec<InterfaceFunction>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvironmentLoop {
+    // only loop expressions can be broken with a value, while and for loops
+    // always evaluate to null
+    pub breaks_with_value: bool,
+
+    // type of the values the loop is broken with, None until the first break
+    // is validated
+    pub break_type: RefCell<Option<Type>>,
 }
 
 #[derive(Debug, Clone)]
@@ -31,7 +115,23 @@ pub struct Environment<'a> {
 
     pub variables: Vec<EnvironmentVariable>,
     pub functions: Vec<EnvironmentFunction>,
+
+    // the type functions of each type, by the name of the type
+    pub type_functions: BTreeMap<String, Vec<EnvironmentFunction>>,
+
     pub structs: Vec<EnvironmentStruct>,
+    pub enums: Vec<EnvironmentEnum>,
+    pub aliases: Vec<EnvironmentAlias>,
+    pub interfaces: Vec<EnvironmentInterface>,
+
+    // Some only for the environment of a function's body. Every variable of an
+    // enclosing function which is used inside of the function's body gets
+    // added to it. This is a RefCell because the variable is usually used
+    // from an inner environment, which only has an immutable reference to it
+    pub captures: Option<RefCell<Vec<String>>>,
+
+    // Some only for the environment of a loop's body
+    pub loop_context: Option<EnvironmentLoop>,
 }
 
 impl<'a> Environment<'a> {
@@ -40,7 +140,20 @@ impl<'a> Environment<'a> {
             parent_env: None,
             variables: vec![],
             functions: vec![],
+            type_functions: BTreeMap::new(),
             structs: vec![],
+            enums: vec![],
+            aliases: vec![],
+            interfaces: vec![],
+            captures: None,
+            loop_context: None,
+        }
+    }
+
+    pub fn for_function_body(parent_env: &'a Environment) -> Self {
+        Self {
+            captures: Some(RefCell::new(vec![])),
+            ..Self::from_parent(parent_env)
         }
     }
 
@@ -54,9 +167,46 @@ impl<'a> Environment<'a> {
             // environment because otherwise you would need to go up
             // and up to the top level one for every function call
             functions: parent_env.functions.clone(),
+            type_functions: parent_env.type_functions.clone(),
 
-            // same goes for structs
+            // same goes for structs, enums, type aliases and interfaces
             structs: parent_env.structs.clone(),
+            enums: parent_env.enums.clone(),
+            aliases: parent_env.aliases.clone(),
+            interfaces: parent_env.interfaces.clone(),
+
+            captures: None,
+            loop_context: None,
+        }
+    }
+
+    pub fn for_loop_body(
+        parent_env: &'a Environment,
+        breaks_with_value: bool,
+    ) -> Self {
+        Self {
+            loop_context: Some(EnvironmentLoop {
+                breaks_with_value,
+                break_type: RefCell::new(None),
+            }),
+            ..Self::from_parent(parent_env)
+        }
+    }
+
+    /// Get the innermost loop which can be broken out of from here, which
+    /// can't be outside of the function this environment is in
+    pub fn get_loop(&self) -> Option<&EnvironmentLoop> {
+        if self.loop_context.is_some() {
+            return self.loop_context.as_ref();
+        }
+
+        if self.captures.is_some() {
+            return None;
+        }
+
+        match self.parent_env {
+            Some(parent_env) => parent_env.get_loop(),
+            None => None,
         }
     }
 
@@ -73,6 +223,56 @@ impl<'a> Environment<'a> {
         }
     }
 
+    /// Same as get_variable, except that this should be used when the variable
+    /// is actually being read or assigned to. If the variable belongs to an
+    /// enclosing function, it's added to the captures of every function
+    /// between here and there
+    pub fn use_variable(&self, name: &str) -> Option<EnvironmentVariable> {
+        let mut functions_in_between: Vec<&Environment> = vec![];
+        let mut env = self;
+
+        loop {
+            for variable in &env.variables {
+                if variable.name == name {
+                    // variables which aren't inside of any function are
+                    // globals, so they don't need to be captured
+                    if env.is_inside_function() {
+                        for function_env in functions_in_between {
+                            let mut captures = function_env
+                                .captures
+                                .as_ref()
+                                .unwrap()
+                                .borrow_mut();
+
+                            if !captures.iter().any(|c| c == name) {
+                                captures.push(name.to_string());
+                            }
+                        }
+                    }
+
+                    return Some(variable.clone());
+                }
+            }
+
+            if env.captures.is_some() {
+                functions_in_between.push(env);
+            }
+
+            env = env.parent_env?;
+        }
+    }
+
+    fn is_inside_function(&self) -> bool {
+        if self.captures.is_some() {
+            return true;
+        }
+
+        match self.parent_env {
+            Some(parent_env) => parent_env.is_inside_function(),
+            None => false,
+        }
+    }
+
     pub fn get_function(
         &self,
         name: &str,
@@ -107,6 +307,20 @@ impl<'a> Environment<'a> {
         return None;
     }
 
+    pub fn get_type_function(
+        &self,
+        type_name: &str,
+        name: &str,
+    ) -> Option<EnvironmentFunction> {
+        self.type_functions
+            .get(type_name)?
+            .iter()
+            .find(|function| function.name == name)
+            .cloned()
+    }
+
+    /// Also finds structs through type aliases, an alias of an anonymous
+    /// struct is treated like a struct with the alias' name
     pub fn get_struct(&self, name: &str) -> Option<EnvironmentStruct> {
         for struct_ in &self.structs {
             if struct_.name == name {
@@ -114,9 +328,182 @@ impl<'a> Environment<'a> {
             }
         }
 
+        if self.get_alias(name).is_some() {
+            match self.resolve_type(&Type::UserDefined(name.to_string())) {
+                Type::UserDefined(struct_name) => {
+                    return self.get_struct(&struct_name)
+                }
+
+                Type::Struct { fields } => {
+                    return Some(EnvironmentStruct {
+                        name: name.to_string(),
+                        fields,
+                        defaults: BTreeMap::new(),
+                    })
+                }
+
+                _ => {}
+            }
+        }
+
         return None;
     }
 
+    /// Also finds enums through type aliases
+    pub fn get_enum(&self, name: &str) -> Option<EnvironmentEnum> {
+        for enum_ in &self.enums {
+            if enum_.name == name {
+                return Some(enum_.clone());
+            }
+        }
+
+        if self.get_alias(name).is_some() {
+            if let Type::UserDefined(enum_name) =
+                self.resolve_type(&Type::UserDefined(name.to_string()))
+            {
+                return self.get_enum(&enum_name);
+            }
+        }
+
+        return None;
+    }
+
+    pub fn get_alias(&self, name: &str) -> Option<EnvironmentAlias> {
+        for alias in &self.aliases {
+            if alias.name == name {
+                return Some(alias.clone());
+            }
+        }
+
+        return None;
+    }
+
+    pub fn get_interface(&self, name: &str) -> Option<EnvironmentInterface> {
+        self.interfaces
+            .iter()
+            .find(|interface| interface.name == name)
+            .cloned()
+    }
+
+    /// Check whether a type has a value function matching each function of
+    /// the interface, with this replaced by the type. The pre-parameter of an
+    /// interface function is always val, since the value it's called on isn't
+    /// updated afterwards
+    pub fn implements(&self, type_: &Type, interface_name: &str) -> bool {
+        let interface = match self.get_interface(interface_name) {
+            Some(interface) => interface,
+            None => return false,
+        };
+
+        let replace_this = |t: &Type| match t {
+            Type::This => type_.clone(),
+            _ => t.clone(),
+        };
+
+        interface.functions.iter().all(|expected| {
+            let function =
+                match self.get_function(&expected.name, &Some(type_.clone())) {
+                    Some(function) => function,
+                    None => return false,
+                };
+
+            !function.pre_param_var
+                && function.var_params == expected.var_params
+                && function.param_types.len() == expected.param_types.len()
+                && function
+                    .param_types
+                    .iter()
+                    .zip(expected.param_types.iter())
+                    .all(|(a, b)| types_equal(self, a, &replace_this(b)))
+                && types_equal(
+                    self,
+                    &function.return_type,
+                    &replace_this(&expected.return_type),
+                )
+        })
+    }
+
+    /// Replace every type alias inside of a type with the type it stands for,
+    /// and the names of interfaces with Type::Interface. Aliases can't refer
+    /// to themselves (see validate_type_alias), so this always ends
+    pub fn resolve_type(&self, type_: &Type) -> Type {
+        match type_ {
+            Type::UserDefined(name) => match self.get_alias(name) {
+                Some(alias) => self.resolve_type(&alias.type_),
+
+                None if self.get_interface(name).is_some() => {
+                    Type::Interface(name.clone())
+                }
+
+                None => type_.clone(),
+            },
+
+            // Alias:Variant where the alias stands for an enum
+            Type::EnumVariant { name, variant } => {
+                match self.resolve_type(&Type::UserDefined(name.clone())) {
+                    Type::UserDefined(name) => Type::EnumVariant {
+                        name,
+                        variant: variant.clone(),
+                    },
+
+                    _ => type_.clone(),
+                }
+            }
+
+            Type::Array(element_type) => {
+                Type::Array(Box::new(self.resolve_type(element_type)))
+            }
+
+            Type::Tuple(element_types) => Type::Tuple(
+                element_types.iter().map(|t| self.resolve_type(t)).collect(),
+            ),
+
+            Type::Map {
+                key_type,
+                value_type,
+            } => Type::Map {
+                key_type: Box::new(self.resolve_type(key_type)),
+                value_type: Box::new(self.resolve_type(value_type)),
+            },
+
+            Type::Nullable(inner) => {
+                Type::Nullable(Box::new(self.resolve_type(inner)))
+            }
+
+            Type::Function {
+                param_types,
+                var_params,
+                return_type,
+            } => Type::Function {
+                param_types: param_types
+                    .iter()
+                    .map(|t| self.resolve_type(t))
+                    .collect(),
+                var_params: var_params.clone(),
+                return_type: Box::new(self.resolve_type(return_type)),
+            },
+
+            Type::Struct { fields } => Type::Struct {
+                fields: fields
+                    .iter()
+                    .map(|(name, t)| (name.clone(), self.resolve_type(t)))
+                    .collect(),
+            },
+
+            _ => type_.clone(),
+        }
+    }
+
+    /// Get the fields of an enum variant, None if either the enum or the
+    /// variant doesn't exist
+    pub fn get_enum_variant(
+        &self,
+        name: &str,
+        variant: &str,
+    ) -> Option<BTreeMap<String, Type>> {
+        self.get_enum(name)?.variants.get(variant).cloned()
+    }
+
     pub fn add_variable(&mut self, name: String, type_: Type, constant: bool) {
         self.variables.push(EnvironmentVariable {
             name,
@@ -125,22 +512,52 @@ impl<'a> Environment<'a> {
         })
     }
 
-    pub fn add_function(
+    pub fn add_function(&mut self, function: EnvironmentFunction) {
+        self.functions.push(function)
+    }
+
+    pub fn add_type_function(
+        &mut self,
+        type_name: String,
+        function: EnvironmentFunction,
+    ) {
+        self.type_functions
+            .entry(type_name)
+            .or_default()
+            .push(function)
+    }
+
+    pub fn add_struct(
         &mut self,
         name: String,
-        pre_param_type: Option<Type>,
-        param_types: Vec<Type>,
-        return_type: Type,
+        fields: BTreeMap<String, Type>,
+        defaults: BTreeMap<String, Expression>,
     ) {
-        self.functions.push(EnvironmentFunction {
+        self.structs.push(EnvironmentStruct {
             name,
-            pre_param_type,
-            param_types,
-            return_type,
+            fields,
+            defaults,
         })
     }
 
-    pub fn add_struct(&mut self, name: String, fields: BTreeMap<String, Type>) {
-        self.structs.push(EnvironmentStruct { name, fields })
+    pub fn add_enum(
+        &mut self,
+        name: String,
+        variants: BTreeMap<String, BTreeMap<String, Type>>,
+    ) {
+        self.enums.push(EnvironmentEnum { name, variants })
+    }
+
+    pub fn add_alias(&mut self, name: String, type_: Type) {
+        self.aliases.push(EnvironmentAlias { name, type_ })
+    }
+
+    pub fn add_interface(
+        &mut self,
+        name: String,
+        functions: Vec<InterfaceFunction>,
+    ) {
+        self.interfaces
+            .push(EnvironmentInterface { name, functions })
     }
 }
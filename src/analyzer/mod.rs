@@ -1,23 +1,63 @@
 use analyzer_error::AnalyzerError;
-use env::Environment;
+use env::{Environment, EnvironmentFunction};
 
 use crate::{
-    analyzer::validate::validate_and_get_type, parser::expression::Expression,
+    analyzer::validate::validate_and_get_type,
+    analyzer_error, expression_pat,
+    parser::expression::{Expression, ExpressionData},
+    vm::native_function::NativeFunctions,
 };
 
-mod analyzer_error;
-mod env;
+pub mod analyzer_error;
+pub(crate) mod env;
 pub mod util;
 mod validate;
 
 pub fn validate(ast: &mut Expression) -> Result<(), AnalyzerError> {
+    validate_with_natives(ast, &NativeFunctions::new())
+}
+
+/// Validate the source code, which can call the given native functions
+pub fn validate_with_natives(
+    ast: &mut Expression,
+    natives: &NativeFunctions,
+) -> Result<(), AnalyzerError> {
     let mut root_env = Environment::new();
 
+    for native in &natives.functions {
+        root_env.add_function(EnvironmentFunction::native(
+            native.name.clone(),
+            native.param_types.clone(),
+            native.return_type.clone(),
+        ));
+    }
+
     validate_expression(ast, &mut root_env)?;
 
     return Ok(());
 }
 
+/// Validate top level code directly in the given environment instead of a
+/// block environment, so that the functions, structs and variables it defines
+/// are still there when validating code which comes after it
+pub(crate) fn validate_top_level(
+    ast: &mut Expression,
+    env: &mut Environment,
+) -> Result<(), AnalyzerError> {
+    if let expression_pat!(ExpressionData::Block { expressions }) = ast {
+        for expression in expressions {
+            validate_and_get_type(expression, env)?;
+        }
+    } else {
+        return analyzer_error!(
+            ast.line,
+            "Source code is expected to be a block expression"
+        );
+    }
+
+    return Ok(());
+}
+
 fn validate_expression(
     expression: &mut Expression,
     parent_env: &mut Environment,
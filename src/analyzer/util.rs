@@ -1,4 +1,7 @@
-use crate::parser::r#type::Type;
+use crate::parser::{
+    expression::{Expression, ExpressionData},
+    r#type::Type,
+};
 
 use super::env::Environment;
 
@@ -7,6 +10,13 @@ pub fn types_equal(env: &Environment, type1: &Type, type2: &Type) -> bool {
         return true;
     }
 
+    // type aliases are compared as the types they stand for
+    let type1 = &env.resolve_type(type1);
+    let type2 = &env.resolve_type(type2);
+    if type1 == type2 {
+        return true;
+    }
+
     // if one type is UserDefined and the other is Struct, convert the
     // UserDefined to Struct and then compare them
     match (type1, type2) {
@@ -25,8 +35,202 @@ pub fn types_equal(env: &Environment, type1: &Type, type2: &Type) -> bool {
             }
         }
 
+        // arrays are equal if their elements are
+        (Type::Array(element1), Type::Array(element2)) => {
+            return types_equal(env, element1, element2);
+        }
+
+        (
+            Type::Map {
+                key_type: key1,
+                value_type: value1,
+            },
+            Type::Map {
+                key_type: key2,
+                value_type: value2,
+            },
+        ) => {
+            return types_equal(env, key1, key2)
+                && types_equal(env, value1, value2);
+        }
+
+        (Type::Tuple(elements1), Type::Tuple(elements2)) => {
+            return elements1.len() == elements2.len()
+                && elements1
+                    .iter()
+                    .zip(elements2.iter())
+                    .all(|(a, b)| types_equal(env, a, b));
+        }
+
+        // a variant can be used where its enum is expected and the other way
+        // around, which is checked at runtime (see cast_to_variant)
+        (Type::EnumVariant { name: name1, .. }, Type::UserDefined(name2))
+        | (Type::UserDefined(name2), Type::EnumVariant { name: name1, .. }) => {
+            return name1 == name2 && env.get_enum(name1).is_some();
+        }
+
+        (Type::Nullable(inner1), Type::Nullable(inner2)) => {
+            return types_equal(env, inner1, inner2);
+        }
+
+        // functions are equal if their signatures are, including which of
+        // their parameters are var
+        (
+            Type::Function {
+                param_types: params1,
+                var_params: var1,
+                return_type: return1,
+            },
+            Type::Function {
+                param_types: params2,
+                var_params: var2,
+                return_type: return2,
+            },
+        ) => {
+            return var1 == var2
+                && params1.len() == params2.len()
+                && params1
+                    .iter()
+                    .zip(params2.iter())
+                    .all(|(a, b)| types_equal(env, a, b))
+                && types_equal(env, return1, return2);
+        }
+
         _ => {}
     }
 
     false
 }
+
+/// Check if a value of type value_type can be stored where expected_type is
+/// expected. This is the same as types_equal, except that null and values of
+/// the inner type can also be stored where a nullable type is expected
+pub fn can_assign(
+    env: &Environment,
+    expected_type: &Type,
+    value_type: &Type,
+) -> bool {
+    if types_equal(env, expected_type, value_type) {
+        return true;
+    }
+
+    match &env.resolve_type(expected_type) {
+        // anything can be put in an any, see cast_value
+        Type::Any => true,
+
+        // as long as its type implements the interface, a value is stored
+        // like it would be in an any
+        Type::Interface(name) => {
+            let value_type = match value_type {
+                Type::EnumVariant { name, .. } => {
+                    Type::UserDefined(name.clone())
+                }
+                _ => value_type.clone(),
+            };
+
+            env.implements(&value_type, name)
+        }
+
+        Type::Nullable(inner) => {
+            matches!(value_type, Type::Null)
+                || can_assign(env, inner, value_type)
+        }
+
+        _ => false,
+    }
+}
+
+/// Wrap a value which was checked with can_assign in whatever the compiler
+/// needs to store it where expected_type is expected
+pub fn cast_value(
+    expected_type: &Type,
+    value: &mut Expression,
+    value_type: &Type,
+) {
+    cast_to_variant(expected_type, value, value_type);
+    cast_to_any(expected_type, value, value_type);
+}
+
+/// If a value of an enum is used where one of its variants is expected, wrap
+/// the value in a VariantCast so that its variant is checked at runtime
+fn cast_to_variant(
+    expected_type: &Type,
+    value: &mut Expression,
+    value_type: &Type,
+) {
+    if let (
+        Type::EnumVariant { name, variant },
+        Type::UserDefined(value_name),
+    ) = (expected_type, value_type)
+    {
+        if name != value_name {
+            return;
+        }
+
+        let line = value.line;
+        let inner_value = std::mem::replace(
+            value,
+            Expression {
+                data: ExpressionData::Null,
+                line,
+            },
+        );
+
+        *value = Expression {
+            data: ExpressionData::VariantCast {
+                value: Box::new(inner_value),
+                name: name.clone(),
+                variant: variant.clone(),
+            },
+            line,
+        };
+    }
+}
+
+/// If a value is used where an any or an interface is expected, wrap it in a
+/// Cast so that it carries its type at runtime, which is checked when it's
+/// cast back and used to find the functions of an interface
+fn cast_to_any(
+    expected_type: &Type,
+    value: &mut Expression,
+    value_type: &Type,
+) {
+    // a value which can't be null is stored in a nullable like it would be in
+    // the inner type
+    let expected_type = match expected_type {
+        Type::Nullable(inner)
+            if !matches!(value_type, Type::Null | Type::Nullable(_)) =>
+        {
+            inner
+        }
+        _ => expected_type,
+    };
+
+    match (expected_type, value_type) {
+        (Type::Any, Type::Any) => return,
+        (Type::Any, _) => {}
+
+        (Type::Interface(_), Type::Interface(_)) => return,
+        (Type::Interface(_), _) => {}
+
+        _ => return,
+    }
+
+    let line = value.line;
+    let inner_value = std::mem::replace(
+        value,
+        Expression {
+            data: ExpressionData::Null,
+            line,
+        },
+    );
+
+    *value = Expression {
+        data: ExpressionData::Cast {
+            value: Box::new(inner_value),
+            type_: Type::Any,
+            value_type: Some(value_type.clone()),
+        },
+        line,
+    };
+}
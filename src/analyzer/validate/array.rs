@@ -0,0 +1,73 @@
+use crate::{
+    analyzer::{
+        analyzer_error::AnalyzerError,
+        env::Environment,
+        util::{can_assign, cast_value, types_equal},
+    },
+    analyzer_error,
+    parser::{expression::Expression, r#type::Type},
+};
+
+use super::{validate_and_get_type, validate_and_get_type_expecting};
+
+pub fn validate_make_array(
+    env: &mut Environment,
+    line: usize,
+    elements: &mut Vec<Expression>,
+) -> Result<Type, AnalyzerError> {
+    // the element type is taken from the first element, so there's nothing
+    // to take it from in an empty array (unless its type is known from where
+    // it's used, see validate_and_get_type_expecting)
+    if elements.is_empty() {
+        return analyzer_error!(
+            line,
+            "Cannot determine the type of an empty array"
+        );
+    }
+
+    let element_type = validate_and_get_type(&mut elements[0], env)?;
+
+    for element in elements.iter_mut().skip(1) {
+        let type_ = validate_and_get_type(element, env)?;
+
+        if !types_equal(env, &element_type, &type_) {
+            return analyzer_error!(
+                element.line,
+                "Expected array element of type {:?}, got value of type {:?} \
+                 instead",
+                element_type,
+                type_
+            );
+        }
+    }
+
+    return Ok(Type::Array(Box::new(element_type)));
+}
+
+/// Validate an array whose type is known from where it's used. Its elements
+/// are checked against the expected element type instead of the first
+/// element's, so that [1, null] can be an array[nullable int]
+pub fn validate_make_array_expecting(
+    env: &mut Environment,
+    elements: &mut Vec<Expression>,
+    element_type: &Type,
+) -> Result<Type, AnalyzerError> {
+    for element in elements.iter_mut() {
+        let type_ =
+            validate_and_get_type_expecting(element, env, element_type)?;
+
+        if !can_assign(env, element_type, &type_) {
+            return analyzer_error!(
+                element.line,
+                "Expected array element of type {:?}, got value of type {:?} \
+                 instead",
+                element_type,
+                type_
+            );
+        }
+
+        cast_value(element_type, element, &type_);
+    }
+
+    return Ok(Type::Array(Box::new(element_type.clone())));
+}
@@ -0,0 +1,172 @@
+use crate::{
+    analyzer::{
+        analyzer_error::AnalyzerError,
+        env::{Environment, EnvironmentFunction},
+        util::{can_assign, cast_value, types_equal},
+    },
+    analyzer_error,
+    parser::{
+        expression::{Expression, ExpressionData, CONVERSION_FUNCTION_PREFIX},
+        r#type::Type,
+    },
+};
+
+use super::validate_and_get_type;
+
+/// Check if a value of the source type can be cast to the target type without
+/// a conversion function
+pub fn builtin_cast_exists(
+    env: &Environment,
+    source_type: &Type,
+    type_: &Type,
+) -> bool {
+    if *type_ == Type::Any || *source_type == Type::Any {
+        return true;
+    }
+
+    if can_assign(env, type_, source_type) {
+        return true;
+    }
+
+    matches!(
+        (source_type, type_),
+        (Type::Int, Type::Float)
+            | (Type::Float, Type::Int)
+            | (Type::Char, Type::Int)
+            | (Type::Int, Type::Char)
+            | (_, Type::String)
+    )
+}
+
+/// Get all the conversion functions which can convert a value of the source
+/// type to the target type
+fn find_conversions(
+    env: &Environment,
+    source_type: &Type,
+    type_: &Type,
+) -> Vec<EnvironmentFunction> {
+    env.functions
+        .iter()
+        .filter(|function| {
+            function.name.starts_with(CONVERSION_FUNCTION_PREFIX)
+                && types_equal(env, &function.return_type, type_)
+                && function
+                    .pre_param_type
+                    .as_ref()
+                    .is_some_and(|p| can_assign(env, p, source_type))
+        })
+        .cloned()
+        .collect()
+}
+
+pub fn validate_cast(
+    env: &mut Environment,
+    expression: &mut Expression,
+) -> Result<Type, AnalyzerError> {
+    let line = expression.line;
+
+    let (value, type_, value_type) = match &mut expression.data {
+        ExpressionData::Cast {
+            value,
+            type_,
+            value_type,
+        } => {
+            // the compiler needs the actual type instead of an alias
+            *type_ = env.resolve_type(type_);
+
+            (value, type_.clone(), value_type)
+        }
+
+        _ => unreachable!(),
+    };
+
+    let source_type = validate_and_get_type(value, env)?;
+
+    // values of an interface are stored like values in an any, so they can
+    // be cast back to their actual type the same way
+    let from_interface = matches!(source_type, Type::Interface(_))
+        && !matches!(type_, Type::Interface(_));
+
+    // putting a value in an any and getting it back out, which is checked at
+    // runtime
+    if type_ == Type::Any || source_type == Type::Any || from_interface {
+        *value_type = Some(source_type);
+        return Ok(type_);
+    }
+
+    // casts which don't change the value, like the ones to a nullable type or
+    // from an enum to one of its variants, are replaced by the value itself
+    if can_assign(env, &type_, &source_type) {
+        let mut inner_value = std::mem::replace(
+            value.as_mut(),
+            Expression {
+                data: ExpressionData::Null,
+                line,
+            },
+        );
+        cast_value(&type_, &mut inner_value, &source_type);
+
+        *expression = inner_value;
+        return Ok(type_);
+    }
+
+    if builtin_cast_exists(env, &source_type, &type_) {
+        *value_type = Some(source_type);
+        return Ok(type_);
+    }
+
+    // otherwise the cast calls a conversion function
+    let conversions = find_conversions(env, &source_type, &type_);
+
+    let conversion = match conversions.as_slice() {
+        [conversion] => conversion.clone(),
+
+        [] => {
+            return analyzer_error!(
+                line,
+                "Cannot cast value of type {:?} to type {:?}, there's no \
+                 conversion function for it",
+                source_type,
+                type_
+            )
+        }
+
+        _ => {
+            return analyzer_error!(
+                line,
+                "Cast of value of type {:?} to type {:?} is ambiguous, it \
+                 could use conversion functions from types {:?}",
+                source_type,
+                type_,
+                conversions
+                    .iter()
+                    .map(|c| c.pre_param_type.clone().unwrap())
+                    .collect::<Vec<_>>()
+            )
+        }
+    };
+
+    let pre_param_type = conversion.pre_param_type.unwrap();
+
+    let mut pre_argument = std::mem::replace(
+        value.as_mut(),
+        Expression {
+            data: ExpressionData::Null,
+            line,
+        },
+    );
+    cast_value(&pre_param_type, &mut pre_argument, &source_type);
+
+    *expression = Expression {
+        data: ExpressionData::ValueFunctionCall {
+            pre_argument: Box::new(pre_argument),
+            function_name: conversion.name,
+            arguments: vec![],
+            keyword_arguments: vec![],
+            pre_argument_type: Some(pre_param_type),
+        },
+        line,
+    };
+
+    return Ok(conversion.return_type);
+}
@@ -0,0 +1,38 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    analyzer::{analyzer_error::AnalyzerError, env::Environment},
+    analyzer_error,
+    parser::r#type::Type,
+};
+
+pub fn validate_enum_definition(
+    env: &mut Environment,
+    line: usize,
+    name: &String,
+    variants: &mut BTreeMap<String, BTreeMap<String, Type>>,
+) -> Result<Type, AnalyzerError> {
+    // structs, enums, type aliases and interfaces are all used as user defined
+    // types, so they can't share a name
+    if env.get_struct(name).is_some()
+        || env.get_enum(name).is_some()
+        || env.get_alias(name).is_some()
+        || env.get_interface(name).is_some()
+    {
+        return analyzer_error!(
+            line,
+            "A type with the name {} already exists",
+            name
+        );
+    }
+
+    for fields in variants.values_mut() {
+        for field_type in fields.values_mut() {
+            *field_type = env.resolve_type(field_type);
+        }
+    }
+
+    env.add_enum(name.clone(), variants.clone());
+
+    return Ok(Type::Null);
+}
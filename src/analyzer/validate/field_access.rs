@@ -27,6 +27,22 @@ pub fn validate_field_access(
             }
         },
 
+        // the fields of a variant can only be accessed once the value is
+        // known to be that variant
+        Type::EnumVariant { name, variant } => {
+            match env.get_enum_variant(&name, &variant) {
+                Some(fields) => fields,
+                None => {
+                    return analyzer_error!(
+                        expression.line,
+                        "Enum variant {}:{} does not exist",
+                        name,
+                        variant
+                    )
+                }
+            }
+        }
+
         _ => {
             return analyzer_error!(
                 expression.line,
@@ -1,20 +1,219 @@
 use crate::{
     analyzer::{
-        analyzer_error::AnalyzerError, env::Environment, util::types_equal,
+        analyzer_error::AnalyzerError,
+        env::{Environment, EnvironmentFunction},
+        util::{can_assign, cast_value},
     },
     analyzer_error,
-    parser::{expression::Expression, r#type::Type},
+    parser::{
+        expression::{Expression, ExpressionData},
+        r#type::Type,
+    },
 };
 
-use super::validate_and_get_type;
+use super::{validate_and_get_type, validate_and_get_type_expecting};
+
+/// Validate an argument and check that it can be passed where a value of the
+/// given type is expected
+fn validate_argument(
+    env: &mut Environment,
+    argument: &mut Expression,
+    param_type: &Type,
+) -> Result<(), AnalyzerError> {
+    let argument_type =
+        validate_and_get_type_expecting(argument, env, param_type)?;
+
+    if !can_assign(env, param_type, &argument_type) {
+        return analyzer_error!(
+            argument.line,
+            "Expected value of type {:?}, got value of type {:?} instead",
+            param_type,
+            argument_type
+        );
+    }
+
+    cast_value(param_type, argument, &argument_type);
+
+    Ok(())
+}
+
+/// A keyword argument like n: abs(x) might have been meant as the value
+/// function call n:abs(x), which has to be put in parentheses to be passed as
+/// an argument
+fn value_function_call_hint(env: &Environment, name: &String) -> String {
+    match env.get_variable(name) {
+        Some(_) => format!(
+            " (to pass a value function call on {} as an argument, put it in parentheses)",
+            name
+        ),
+        None => String::new(),
+    }
+}
+
+/// Turn the positional and keyword arguments of a call to a function which is
+/// known by its name into one argument for each of its parameters, in order.
+/// Parameters which were left out get a DefaultArgument and the remaining
+/// positional arguments are put in an array for the rest parameter
+pub fn resolve_arguments(
+    env: &mut Environment,
+    line: usize,
+    function: &EnvironmentFunction,
+    arguments: &mut Vec<Expression>,
+    keyword_arguments: &mut Vec<(String, Expression)>,
+) -> Result<(), AnalyzerError> {
+    let param_count = function.param_types.len();
+    let fixed_count = if function.rest_param {
+        param_count - 1
+    } else {
+        param_count
+    };
+
+    if arguments.len() > fixed_count && !function.rest_param {
+        return analyzer_error!(
+            line,
+            "Expected {} arguments, {} provided",
+            param_count,
+            arguments.len() + keyword_arguments.len()
+        );
+    }
+
+    let mut resolved: Vec<Option<Expression>> = vec![None; fixed_count];
+    let mut rest_elements: Vec<Expression> = vec![];
+
+    for (i, argument) in std::mem::take(arguments).into_iter().enumerate() {
+        if i < fixed_count {
+            resolved[i] = Some(argument);
+        } else {
+            rest_elements.push(argument);
+        }
+    }
+
+    for (name, argument) in std::mem::take(keyword_arguments) {
+        let index = match function.param_names[..fixed_count]
+            .iter()
+            .position(|param_name| *param_name == name)
+        {
+            Some(index) => index,
+            None => {
+                return analyzer_error!(
+                    argument.line,
+                    "Function {} has no parameter called {}{}",
+                    function.name,
+                    name,
+                    value_function_call_hint(env, &name)
+                )
+            }
+        };
+
+        if resolved[index].is_some() {
+            return analyzer_error!(
+                argument.line,
+                "Parameter {} is given more than once",
+                name
+            );
+        }
+
+        resolved[index] = Some(argument);
+    }
+
+    for (i, argument) in resolved.into_iter().enumerate() {
+        let mut argument = match argument {
+            Some(argument) => argument,
+
+            None if function.default_params[i] => Expression {
+                data: ExpressionData::DefaultArgument,
+                line,
+            },
+
+            None => match function.param_names.get(i) {
+                Some(name) => {
+                    return analyzer_error!(
+                        line,
+                        "Missing argument for parameter {}",
+                        name
+                    )
+                }
+                None => {
+                    return analyzer_error!(
+                        line,
+                        "Expected {} arguments, {} provided",
+                        param_count,
+                        i
+                    )
+                }
+            },
+        };
+
+        if argument.data != ExpressionData::DefaultArgument {
+            validate_argument(env, &mut argument, &function.param_types[i])?;
+        }
+
+        arguments.push(argument);
+    }
+
+    if function.rest_param {
+        let element_type = match &function.param_types[fixed_count] {
+            Type::Array(element_type) => element_type,
+            _ => unreachable!(),
+        };
+
+        for element in rest_elements.iter_mut() {
+            validate_argument(env, element, element_type)?;
+        }
+
+        arguments.push(Expression {
+            data: ExpressionData::MakeArray {
+                elements: rest_elements,
+            },
+            line,
+        });
+    }
+
+    Ok(())
+}
 
 pub fn validate_function_call(
     env: &mut Environment,
+    line: usize,
     function: &mut Expression,
     arguments: &mut Vec<Expression>,
+    keyword_arguments: &mut Vec<(String, Expression)>,
 ) -> Result<Type, AnalyzerError> {
     let function_type = validate_and_get_type(function, env)?;
 
+    // only functions called by their name have default, keyword and rest
+    // parameters, since a function's type doesn't include them
+    let named_function = match &function.data {
+        ExpressionData::Identifier(name) => env.get_function(name, &None),
+
+        ExpressionData::TypeFunction {
+            type_name,
+            function_name,
+        } => env.get_type_function(type_name, function_name),
+
+        _ => None,
+    };
+
+    if let Some(named_function) = named_function {
+        resolve_arguments(
+            env,
+            line,
+            &named_function,
+            arguments,
+            keyword_arguments,
+        )?;
+
+        return Ok(named_function.return_type);
+    }
+
+    if let Some((name, _)) = keyword_arguments.first() {
+        return analyzer_error!(
+            line,
+            "Keyword arguments can only be used when calling a function by its name{}",
+            value_function_call_hint(env, name)
+        );
+    }
+
     let return_type: Type;
     let param_types: Vec<Type>;
 
@@ -22,6 +221,7 @@ pub fn validate_function_call(
         Type::Function {
             return_type: a,
             param_types: b,
+            ..
         } => {
             return_type = *a.clone();
             param_types = b;
@@ -46,17 +246,7 @@ pub fn validate_function_call(
     }
 
     for i in 0..param_types.len() {
-        let argument_type = validate_and_get_type(&mut arguments[i], env)?;
-        let param_type = param_types[i].clone();
-
-        if !types_equal(env, &argument_type, &param_type) {
-            return analyzer_error!(
-                arguments[i].line,
-                "Expected value of type {:?}, got value of type {:?} instead",
-                param_type,
-                argument_type
-            );
-        }
+        validate_argument(env, &mut arguments[i], &param_types[i])?;
     }
 
     return Ok(return_type);
@@ -1,28 +1,73 @@
 use crate::{
     analyzer::{
-        analyzer_error::AnalyzerError, env::Environment, util::types_equal,
+        analyzer_error::AnalyzerError,
+        env::{Environment, EnvironmentFunction},
+        util::{can_assign, cast_value},
     },
     analyzer_error,
     parser::{
-        expression::{Expression, FunctionParam},
+        expression::{Expression, ExpressionData, CONVERSION_FUNCTION_PREFIX},
         r#type::Type,
     },
 };
 
-use super::validate_and_get_type;
+use super::{
+    cast::builtin_cast_exists, type_function::resolve_type_name,
+    validate_and_get_type, validate_and_get_type_expecting,
+};
 
 pub fn validate_function_definition(
     env: &mut Environment,
-    line: usize,
-    name: &Option<String>,
-    pre_parameter: &Option<FunctionParam>,
-    params: &Vec<FunctionParam>,
-    return_type: &Type,
-    body: &mut Box<Expression>,
+    definition: &mut Expression,
 ) -> Result<Type, AnalyzerError> {
+    let line = definition.line;
+
+    let (name, pre_parameter, params, return_type, body, type_name, captures) =
+        match &mut definition.data {
+            ExpressionData::FunctionDefinition {
+                name,
+                pre_parameter,
+                params,
+                return_type,
+                body,
+                type_name,
+                captures,
+            } => (
+                name,
+                pre_parameter,
+                params,
+                return_type,
+                body,
+                type_name,
+                captures,
+            ),
+
+            _ => unreachable!(),
+        };
+
+    // type aliases are replaced in the definition itself, since the compiler
+    // looks up value functions by the type of their pre-parameter
+    for param in params.iter_mut().chain(pre_parameter.iter_mut()) {
+        param.type_ = env.resolve_type(&param.type_);
+    }
+    *return_type = env.resolve_type(return_type);
+
+    if let Some(type_name) = type_name {
+        resolve_type_name(env, line, type_name)?;
+    }
+
+    // the name of a conversion function contains its return type
+    if let Some(name) = name {
+        if name.starts_with(CONVERSION_FUNCTION_PREFIX) {
+            *name = format!("{}{:?}", CONVERSION_FUNCTION_PREFIX, return_type);
+        }
+    }
+
     let mut param_types: Vec<Type> = vec![];
-    for param in params {
+    let mut var_params: Vec<bool> = vec![];
+    for param in params.iter() {
         param_types.push(param.type_.clone());
+        var_params.push(!param.constant);
     }
 
     let mut pre_param_type: Option<Type> = None;
@@ -35,7 +80,60 @@ pub fn validate_function_definition(
     if name.is_some() {
         let name = name.clone().unwrap();
 
-        if env.get_function(&name, &pre_param_type).is_some() {
+        // a conversion function would never be called if its cast already
+        // works without it
+        if name.starts_with(CONVERSION_FUNCTION_PREFIX) {
+            let pre_param_type = pre_param_type.as_ref().unwrap();
+
+            if builtin_cast_exists(env, pre_param_type, return_type) {
+                return analyzer_error!(
+                    line,
+                    "Values of type {:?} can already be cast to type {:?}",
+                    pre_param_type,
+                    return_type
+                );
+            }
+
+            if env
+                .get_function(&name, &Some(pre_param_type.clone()))
+                .is_some()
+            {
+                return analyzer_error!(
+                    line,
+                    "Conversion from type {:?} to type {:?} already exists",
+                    pre_param_type,
+                    return_type
+                );
+            }
+        }
+
+        let function = EnvironmentFunction {
+            name: name.clone(),
+            pre_param_type: pre_param_type.clone(),
+            pre_param_var: pre_parameter.as_ref().is_some_and(|p| !p.constant),
+            param_types: param_types.clone(),
+            var_params: var_params.clone(),
+            return_type: return_type.clone(),
+            param_names: params.iter().map(|p| p.name.clone()).collect(),
+            default_params: params
+                .iter()
+                .map(|p| p.default.is_some())
+                .collect(),
+            rest_param: params.last().is_some_and(|p| p.rest),
+        };
+
+        if let Some(type_name) = type_name {
+            if env.get_type_function(type_name, &name).is_some() {
+                return analyzer_error!(
+                    line,
+                    "Function {}#{} already exists",
+                    type_name,
+                    name
+                );
+            }
+
+            env.add_type_function(type_name.clone(), function);
+        } else if env.get_function(&name, &pre_param_type).is_some() {
             return if pre_param_type.is_some() {
                 analyzer_error!(
                     line,
@@ -46,18 +144,13 @@ pub fn validate_function_definition(
             } else {
                 analyzer_error!(line, "Function {} already exists", name)
             };
+        } else {
+            env.add_function(function);
         }
-
-        env.add_function(
-            name,
-            pre_param_type,
-            param_types.clone(),
-            return_type.clone(),
-        );
     }
 
     // validate inner body of function
-    let mut body_env = Environment::from_parent(&env);
+    let mut body_env = Environment::for_function_body(&env);
 
     // add function params to its environment before validating its body
     if let Some(pre_parameter) = pre_parameter {
@@ -67,7 +160,29 @@ pub fn validate_function_definition(
             pre_parameter.constant,
         );
     }
-    for param in params {
+    for param in params.iter_mut() {
+        // a default value is evaluated inside of the function when the
+        // argument is left out, so it can use the parameters before it
+        if let Some(default) = &mut param.default {
+            let default_type = validate_and_get_type_expecting(
+                default,
+                &mut body_env,
+                &param.type_,
+            )?;
+
+            if !can_assign(&body_env, &param.type_, &default_type) {
+                return analyzer_error!(
+                    default.line,
+                    "Default value of parameter {} should have type {:?}, got value of type {:?}",
+                    param.name,
+                    param.type_,
+                    default_type
+                );
+            }
+
+            cast_value(&param.type_, default, &default_type);
+        }
+
         body_env.add_variable(
             param.name.clone(),
             param.type_.clone(),
@@ -76,7 +191,9 @@ pub fn validate_function_definition(
     }
     let body_type = validate_and_get_type(body, &mut body_env)?;
 
-    if !types_equal(env, &body_type, return_type) {
+    *captures = body_env.captures.take().unwrap().into_inner();
+
+    if !can_assign(env, return_type, &body_type) {
         return analyzer_error!(
             body.line,
             "Function should return {:?}, but it returns {:?}",
@@ -85,8 +202,11 @@ pub fn validate_function_definition(
         );
     }
 
+    cast_value(return_type, body, &body_type);
+
     return Ok(Type::Function {
         param_types,
+        var_params,
         return_type: Box::new(return_type.clone()),
     });
 }
@@ -10,11 +10,8 @@ pub fn validate_identifier(
     identifier: &String,
 ) -> Result<Type, AnalyzerError> {
     if let Some(function) = env.get_function(&identifier, &None) {
-        return Ok(Type::Function {
-            param_types: function.param_types,
-            return_type: Box::new(function.return_type),
-        });
-    } else if let Some(variable) = env.get_variable(&identifier) {
+        return Ok(function.type_());
+    } else if let Some(variable) = env.use_variable(&identifier) {
         return Ok(variable.type_);
     } else {
         return analyzer_error!(line, "Unknown variable: {}", identifier);
@@ -1,13 +1,57 @@
 use crate::{
     analyzer::{
-        analyzer_error::AnalyzerError, env::Environment, util::types_equal,
+        analyzer_error::AnalyzerError,
+        env::Environment,
+        util::{can_assign, types_equal},
+    },
+    analyzer_error, expression_pat,
+    lexer::token::TokenData,
+    parser::{
+        expression::{Expression, ExpressionData},
+        r#type::Type,
     },
-    analyzer_error,
-    parser::{expression::Expression, r#type::Type},
 };
 
 use super::validate_and_get_type;
 
+fn make_nullable(type_: Type) -> Type {
+    match type_ {
+        Type::Nullable(_) | Type::Null => type_,
+        _ => Type::Nullable(Box::new(type_)),
+    }
+}
+
+/// If the condition is `a?` where a is a nullable val, return a's name and the
+/// type it has once it's known not to be null. A var could be set to null
+/// inside of the branch (directly, by a function it's passed to as a var
+/// parameter or by a closure which captured it), so it's never narrowed
+fn get_narrowed_variable(
+    env: &Environment,
+    condition: &Expression,
+) -> Option<(String, Type)> {
+    if let expression_pat!(ExpressionData::Postfix {
+        value,
+        operator: TokenData::QuestionMark,
+    }) = condition
+    {
+        if let expression_pat!(ExpressionData::Identifier(name)) =
+            value.as_ref()
+        {
+            let variable = env.get_variable(name)?;
+
+            match variable.type_ {
+                Type::Nullable(inner) if variable.constant => {
+                    return Some((name.clone(), *inner));
+                }
+
+                _ => {}
+            }
+        }
+    }
+
+    None
+}
+
 pub fn validate_if_condition(
     env: &mut Environment,
     line: usize,
@@ -23,21 +67,39 @@ pub fn validate_if_condition(
         );
     }
 
-    if else_branch.is_none() {
-        todo!("Return a nullable type here");
+    // in `if a? { ... }`, a isn't null inside of the true branch
+    let mut true_env = Environment::from_parent(env);
+    if let Some((name, type_)) = get_narrowed_variable(env, condition) {
+        true_env.add_variable(name, type_, true);
     }
 
-    let else_branch = else_branch.as_mut().unwrap();
+    let true_type = validate_and_get_type(true_branch, &mut true_env)?;
 
-    let true_type = validate_and_get_type(true_branch, env)?;
+    // an if without an else evaluates to null when the condition is false
+    let else_branch = match else_branch {
+        Some(else_branch) => else_branch,
+
+        None => return Ok(make_nullable(true_type)),
+    };
     let else_type = validate_and_get_type(else_branch, env)?;
 
-    if !types_equal(env, &true_type, &else_type) {
-        return analyzer_error!(
-            line,
-            "The true and else branch must have the same type"
-        );
+    if types_equal(env, &true_type, &else_type) {
+        return Ok(true_type);
+    }
+
+    // if one of the branches is null or nullable, the if is nullable
+    if matches!(else_type, Type::Null) {
+        return Ok(make_nullable(true_type));
+    } else if matches!(true_type, Type::Null) {
+        return Ok(make_nullable(else_type));
+    } else if can_assign(env, &true_type, &else_type) {
+        return Ok(true_type);
+    } else if can_assign(env, &else_type, &true_type) {
+        return Ok(else_type);
     }
 
-    return Ok(true_type);
+    return analyzer_error!(
+        line,
+        "The true and else branch must have the same type"
+    );
 }
@@ -0,0 +1,89 @@
+use crate::{
+    analyzer::{
+        analyzer_error::AnalyzerError, env::Environment, util::types_equal,
+    },
+    analyzer_error,
+    parser::{
+        expression::{Expression, ExpressionData},
+        r#type::Type,
+    },
+};
+
+use super::validate_and_get_type;
+
+/// Validate an index and return the type of the element it gets out of a value
+/// of the given type. Looking up a key in a map gives a nullable value, since
+/// the key might not be there
+pub fn validate_index(
+    env: &mut Environment,
+    type_: &Type,
+    index: &mut Expression,
+) -> Result<Type, AnalyzerError> {
+    let (index_type, element_type) = match type_ {
+        Type::Array(element_type) => (Type::Int, *element_type.clone()),
+        Type::String => (Type::Int, Type::Char),
+
+        Type::Map {
+            key_type,
+            value_type,
+        } => (
+            *key_type.clone(),
+            Type::Nullable(Box::new(*value_type.clone())),
+        ),
+
+        // the position has to be known in advance to know the element type
+        Type::Tuple(element_types) => {
+            return match index.data {
+                ExpressionData::Int(i) if i >= 0 => {
+                    match element_types.get(i as usize) {
+                        Some(element_type) => Ok(element_type.clone()),
+
+                        None => analyzer_error!(
+                            index.line,
+                            "Index {} is out of bounds for a tuple with {} \
+                             elements",
+                            i,
+                            element_types.len()
+                        ),
+                    }
+                }
+
+                _ => analyzer_error!(
+                    index.line,
+                    "Tuples can only be indexed with a non-negative int \
+                     literal"
+                ),
+            };
+        }
+
+        _ => {
+            return analyzer_error!(
+                index.line,
+                "Cannot index a value of type {:?}",
+                type_
+            )
+        }
+    };
+
+    let type_ = validate_and_get_type(index, env)?;
+    if !types_equal(env, &type_, &index_type) {
+        return analyzer_error!(
+            index.line,
+            "Expected index of type {:?}, got value of type {:?} instead",
+            index_type,
+            type_
+        );
+    }
+
+    return Ok(element_type);
+}
+
+pub fn validate_index_access(
+    env: &mut Environment,
+    expression: &mut Expression,
+    index: &mut Expression,
+) -> Result<Type, AnalyzerError> {
+    let expression_type = validate_and_get_type(expression, env)?;
+
+    validate_index(env, &expression_type, index)
+}
@@ -1,6 +1,6 @@
 use crate::{
     analyzer::{
-        analyzer_error::AnalyzerError, env::Environment, util::types_equal,
+        analyzer_error::AnalyzerError, env::Environment, util::can_assign,
     },
     analyzer_error,
     lexer::token::TokenData,
@@ -73,11 +73,17 @@ pub fn validate_infix(
                 )
             }
         }
-    } else if *operator == TokenData::EqualsEquals {
+    } else if *operator == TokenData::EqualsEquals
+        || *operator == TokenData::ExclamationEquals
+    {
         let left_type = validate_and_get_type(left, env)?;
         let right_type = validate_and_get_type(right, env)?;
 
-        if types_equal(env, &left_type, &right_type) {
+        // nullable values can also be compared with null and with values of
+        // their inner type
+        if can_assign(env, &left_type, &right_type)
+            || can_assign(env, &right_type, &left_type)
+        {
             return Ok(Type::Bool);
         } else {
             return analyzer_error!(
@@ -86,6 +92,23 @@ pub fn validate_infix(
                 operator
             );
         }
+    } else if *operator == TokenData::And || *operator == TokenData::Or {
+        let left_type = validate_and_get_type(left, env)?;
+        let right_type = validate_and_get_type(right, env)?;
+
+        match (left_type, right_type) {
+            (Type::Bool, Type::Bool) => {
+                return Ok(Type::Bool);
+            }
+
+            _ => {
+                return analyzer_error!(
+                    left.line,
+                    "Operator {:?} only works on booleans",
+                    operator
+                )
+            }
+        }
     } else {
         unreachable!();
     }
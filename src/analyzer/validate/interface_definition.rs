@@ -0,0 +1,109 @@
+use crate::{
+    analyzer::{analyzer_error::AnalyzerError, env::Environment},
+    analyzer_error,
+    parser::{expression::InterfaceFunction, r#type::Type},
+};
+
+/// Check if this appears anywhere inside of a type
+fn mentions_this(type_: &Type) -> bool {
+    match type_ {
+        Type::This => true,
+
+        Type::Array(inner) | Type::Nullable(inner) => mentions_this(inner),
+
+        Type::Tuple(element_types) => element_types.iter().any(mentions_this),
+
+        Type::Map {
+            key_type,
+            value_type,
+        } => mentions_this(key_type) || mentions_this(value_type),
+
+        Type::Function {
+            param_types,
+            return_type,
+            ..
+        } => {
+            param_types.iter().any(mentions_this) || mentions_this(return_type)
+        }
+
+        Type::Struct { fields } => fields.values().any(mentions_this),
+
+        _ => false,
+    }
+}
+
+pub fn validate_interface_definition(
+    env: &mut Environment,
+    line: usize,
+    name: &String,
+    functions: &mut Vec<InterfaceFunction>,
+) -> Result<Type, AnalyzerError> {
+    if env.get_struct(name).is_some()
+        || env.get_enum(name).is_some()
+        || env.get_alias(name).is_some()
+        || env.get_interface(name).is_some()
+    {
+        return analyzer_error!(
+            line,
+            "A type with the name {} already exists",
+            name
+        );
+    }
+
+    for i in 0..functions.len() {
+        let function_name = &functions[i].name;
+
+        if functions[..i].iter().any(|f| f.name == *function_name) {
+            return analyzer_error!(
+                line,
+                "Interface {} has more than one function called {}",
+                name,
+                function_name
+            );
+        }
+    }
+
+    for function in functions.iter_mut() {
+        for param_type in function.param_types.iter_mut() {
+            *param_type = env.resolve_type(param_type);
+        }
+        function.return_type = env.resolve_type(&function.return_type);
+
+        // only values which are this themselves are checked to have the same
+        // type as the pre-argument when an interface function is called, so
+        // this can't be inside of another type
+        if function
+            .param_types
+            .iter()
+            .chain(std::iter::once(&function.return_type))
+            .any(|type_| *type_ != Type::This && mentions_this(type_))
+        {
+            return analyzer_error!(
+                line,
+                "Function {} of interface {} can only use this as the type of a parameter or its return type, not inside of another type",
+                function.name,
+                name
+            );
+        }
+
+        // the value of a var parameter would be updated with the value inside
+        // of the interface, which isn't the interface's type
+        if function
+            .param_types
+            .iter()
+            .zip(function.var_params.iter())
+            .any(|(type_, var)| *type_ == Type::This && *var)
+        {
+            return analyzer_error!(
+                line,
+                "A parameter of function {} of interface {} with the type this cannot be var",
+                function.name,
+                name
+            );
+        }
+    }
+
+    env.add_interface(name.clone(), functions.clone());
+
+    return Ok(Type::Null);
+}
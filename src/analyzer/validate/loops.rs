@@ -0,0 +1,125 @@
+use crate::{
+    analyzer::{
+        analyzer_error::AnalyzerError, env::Environment, util::types_equal,
+    },
+    analyzer_error,
+    parser::{expression::Expression, r#type::Type},
+};
+
+use super::validate_and_get_type;
+
+pub fn validate_while(
+    env: &mut Environment,
+    condition: &mut Expression,
+    body: &mut Expression,
+) -> Result<Type, AnalyzerError> {
+    let condition_type = validate_and_get_type(condition, env)?;
+    if !matches!(condition_type, Type::Bool) {
+        return analyzer_error!(
+            condition.line,
+            "The condition needs to be a boolean"
+        );
+    }
+
+    let mut body_env = Environment::for_loop_body(env, false);
+    validate_and_get_type(body, &mut body_env)?;
+
+    return Ok(Type::Null);
+}
+
+pub fn validate_loop(
+    env: &mut Environment,
+    body: &mut Expression,
+) -> Result<Type, AnalyzerError> {
+    let mut body_env = Environment::for_loop_body(env, true);
+    validate_and_get_type(body, &mut body_env)?;
+
+    // the loop evaluates to the value it's broken with
+    let break_type = body_env.loop_context.unwrap().break_type.into_inner();
+
+    return Ok(break_type.unwrap_or(Type::Null));
+}
+
+pub fn validate_for(
+    env: &mut Environment,
+    variable: &String,
+    iterable: &mut Expression,
+    body: &mut Expression,
+) -> Result<Type, AnalyzerError> {
+    let iterable_type = validate_and_get_type(iterable, env)?;
+
+    let element_type = match iterable_type {
+        Type::String => Type::Char,
+        Type::Array(element_type) => *element_type,
+
+        _ => {
+            return analyzer_error!(
+                iterable.line,
+                "Cannot iterate over a value of type {:?}",
+                iterable_type
+            )
+        }
+    };
+
+    let mut body_env = Environment::for_loop_body(env, false);
+    body_env.add_variable(variable.clone(), element_type, true);
+
+    validate_and_get_type(body, &mut body_env)?;
+
+    return Ok(Type::Null);
+}
+
+pub fn validate_break(
+    env: &mut Environment,
+    line: usize,
+    value: &mut Option<Box<Expression>>,
+) -> Result<Type, AnalyzerError> {
+    let value_type = match value {
+        Some(value) => Some(validate_and_get_type(value, env)?),
+        None => None,
+    };
+
+    let loop_context = match env.get_loop() {
+        Some(loop_context) => loop_context,
+        None => return analyzer_error!(line, "Cannot break outside of a loop"),
+    };
+
+    if value_type.is_some() && !loop_context.breaks_with_value {
+        return analyzer_error!(
+            line,
+            "Only loop expressions can be broken with a value"
+        );
+    }
+
+    let value_type = value_type.unwrap_or(Type::Null);
+    let mut break_type = loop_context.break_type.borrow_mut();
+
+    match &*break_type {
+        Some(break_type) if !types_equal(env, break_type, &value_type) => {
+            return analyzer_error!(
+                line,
+                "Expected loop to be broken with a value of type {:?}, got \
+                 value of type {:?} instead",
+                break_type,
+                value_type
+            );
+        }
+
+        Some(_) => {}
+
+        None => *break_type = Some(value_type),
+    }
+
+    return Ok(Type::Null);
+}
+
+pub fn validate_continue(
+    env: &mut Environment,
+    line: usize,
+) -> Result<Type, AnalyzerError> {
+    if env.get_loop().is_none() {
+        return analyzer_error!(line, "Cannot continue outside of a loop");
+    }
+
+    return Ok(Type::Null);
+}
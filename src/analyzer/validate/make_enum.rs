@@ -0,0 +1,75 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    analyzer::{
+        analyzer_error::AnalyzerError, env::Environment, util::types_equal,
+    },
+    analyzer_error,
+    parser::{expression::Expression, r#type::Type},
+};
+
+use super::{make_struct::validate_fields, validate_and_get_type};
+
+pub fn validate_make_enum(
+    env: &mut Environment,
+    line: usize,
+    name: &String,
+    variant: &String,
+    fields: &mut HashMap<String, Expression>,
+) -> Result<Type, AnalyzerError> {
+    if env.get_enum(name).is_none() {
+        return analyzer_error!(line, "No enum with the name {} found", name);
+    }
+
+    let defined_fields = match env.get_enum_variant(name, variant) {
+        Some(fields) => fields,
+        None => {
+            return analyzer_error!(
+                line,
+                "Enum {} has no variant called {}",
+                name,
+                variant
+            )
+        }
+    };
+
+    validate_fields(env, line, defined_fields, &BTreeMap::new(), fields)?;
+
+    // the value has the type of the whole enum, so it can be stored together
+    // with the other variants
+    return Ok(env.resolve_type(&Type::UserDefined(name.clone())));
+}
+
+pub fn validate_variant_cast(
+    env: &mut Environment,
+    line: usize,
+    value: &mut Expression,
+    name: &String,
+    variant: &String,
+) -> Result<Type, AnalyzerError> {
+    let value_type = validate_and_get_type(value, env)?;
+
+    if env.get_enum_variant(name, variant).is_none() {
+        return analyzer_error!(
+            line,
+            "Enum variant {}:{} does not exist",
+            name,
+            variant
+        );
+    }
+
+    if !types_equal(env, &value_type, &Type::UserDefined(name.clone())) {
+        return analyzer_error!(
+            line,
+            "Cannot cast value of type {:?} to {}:{}",
+            value_type,
+            name,
+            variant
+        );
+    }
+
+    return Ok(Type::EnumVariant {
+        name: name.clone(),
+        variant: variant.clone(),
+    });
+}
@@ -2,26 +2,84 @@ use std::collections::{BTreeMap, HashMap};
 
 use crate::{
     analyzer::{
-        analyzer_error::AnalyzerError, env::Environment, util::types_equal,
+        analyzer_error::AnalyzerError,
+        env::Environment,
+        util::{can_assign, cast_value},
     },
     analyzer_error,
     parser::{expression::Expression, r#type::Type},
 };
 
-use super::validate_and_get_type;
+use super::{validate_and_get_type, validate_and_get_type_expecting};
+
+/// Check that the given fields match the defined ones, fields with default
+/// values can be left out. This is shared with enum variants
+pub fn validate_fields(
+    env: &mut Environment,
+    line: usize,
+    defined_fields: BTreeMap<String, Type>,
+    defaults: &BTreeMap<String, Expression>,
+    fields: &mut HashMap<String, Expression>,
+) -> Result<(), AnalyzerError> {
+    for field_name in fields.keys() {
+        if !defined_fields.contains_key(field_name) {
+            return analyzer_error!(
+                line,
+                "Field {} does not exist",
+                field_name
+            );
+        }
+    }
+
+    for (defined_field_name, defined_field_type) in defined_fields {
+        match fields.get_mut(&defined_field_name) {
+            Some(field_value) => {
+                let field_type = validate_and_get_type_expecting(
+                    field_value,
+                    env,
+                    &defined_field_type,
+                )?;
+
+                if !can_assign(env, &defined_field_type, &field_type) {
+                    return analyzer_error!(
+                        line,
+                        "Field {} should have value of type {:?}, got value of type {:?}",
+                        defined_field_name,
+                        defined_field_type,
+                        field_type
+                    );
+                }
+
+                cast_value(&defined_field_type, field_value, &field_type);
+            }
+
+            None if defaults.contains_key(&defined_field_name) => {}
+
+            None => {
+                return analyzer_error!(
+                    line,
+                    "Field {} is missing",
+                    defined_field_name
+                )
+            }
+        }
+    }
+
+    Ok(())
+}
 
 pub fn validate_make_struct(
     env: &mut Environment,
     line: usize,
-    name: &Option<String>,
+    name: &mut Option<String>,
     fields: &mut HashMap<String, Expression>,
 ) -> Result<Type, AnalyzerError> {
     // check if fields match the predefined struct if a name is given
     if name.is_some() {
-        let name = name.clone().unwrap();
+        let name = name.as_mut().unwrap();
 
-        let defined_fields = match env.get_struct(&name) {
-            Some(s) => s.fields,
+        let struct_ = match env.get_struct(name) {
+            Some(s) => s,
             None => {
                 return analyzer_error!(
                     line,
@@ -31,42 +89,14 @@ pub fn validate_make_struct(
             }
         };
 
-        if defined_fields.len() != fields.len() {
-            return analyzer_error!(
-                line,
-                "Expected {} fields, {} given",
-                defined_fields.len(),
-                fields.len()
-            );
-        }
+        validate_fields(env, line, struct_.fields, &struct_.defaults, fields)?;
 
-        for (defined_field_name, defined_field_type) in defined_fields {
-            match fields.get_mut(&defined_field_name) {
-                Some(field_value) => {
-                    let field_type = validate_and_get_type(field_value, env)?;
-
-                    if !types_equal(env, &field_type, &defined_field_type) {
-                        return analyzer_error!(
-                            line,
-                            "Field {} should have value of type {:?}, got value of type {:?}",
-                            defined_field_name,
-                            defined_field_type,
-                            field_type
-                        );
-                    }
-                }
-
-                None => {
-                    return analyzer_error!(
-                        line,
-                        "Field {} is missing",
-                        defined_field_name
-                    )
-                }
-            }
-        }
+        // the name can be an alias, the compiler needs the struct's actual
+        // name to find its default values
+        let type_ = env.resolve_type(&Type::UserDefined(name.clone()));
+        *name = struct_.name;
 
-        return Ok(Type::UserDefined(name));
+        return Ok(type_);
     } else {
         // if there's no name, just validate all the fields
         let mut field_types: HashMap<String, Type> = HashMap::new();
@@ -0,0 +1,120 @@
+use crate::{
+    analyzer::{
+        analyzer_error::AnalyzerError,
+        env::Environment,
+        util::{can_assign, cast_value, types_equal},
+    },
+    analyzer_error,
+    parser::{expression::Expression, r#type::Type},
+};
+
+use super::{validate_and_get_type, validate_and_get_type_expecting};
+
+/// Only these types can be hashed by the VM
+pub fn is_map_key_type(type_: &Type) -> bool {
+    matches!(type_, Type::String | Type::Int | Type::Char | Type::Bool)
+}
+
+pub fn validate_make_map(
+    env: &mut Environment,
+    line: usize,
+    entries: &mut Vec<(Expression, Expression)>,
+) -> Result<Type, AnalyzerError> {
+    // the key and value types are taken from the first entry, so there's
+    // nothing to take them from in an empty map (unless its type is known from
+    // where it's used, see validate_and_get_type_expecting)
+    if entries.is_empty() {
+        return analyzer_error!(
+            line,
+            "Cannot determine the type of an empty map"
+        );
+    }
+
+    let key_type = validate_and_get_type(&mut entries[0].0, env)?;
+    let value_type = validate_and_get_type(&mut entries[0].1, env)?;
+
+    if !is_map_key_type(&key_type) {
+        return analyzer_error!(
+            line,
+            "Values of type {:?} cannot be used as map keys",
+            key_type
+        );
+    }
+
+    for (key, value) in entries.iter_mut().skip(1) {
+        let type_ = validate_and_get_type(key, env)?;
+        if !types_equal(env, &key_type, &type_) {
+            return analyzer_error!(
+                key.line,
+                "Expected map key of type {:?}, got value of type {:?} instead",
+                key_type,
+                type_
+            );
+        }
+
+        let type_ = validate_and_get_type(value, env)?;
+        if !types_equal(env, &value_type, &type_) {
+            return analyzer_error!(
+                value.line,
+                "Expected map value of type {:?}, got value of type {:?} \
+                 instead",
+                value_type,
+                type_
+            );
+        }
+    }
+
+    return Ok(Type::Map {
+        key_type: Box::new(key_type),
+        value_type: Box::new(value_type),
+    });
+}
+
+/// Validate a map whose type is known from where it's used. Its entries are
+/// checked against the expected key and value types instead of the first
+/// entry's, so that map { "a": 1, "b": false } can be a map[string, any]
+pub fn validate_make_map_expecting(
+    env: &mut Environment,
+    line: usize,
+    entries: &mut Vec<(Expression, Expression)>,
+    key_type: &Type,
+    value_type: &Type,
+) -> Result<Type, AnalyzerError> {
+    if !is_map_key_type(key_type) {
+        return analyzer_error!(
+            line,
+            "Values of type {:?} cannot be used as map keys",
+            key_type
+        );
+    }
+
+    for (key, value) in entries.iter_mut() {
+        let type_ = validate_and_get_type(key, env)?;
+        if !types_equal(env, key_type, &type_) {
+            return analyzer_error!(
+                key.line,
+                "Expected map key of type {:?}, got value of type {:?} instead",
+                key_type,
+                type_
+            );
+        }
+
+        let type_ = validate_and_get_type_expecting(value, env, value_type)?;
+        if !can_assign(env, value_type, &type_) {
+            return analyzer_error!(
+                value.line,
+                "Expected map value of type {:?}, got value of type {:?} \
+                 instead",
+                value_type,
+                type_
+            );
+        }
+
+        cast_value(value_type, value, &type_);
+    }
+
+    return Ok(Type::Map {
+        key_type: Box::new(key_type.clone()),
+        value_type: Box::new(value_type.clone()),
+    });
+}
@@ -0,0 +1,439 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    analyzer::{
+        analyzer_error::AnalyzerError,
+        env::Environment,
+        util::{can_assign, types_equal},
+    },
+    analyzer_error,
+    parser::{
+        expression::{Expression, ExpressionData, MatchArm, Pattern},
+        r#type::Type,
+    },
+};
+
+use super::{validate_and_get_type, variable_definition::check_name_is_free};
+
+pub fn validate_match(
+    env: &mut Environment,
+    line: usize,
+    value: &mut Expression,
+    arms: &mut Vec<MatchArm>,
+) -> Result<Type, AnalyzerError> {
+    let value_type = validate_and_get_type(value, env)?;
+
+    if arms.is_empty() {
+        return analyzer_error!(line, "A match needs at least one arm");
+    }
+
+    let mut match_type: Option<Type> = None;
+    let arm_count = arms.len();
+
+    for (i, arm) in arms.iter_mut().enumerate() {
+        if arm.pattern.is_none() && i != arm_count - 1 {
+            return analyzer_error!(arm.line, "else must be the last arm");
+        }
+
+        let mut arm_env = Environment::from_parent(env);
+
+        if let Some(pattern) = &mut arm.pattern {
+            let mut bindings: Vec<(String, Type)> = vec![];
+            validate_pattern(
+                &mut arm_env,
+                arm.line,
+                pattern,
+                &value_type,
+                &mut bindings,
+            )?;
+
+            for (name, type_) in bindings {
+                arm_env.add_variable(name, type_, true);
+            }
+        }
+
+        let arm_type = validate_and_get_type(&mut arm.body, &mut arm_env)?;
+
+        match &match_type {
+            None => match_type = Some(arm_type),
+
+            Some(match_type) => {
+                if !types_equal(env, match_type, &arm_type) {
+                    return analyzer_error!(
+                        arm.line,
+                        "All arms of a match must have the same type, expected {:?} but got {:?}",
+                        match_type,
+                        arm_type
+                    );
+                }
+            }
+        }
+    }
+
+    check_exhaustive(env, line, arms, &value_type)?;
+
+    return Ok(match_type.unwrap());
+}
+
+/// Check that a pattern can match a value of the given type, and collect the
+/// variables it binds. A name which is already in bindings is compared with
+/// the earlier value instead of being bound again
+fn validate_pattern(
+    env: &mut Environment,
+    line: usize,
+    pattern: &mut Pattern,
+    type_: &Type,
+    bindings: &mut Vec<(String, Type)>,
+) -> Result<(), AnalyzerError> {
+    match pattern {
+        Pattern::Literal(literal) => {
+            let literal_type = validate_and_get_type(literal, env)?;
+
+            if !can_assign(env, type_, &literal_type) {
+                return analyzer_error!(
+                    line,
+                    "Pattern {:?} cannot match a value of type {:?}",
+                    literal.data,
+                    type_
+                );
+            }
+        }
+
+        Pattern::Binding(name) => {
+            match bindings.iter().find(|(bound, _)| bound == name) {
+                Some((_, bound_type)) => {
+                    if !types_equal(env, bound_type, type_) {
+                        return analyzer_error!(
+                            line,
+                            "Variable {} is bound to values of different types",
+                            name
+                        );
+                    }
+                }
+
+                None => {
+                    check_name_is_free(env, line, name)?;
+                    bindings.push((name.clone(), type_.clone()));
+                }
+            }
+        }
+
+        Pattern::Struct { name, fields, rest } => {
+            if let Some(name) = name {
+                if env.get_struct(name).is_none() {
+                    return analyzer_error!(
+                        line,
+                        "Struct {} does not exist",
+                        name
+                    );
+                }
+
+                if !types_equal(env, &Type::UserDefined(name.clone()), type_) {
+                    return analyzer_error!(
+                        line,
+                        "Pattern of struct {} cannot match a value of type {:?}",
+                        name,
+                        type_
+                    );
+                }
+            }
+
+            let defined_fields = match type_ {
+                Type::Struct { fields } => fields.clone(),
+
+                Type::UserDefined(name) if env.get_struct(name).is_some() => {
+                    env.get_struct(name).unwrap().fields
+                }
+
+                _ => {
+                    return analyzer_error!(
+                        line,
+                        "Struct pattern cannot match a value of type {:?}",
+                        type_
+                    )
+                }
+            };
+
+            validate_field_patterns(
+                env,
+                line,
+                fields,
+                *rest,
+                &defined_fields,
+                bindings,
+            )?;
+        }
+
+        Pattern::EnumVariant {
+            name,
+            variant,
+            fields,
+            rest,
+        } => {
+            // the enum can be given through an alias
+            if let Type::UserDefined(enum_name) =
+                env.resolve_type(&Type::UserDefined(name.clone()))
+            {
+                *name = enum_name;
+            }
+
+            let matches_type = match type_ {
+                Type::UserDefined(enum_name) => enum_name == name,
+
+                Type::EnumVariant {
+                    name: enum_name,
+                    variant: known_variant,
+                } => enum_name == name && known_variant == variant,
+
+                _ => false,
+            };
+
+            if !matches_type {
+                return analyzer_error!(
+                    line,
+                    "Pattern {}:{} cannot match a value of type {:?}",
+                    name,
+                    variant,
+                    type_
+                );
+            }
+
+            let defined_fields = match env.get_enum_variant(name, variant) {
+                Some(fields) => fields,
+                None => {
+                    return analyzer_error!(
+                        line,
+                        "Enum variant {}:{} does not exist",
+                        name,
+                        variant
+                    )
+                }
+            };
+
+            validate_field_patterns(
+                env,
+                line,
+                fields,
+                *rest,
+                &defined_fields,
+                bindings,
+            )?;
+        }
+
+        Pattern::Tuple(elements) => {
+            let element_types = match type_ {
+                Type::Tuple(element_types)
+                    if element_types.len() == elements.len() =>
+                {
+                    element_types
+                }
+
+                _ => {
+                    return analyzer_error!(
+                        line,
+                        "Tuple pattern with {} elements cannot match a value of type {:?}",
+                        elements.len(),
+                        type_
+                    )
+                }
+            };
+
+            for (element, element_type) in
+                elements.iter_mut().zip(element_types.iter())
+            {
+                validate_pattern(env, line, element, element_type, bindings)?;
+            }
+        }
+
+        Pattern::Array(groups) => {
+            let element_type = match type_ {
+                Type::Array(element_type) => element_type,
+
+                _ => {
+                    return analyzer_error!(
+                        line,
+                        "Array pattern cannot match a value of type {:?}",
+                        type_
+                    )
+                }
+            };
+
+            for element in groups.iter_mut().flatten() {
+                validate_pattern(env, line, element, element_type, bindings)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_field_patterns(
+    env: &mut Environment,
+    line: usize,
+    fields: &mut Vec<(String, Pattern)>,
+    rest: bool,
+    defined_fields: &BTreeMap<String, Type>,
+    bindings: &mut Vec<(String, Type)>,
+) -> Result<(), AnalyzerError> {
+    for (field, pattern) in fields.iter_mut() {
+        let field_type = match defined_fields.get(field) {
+            Some(field_type) => field_type,
+            None => {
+                return analyzer_error!(line, "Field {} does not exist", field)
+            }
+        };
+
+        validate_pattern(env, line, pattern, field_type, bindings)?;
+    }
+
+    let mut listed = BTreeSet::new();
+    for (field, _) in fields.iter() {
+        if !listed.insert(field) {
+            return analyzer_error!(
+                line,
+                "Field {} is listed more than once",
+                field
+            );
+        }
+    }
+
+    if !rest && listed.len() != defined_fields.len() {
+        return analyzer_error!(
+            line,
+            "Pattern is missing some fields, use ... to ignore the rest"
+        );
+    }
+
+    Ok(())
+}
+
+/// Whether a pattern matches every value of its type. A binding which was
+/// bound earlier in the same pattern only matches equal values, so it isn't
+fn is_irrefutable(pattern: &Pattern, bound: &mut Vec<String>) -> bool {
+    match pattern {
+        Pattern::Literal(_) | Pattern::EnumVariant { .. } => false,
+
+        Pattern::Binding(name) => {
+            if bound.contains(name) {
+                return false;
+            }
+
+            bound.push(name.clone());
+            true
+        }
+
+        Pattern::Struct { fields, .. } => fields
+            .iter()
+            .all(|(_, pattern)| is_irrefutable(pattern, bound)),
+
+        Pattern::Tuple(elements) => elements
+            .iter()
+            .all(|element| is_irrefutable(element, bound)),
+
+        Pattern::Array(groups) => {
+            groups.len() > 1 && groups.iter().all(|group| group.is_empty())
+        }
+    }
+}
+
+fn check_exhaustive(
+    env: &Environment,
+    line: usize,
+    arms: &Vec<MatchArm>,
+    value_type: &Type,
+) -> Result<(), AnalyzerError> {
+    let mut uncovered: BTreeSet<String> = match value_type {
+        Type::UserDefined(name) => match env.get_enum(name) {
+            Some(enum_) => enum_.variants.keys().cloned().collect(),
+            None => BTreeSet::new(),
+        },
+
+        Type::EnumVariant { variant, .. } => BTreeSet::from([variant.clone()]),
+
+        Type::Bool => BTreeSet::from(["true".to_string(), "false".to_string()]),
+
+        _ => BTreeSet::new(),
+    };
+    let has_cases = !uncovered.is_empty();
+
+    // arrays are covered by patterns for each length up to the shortest one
+    // with a ..., like [], [a] and [a, ..., b]
+    let mut covered_lengths: BTreeSet<usize> = BTreeSet::new();
+    let mut shortest_rest: Option<usize> = None;
+
+    for arm in arms {
+        let pattern = match &arm.pattern {
+            Some(pattern) => pattern,
+            None => return Ok(()),
+        };
+
+        if is_irrefutable(pattern, &mut vec![]) {
+            return Ok(());
+        }
+
+        match pattern {
+            // a variant is covered once all of its values are matched
+            Pattern::EnumVariant {
+                variant, fields, ..
+            } => {
+                let mut bound = vec![];
+                if fields
+                    .iter()
+                    .all(|(_, pattern)| is_irrefutable(pattern, &mut bound))
+                {
+                    uncovered.remove(variant);
+                }
+            }
+
+            Pattern::Literal(literal) => {
+                if let ExpressionData::Bool(value) = literal.data {
+                    uncovered.remove(&value.to_string());
+                }
+            }
+
+            Pattern::Array(groups) => {
+                let mut bound = vec![];
+                if groups
+                    .iter()
+                    .flatten()
+                    .all(|element| is_irrefutable(element, &mut bound))
+                {
+                    let length = groups.iter().map(|group| group.len()).sum();
+
+                    if groups.len() > 1 {
+                        shortest_rest = Some(
+                            shortest_rest.map_or(length, |l| l.min(length)),
+                        );
+                    } else {
+                        covered_lengths.insert(length);
+                    }
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    if let Some(shortest_rest) = shortest_rest {
+        if (0..shortest_rest).all(|length| covered_lengths.contains(&length)) {
+            return Ok(());
+        }
+    }
+
+    if !has_cases {
+        return analyzer_error!(
+            line,
+            "Match is not exhaustive, add an else arm"
+        );
+    }
+
+    if !uncovered.is_empty() {
+        return analyzer_error!(
+            line,
+            "Match is not exhaustive, {:?} aren't covered",
+            uncovered
+        );
+    }
+
+    Ok(())
+}
@@ -1,16 +1,36 @@
+use array::{validate_make_array, validate_make_array_expecting};
 use block::validate_block;
+use cast::validate_cast;
+use enum_definition::validate_enum_definition;
 use field_access::validate_field_access;
 use function_call::validate_function_call;
 use function_definition::validate_function_definition;
 use identifier::validate_identifier;
 use if_condition::validate_if_condition;
+use index_access::validate_index_access;
 use infix::validate_infix;
+use interface_definition::validate_interface_definition;
+use loops::{
+    validate_break, validate_continue, validate_for, validate_loop,
+    validate_while,
+};
+use make_enum::{validate_make_enum, validate_variant_cast};
 use make_struct::validate_make_struct;
+use map::{validate_make_map, validate_make_map_expecting};
+use match_expression::validate_match;
+use postfix::validate_postfix;
 use prefix::validate_prefix;
 use struct_definition::validate_struct_definition;
+use tuple::validate_make_tuple;
+use type_alias::validate_type_alias;
+use type_function::validate_type_function;
 use value_function_call::validate_value_function_call;
-use variable_assignment::validate_variable_assignment;
-use variable_definition::validate_variable_definition;
+use variable_assignment::{
+    validate_compound_assignment, validate_variable_assignment,
+};
+use variable_definition::{
+    validate_tuple_definition, validate_variable_definition,
+};
 
 use crate::{
     analyzer_error, expression_pat,
@@ -22,20 +42,75 @@ use crate::{
 
 use super::{analyzer_error::AnalyzerError, env::Environment};
 
+mod array;
 mod block;
+mod cast;
+mod enum_definition;
 mod field_access;
 mod function_call;
 mod function_definition;
 mod identifier;
 mod if_condition;
+mod index_access;
 mod infix;
+mod interface_definition;
+mod loops;
+mod make_enum;
 mod make_struct;
+mod map;
+mod match_expression;
+mod postfix;
 mod prefix;
 mod struct_definition;
+mod tuple;
+mod type_alias;
+mod type_function;
 mod value_function_call;
 mod variable_assignment;
 mod variable_definition;
 
+/// Same as validate_and_get_type, but an array or map literal takes its type
+/// from the type it's expected to have. Its elements are checked against that
+/// type, and an empty one has no elements to take its type from anyway
+pub fn validate_and_get_type_expecting(
+    expression: &mut Expression,
+    env: &mut Environment,
+    expected_type: &Type,
+) -> Result<Type, AnalyzerError> {
+    let expected_type = match env.resolve_type(expected_type) {
+        Type::Nullable(inner) => env.resolve_type(&inner),
+        type_ => type_,
+    };
+
+    match (&mut expression.data, &expected_type) {
+        (ExpressionData::MakeArray { elements }, Type::Array(element_type)) => {
+            let element_type = env.resolve_type(element_type);
+            validate_make_array_expecting(env, elements, &element_type)
+        }
+
+        (
+            ExpressionData::MakeMap { entries },
+            Type::Map {
+                key_type,
+                value_type,
+            },
+        ) => {
+            let key_type = env.resolve_type(key_type);
+            let value_type = env.resolve_type(value_type);
+
+            validate_make_map_expecting(
+                env,
+                expression.line,
+                entries,
+                &key_type,
+                &value_type,
+            )
+        }
+
+        _ => validate_and_get_type(expression, env),
+    }
+}
+
 pub fn validate_and_get_type(
     expression: &mut Expression,
     env: &mut Environment,
@@ -67,7 +142,9 @@ pub fn validate_and_get_type(
             right,
         }) => validate_infix(env, left, operator, right),
 
-        expression_pat!(ExpressionData::Postfix { .. }) => todo!(),
+        expression_pat!(ExpressionData::Postfix { value, operator }) => {
+            validate_postfix(env, operator, value)
+        }
 
         expression_pat!(ExpressionData::Block { expressions }) => {
             validate_block(env, expressions)
@@ -88,6 +165,32 @@ pub fn validate_and_get_type(
             else_branch,
         ),
 
+        expression_pat!(ExpressionData::Match { value, arms }, line) => {
+            validate_match(env, *line, value, arms)
+        }
+
+        expression_pat!(ExpressionData::While { condition, body }) => {
+            validate_while(env, condition, body)
+        }
+
+        expression_pat!(ExpressionData::Loop { body }) => {
+            validate_loop(env, body)
+        }
+
+        expression_pat!(ExpressionData::For {
+            variable,
+            iterable,
+            body,
+        }) => validate_for(env, variable, iterable, body),
+
+        expression_pat!(ExpressionData::Break { value }, line) => {
+            validate_break(env, *line, value)
+        }
+
+        expression_pat!(ExpressionData::Continue, line) => {
+            validate_continue(env, *line)
+        }
+
         expression_pat!(
             ExpressionData::VariableDefinition {
                 type_,
@@ -100,6 +203,18 @@ pub fn validate_and_get_type(
             env, *line, type_, value, name, *constant,
         ),
 
+        expression_pat!(
+            ExpressionData::TupleDefinition {
+                type_,
+                value,
+                names,
+                constant,
+            },
+            line
+        ) => validate_tuple_definition(
+            env, *line, type_, value, names, *constant,
+        ),
+
         expression_pat!(
             ExpressionData::VariableAssignment {
                 name,
@@ -110,48 +225,121 @@ pub fn validate_and_get_type(
         ) => validate_variable_assignment(env, *line, name, accessors, value),
 
         expression_pat!(
-            ExpressionData::FunctionDefinition {
+            ExpressionData::CompoundAssignment {
                 name,
-                pre_parameter,
-                params,
-                return_type,
-                body,
+                accessors,
+                operator,
+                value
             },
             line
-        ) => validate_function_definition(
-            env,
-            *line,
-            name,
-            pre_parameter,
-            params,
-            return_type,
-            body,
+        ) => validate_compound_assignment(
+            env, *line, name, accessors, operator, value,
         ),
 
+        definition @ expression_pat!(
+            ExpressionData::FunctionDefinition { .. }
+        ) => validate_function_definition(env, definition),
+
+        expression_pat!(
+            ExpressionData::TypeFunction {
+                type_name,
+                function_name
+            },
+            line
+        ) => validate_type_function(env, *line, type_name, function_name),
+
         expression_pat!(
-            ExpressionData::StructDefinition { name, fields },
+            ExpressionData::StructDefinition {
+                name,
+                fields,
+                defaults
+            },
             line
-        ) => validate_struct_definition(env, *line, name, fields),
+        ) => validate_struct_definition(env, *line, name, fields, defaults),
 
         expression_pat!(ExpressionData::MakeStruct { name, fields }, line) => {
             validate_make_struct(env, *line, name, fields)
         }
 
-        expression_pat!(ExpressionData::FunctionCall {
+        expression_pat!(ExpressionData::MakeArray { elements }, line) => {
+            validate_make_array(env, *line, elements)
+        }
+
+        expression_pat!(ExpressionData::MakeTuple { elements }) => {
+            validate_make_tuple(env, elements)
+        }
+
+        expression_pat!(ExpressionData::MakeMap { entries }, line) => {
+            validate_make_map(env, *line, entries)
+        }
+
+        expression_pat!(
+            ExpressionData::EnumDefinition { name, variants },
+            line
+        ) => validate_enum_definition(env, *line, name, variants),
+
+        expression_pat!(ExpressionData::TypeAlias { name, type_ }, line) => {
+            validate_type_alias(env, *line, name, type_)
+        }
+
+        expression_pat!(
+            ExpressionData::InterfaceDefinition { name, functions },
+            line
+        ) => validate_interface_definition(env, *line, name, functions),
+
+        expression_pat!(
+            ExpressionData::MakeEnum {
+                name,
+                variant,
+                fields
+            },
+            line
+        ) => validate_make_enum(env, *line, name, variant, fields),
+
+        expression_pat!(
+            ExpressionData::VariantCast {
+                value,
+                name,
+                variant
+            },
+            line
+        ) => validate_variant_cast(env, *line, value, name, variant),
+
+        cast @ expression_pat!(ExpressionData::Cast { .. }) => {
+            validate_cast(env, cast)
+        }
+
+        expression_pat!(
+            ExpressionData::FunctionCall {
+                function,
+                arguments,
+                keyword_arguments,
+            },
+            line
+        ) => validate_function_call(
+            env,
+            *line,
             function,
             arguments,
-        }) => validate_function_call(env, function, arguments),
+            keyword_arguments,
+        ),
 
-        expression_pat!(ExpressionData::ValueFunctionCall {
-            pre_argument,
-            function_name,
-            arguments,
-            pre_argument_type
-        }) => validate_value_function_call(
+        expression_pat!(
+            ExpressionData::ValueFunctionCall {
+                pre_argument,
+                function_name,
+                arguments,
+                keyword_arguments,
+                pre_argument_type
+            },
+            line
+        ) => validate_value_function_call(
             env,
+            *line,
             pre_argument,
             function_name,
             arguments,
+            keyword_arguments,
             pre_argument_type,
         ),
 
@@ -159,6 +347,13 @@ pub fn validate_and_get_type(
             validate_field_access(env, expression, field)
         }
 
+        expression_pat!(ExpressionData::IndexAccess { expression, index }) => {
+            validate_index_access(env, expression, index)
+        }
+
+        // only ever produced by the analyzer itself after validating a call
+        expression_pat!(ExpressionData::DefaultArgument) => unreachable!(),
+
         expression_pat!(ExpressionData::Type { .. }, line) => {
             return analyzer_error!(*line, "Cannot use a type as an expression")
         }
@@ -0,0 +1,49 @@
+use crate::{
+    analyzer::{analyzer_error::AnalyzerError, env::Environment},
+    analyzer_error,
+    lexer::token::TokenData,
+    parser::{expression::Expression, r#type::Type},
+};
+
+use super::validate_and_get_type;
+
+pub fn validate_postfix(
+    env: &mut Environment,
+    operator: &TokenData,
+    value: &mut Expression,
+) -> Result<Type, AnalyzerError> {
+    let value_type = validate_and_get_type(value, env)?;
+
+    match operator {
+        // checks if the value isn't null
+        TokenData::QuestionMark => match value_type {
+            Type::Nullable(_) | Type::Null => return Ok(Type::Bool),
+
+            type_ => {
+                return analyzer_error!(
+                    value.line,
+                    "Postfix operator ? can only work on nullable values, got \
+                     value of type {:?}",
+                    type_
+                )
+            }
+        },
+
+        // gets the value out of a nullable, which is a runtime error if it's
+        // null
+        TokenData::ExclamationMark => match value_type {
+            Type::Nullable(inner) => return Ok(*inner),
+
+            type_ => {
+                return analyzer_error!(
+                    value.line,
+                    "Postfix operator ! can only work on nullable values, got \
+                     value of type {:?}",
+                    type_
+                )
+            }
+        },
+
+        _ => unreachable!(),
+    }
+}
@@ -12,6 +12,19 @@ pub fn validate_prefix(
     operator: &TokenData,
     value: &mut Expression,
 ) -> Result<Type, AnalyzerError> {
+    if *operator == TokenData::Not {
+        return match validate_and_get_type(value, env)? {
+            Type::Bool => Ok(Type::Bool),
+
+            type_ => analyzer_error!(
+                value.line,
+                "Prefix operator not can not work on an expression of type \
+                 {:?}",
+                type_
+            ),
+        };
+    }
+
     if *operator != TokenData::Minus {
         unreachable!();
     }
@@ -1,31 +1,77 @@
 use std::collections::BTreeMap;
 
 use crate::{
-    analyzer::{analyzer_error::AnalyzerError, env::Environment},
+    analyzer::{
+        analyzer_error::AnalyzerError,
+        env::Environment,
+        util::{can_assign, cast_value},
+    },
     analyzer_error,
-    parser::r#type::Type,
+    parser::{expression::Expression, r#type::Type},
 };
 
+use super::validate_and_get_type_expecting;
+
 pub fn validate_struct_definition(
     env: &mut Environment,
     line: usize,
     name: &Option<String>,
-    fields: &BTreeMap<String, Type>,
+    fields: &mut BTreeMap<String, Type>,
+    defaults: &mut BTreeMap<String, Expression>,
 ) -> Result<Type, AnalyzerError> {
+    for field_type in fields.values_mut() {
+        *field_type = env.resolve_type(field_type);
+    }
+
+    // default values are compiled wherever the struct is made, so they're
+    // validated without any variables since those might not be visible there
+    let mut defaults_env = Environment::new();
+    defaults_env.functions = env.functions.clone();
+    defaults_env.structs = env.structs.clone();
+    defaults_env.enums = env.enums.clone();
+    defaults_env.aliases = env.aliases.clone();
+    defaults_env.interfaces = env.interfaces.clone();
+    defaults_env.type_functions = env.type_functions.clone();
+
+    for (field_name, default) in defaults.iter_mut() {
+        let field_type = &fields[field_name];
+        let default_type = validate_and_get_type_expecting(
+            default,
+            &mut defaults_env,
+            field_type,
+        )?;
+
+        if !can_assign(env, field_type, &default_type) {
+            return analyzer_error!(
+                default.line,
+                "Default value of field {} should have type {:?}, got value of type {:?}",
+                field_name,
+                field_type,
+                default_type
+            );
+        }
+
+        cast_value(field_type, default, &default_type);
+    }
+
     // add struct to the environment if it has a name (only top level structs
     // can have names, this is ensured by the parser)
     if name.is_some() {
         let name = name.clone().unwrap();
 
-        if env.get_struct(&name).is_some() {
+        if env.get_struct(&name).is_some()
+            || env.get_enum(&name).is_some()
+            || env.get_alias(&name).is_some()
+            || env.get_interface(&name).is_some()
+        {
             return analyzer_error!(
                 line,
-                "A struct with the name {} already exists",
+                "A type with the name {} already exists",
                 name
             );
         }
 
-        env.add_struct(name, fields.clone());
+        env.add_struct(name, fields.clone(), defaults.clone());
     }
 
     return Ok(Type::Null);
@@ -0,0 +1,19 @@
+use crate::{
+    analyzer::{analyzer_error::AnalyzerError, env::Environment},
+    parser::{expression::Expression, r#type::Type},
+};
+
+use super::validate_and_get_type;
+
+pub fn validate_make_tuple(
+    env: &mut Environment,
+    elements: &mut Vec<Expression>,
+) -> Result<Type, AnalyzerError> {
+    let mut element_types = vec![];
+
+    for element in elements {
+        element_types.push(validate_and_get_type(element, env)?);
+    }
+
+    return Ok(Type::Tuple(element_types));
+}
@@ -0,0 +1,77 @@
+use crate::{
+    analyzer::{analyzer_error::AnalyzerError, env::Environment},
+    analyzer_error,
+    parser::r#type::Type,
+};
+
+/// Check if a type mentions the given name, either directly or through the
+/// aliases it uses
+fn refers_to(env: &Environment, type_: &Type, name: &str) -> bool {
+    match type_ {
+        Type::UserDefined(type_name)
+        | Type::EnumVariant {
+            name: type_name, ..
+        } => {
+            type_name == name
+                || env
+                    .get_alias(type_name)
+                    .is_some_and(|alias| refers_to(env, &alias.type_, name))
+        }
+
+        Type::Array(inner) | Type::Nullable(inner) => {
+            refers_to(env, inner, name)
+        }
+
+        Type::Tuple(element_types) => {
+            element_types.iter().any(|t| refers_to(env, t, name))
+        }
+
+        Type::Map {
+            key_type,
+            value_type,
+        } => refers_to(env, key_type, name) || refers_to(env, value_type, name),
+
+        Type::Function {
+            param_types,
+            return_type,
+            ..
+        } => {
+            param_types.iter().any(|t| refers_to(env, t, name))
+                || refers_to(env, return_type, name)
+        }
+
+        Type::Struct { fields } => {
+            fields.values().any(|t| refers_to(env, t, name))
+        }
+
+        _ => false,
+    }
+}
+
+pub fn validate_type_alias(
+    env: &mut Environment,
+    line: usize,
+    name: &String,
+    type_: &Type,
+) -> Result<Type, AnalyzerError> {
+    if env.get_struct(name).is_some()
+        || env.get_enum(name).is_some()
+        || env.get_alias(name).is_some()
+        || env.get_interface(name).is_some()
+    {
+        return analyzer_error!(
+            line,
+            "A type with the name {} already exists",
+            name
+        );
+    }
+
+    // an alias which contains itself could never be resolved
+    if refers_to(env, type_, name) {
+        return analyzer_error!(line, "Type alias {} refers to itself", name);
+    }
+
+    env.add_alias(name.clone(), type_.clone());
+
+    return Ok(Type::Null);
+}
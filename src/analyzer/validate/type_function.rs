@@ -0,0 +1,47 @@
+use crate::{
+    analyzer::{analyzer_error::AnalyzerError, env::Environment},
+    analyzer_error,
+    parser::r#type::Type,
+};
+
+/// Check that type functions can be defined on the given type. The name is
+/// replaced with the one of the struct or enum if it's an alias, so that the
+/// compiler finds the functions by the same name either way
+pub fn resolve_type_name(
+    env: &Environment,
+    line: usize,
+    type_name: &mut String,
+) -> Result<(), AnalyzerError> {
+    if let Type::UserDefined(name) =
+        env.resolve_type(&Type::UserDefined(type_name.clone()))
+    {
+        *type_name = name;
+    }
+
+    if env.get_struct(type_name).is_none() && env.get_enum(type_name).is_none()
+    {
+        return analyzer_error!(line, "Type {} does not exist", type_name);
+    }
+
+    Ok(())
+}
+
+pub fn validate_type_function(
+    env: &mut Environment,
+    line: usize,
+    type_name: &mut String,
+    function_name: &String,
+) -> Result<Type, AnalyzerError> {
+    resolve_type_name(env, line, type_name)?;
+
+    match env.get_type_function(type_name, function_name) {
+        Some(function) => Ok(function.type_()),
+
+        None => analyzer_error!(
+            line,
+            "Type function {}#{} does not exist",
+            type_name,
+            function_name
+        ),
+    }
+}
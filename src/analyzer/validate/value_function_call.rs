@@ -1,30 +1,77 @@
 use crate::{
     analyzer::{
-        analyzer_error::AnalyzerError, env::Environment, util::types_equal,
+        analyzer_error::AnalyzerError,
+        env::{Environment, EnvironmentFunction},
+        util::types_equal,
     },
     analyzer_error,
     parser::{expression::Expression, r#type::Type},
 };
 
-use super::validate_and_get_type;
+use super::{function_call::resolve_arguments, validate_and_get_type};
+
+/// Get a function of the interface which is the pre-argument's type, with
+/// this replaced by the interface. The arguments which are this are checked to
+/// have the same actual type as the pre-argument when it's called
+fn interface_function(
+    env: &Environment,
+    pre_argument_type: &Option<Type>,
+    function_name: &str,
+) -> Option<EnvironmentFunction> {
+    let interface_name = match env.resolve_type(pre_argument_type.as_ref()?) {
+        Type::Interface(name) => name,
+        _ => return None,
+    };
+
+    let interface = env.get_interface(&interface_name)?;
+    let function = interface
+        .functions
+        .iter()
+        .find(|function| function.name == function_name)?;
+
+    let interface_type = Type::Interface(interface_name);
+    let replace_this = |type_: &Type| match type_ {
+        Type::This => interface_type.clone(),
+        type_ => type_.clone(),
+    };
+
+    Some(EnvironmentFunction {
+        name: function.name.clone(),
+        pre_param_type: Some(interface_type.clone()),
+        pre_param_var: false,
+        param_types: function.param_types.iter().map(replace_this).collect(),
+        var_params: function.var_params.clone(),
+        return_type: replace_this(&function.return_type),
+        param_names: function.param_names.clone(),
+        default_params: vec![false; function.param_types.len()],
+        rest_param: false,
+    })
+}
 
 pub fn validate_value_function_call(
     env: &mut Environment,
+    line: usize,
     pre_argument: &mut Expression,
     function_name: &String,
     arguments: &mut Vec<Expression>,
+    keyword_arguments: &mut Vec<(String, Expression)>,
     pre_argument_type: &mut Option<Type>,
 ) -> Result<Type, AnalyzerError> {
-    *pre_argument_type = Some(validate_and_get_type(pre_argument, env)?);
+    let argument_type = Some(validate_and_get_type(pre_argument, env)?);
 
-    let function = match env.get_function(&function_name, &pre_argument_type) {
+    // the functions of an interface are found at runtime, from the actual
+    // type of the value
+    let function = match env
+        .get_function(&function_name, &argument_type)
+        .or_else(|| interface_function(env, &argument_type, function_name))
+    {
         Some(function) => function,
 
         None => {
             return analyzer_error!(
                 pre_argument.line,
                 "Cannot find function {:?}:{}",
-                pre_argument_type,
+                argument_type,
                 function_name
             )
         }
@@ -32,39 +79,22 @@ pub fn validate_value_function_call(
 
     if !types_equal(
         &env,
-        pre_argument_type.as_ref().unwrap(),
+        argument_type.as_ref().unwrap(),
         &function.pre_param_type.clone().unwrap(),
     ) {
         return analyzer_error!(
             pre_argument.line,
             "Expected pre-argument of type {:?}, got type {:?}",
             function.pre_param_type.unwrap(),
-            pre_argument_type
+            argument_type
         );
     }
 
-    if arguments.len() != function.param_types.len() {
-        return analyzer_error!(
-            pre_argument.line,
-            "Expected {} arguments, {} provided",
-            function.param_types.len(),
-            arguments.len()
-        );
-    }
-
-    for i in 0..function.param_types.len() {
-        let argument_type = validate_and_get_type(&mut arguments[i], env)?;
-        let param_type = function.param_types[i].clone();
+    // the compiler looks the function up by the type it was defined with,
+    // which isn't always written the same way as the pre-argument's type
+    *pre_argument_type = function.pre_param_type.clone();
 
-        if !types_equal(env, &argument_type, &param_type) {
-            return analyzer_error!(
-                arguments[i].line,
-                "Expected value of type {:?}, got value of type {:?} instead",
-                param_type,
-                argument_type
-            );
-        }
-    }
+    resolve_arguments(env, line, &function, arguments, keyword_arguments)?;
 
     return Ok(function.return_type);
 }
@@ -2,16 +2,22 @@ use std::collections::BTreeMap;
 
 use crate::{
     analyzer::{
-        analyzer_error::AnalyzerError, env::Environment, util::types_equal,
+        analyzer_error::AnalyzerError,
+        env::Environment,
+        util::{can_assign, cast_value},
     },
     analyzer_error,
+    lexer::token::TokenData,
     parser::{
         expression::{Expression, VariableAccessor},
         r#type::Type,
     },
 };
 
-use super::validate_and_get_type;
+use super::{
+    index_access::validate_index, validate_and_get_type,
+    validate_and_get_type_expecting,
+};
 
 fn extract_struct_fields(
     env: &mut Environment,
@@ -27,18 +33,34 @@ fn extract_struct_fields(
             None => analyzer_error!(line, "Struct {} does not exist", name),
         },
 
+        Type::EnumVariant { name, variant } => {
+            match env.get_enum_variant(name, variant) {
+                Some(fields) => Ok(fields),
+
+                None => analyzer_error!(
+                    line,
+                    "Enum variant {}:{} does not exist",
+                    name,
+                    variant
+                ),
+            }
+        }
+
         _ => analyzer_error!(line, "Not a struct"),
     }
 }
 
-pub fn validate_variable_assignment(
+/// Check that the variable can be assigned to and get the type of the location
+/// the accessors lead to. If the location is also read (like in a += 1), a map
+/// entry at the end might not exist, so its type is nullable
+fn validate_assignment_target(
     env: &mut Environment,
     line: usize,
     name: &String,
-    accessors: &Vec<VariableAccessor>,
-    value: &mut Expression,
+    accessors: &mut Vec<VariableAccessor>,
+    reads_target: bool,
 ) -> Result<Type, AnalyzerError> {
-    let var = match env.get_variable(name) {
+    let var = match env.use_variable(name) {
         None => {
             return analyzer_error!(
                 line,
@@ -57,49 +79,142 @@ pub fn validate_variable_assignment(
         );
     }
 
-    // if there's no accessors just compare the variable and value types, else
-    // check if each field exists and compare the last one's type with the value
-    if accessors.is_empty() {
-        let value_type = validate_and_get_type(value, env)?;
+    // trace the accessors one by one to get the type of the location which is
+    // being assigned to, then compare it with the value type
+    let mut target_type = var.type_.clone();
 
-        if !types_equal(env, &value_type, &var.type_) {
-            return analyzer_error!(
-                value.line,
-                "Expected value of type {:?}, got value of type {:?} instead",
-                var.type_,
-                value_type
-            );
-        }
-    } else {
-        let mut fields = extract_struct_fields(env, line, &var.type_)?;
-
-        for (i, accessor) in accessors.iter().enumerate() {
-            // there will also be array indexes later, so this is just some
-            // setup in advance
-            let field = match accessor {
-                VariableAccessor::StructField(f) => f,
-            };
-
-            if fields.get(field).is_none() {
-                // error
+    let accessor_count = accessors.len();
+
+    for (i, accessor) in accessors.iter_mut().enumerate() {
+        target_type = match accessor {
+            VariableAccessor::StructField(field) => {
+                let fields = extract_struct_fields(env, line, &target_type)?;
+
+                match fields.get(field) {
+                    Some(field_type) => field_type.clone(),
+
+                    None => {
+                        return analyzer_error!(
+                            line,
+                            "Field {:?} does not exist on variable {}",
+                            field,
+                            name
+                        )
+                    }
+                }
             }
 
-            // if it's on the last field compare its type to the value type,
-            // else prepare for the next iteration
-            if i == accessors.len() - 1 {
-                let field_type = &fields[field];
-                let value_type = validate_and_get_type(value, env)?;
+            // strings can be indexed, but not assigned to
+            VariableAccessor::Index(index) => match target_type {
+                Type::Array(_) => validate_index(env, &target_type, index)?,
+
+                // assigning to a key inserts it into the map, so the value
+                // doesn't have to be nullable. Anything after that goes
+                // through a lookup, which does give a nullable value
+                Type::Map { .. } => {
+                    match validate_index(env, &target_type, index)? {
+                        Type::Nullable(value_type)
+                            if i == accessor_count - 1 && !reads_target =>
+                        {
+                            *value_type
+                        }
 
-                if !types_equal(env, &value_type, field_type) {
+                        type_ => type_,
+                    }
+                }
+
+                _ => {
                     return analyzer_error!(
-                        value.line,
-                        "Expected value of type {:?}, got value of type {:?} instead",
-                        field_type, value_type
-                    );
+                        line,
+                        "Cannot assign to an index of a value of type {:?}",
+                        target_type
+                    )
                 }
-            } else {
-                fields = extract_struct_fields(env, line, &fields[field])?;
-            }
+            },
+        };
+    }
+
+    Ok(target_type)
+}
+
+pub fn validate_variable_assignment(
+    env: &mut Environment,
+    line: usize,
+    name: &String,
+    accessors: &mut Vec<VariableAccessor>,
+    value: &mut Expression,
+) -> Result<Type, AnalyzerError> {
+    let target_type =
+        validate_assignment_target(env, line, name, accessors, false)?;
+
+    let value_type = validate_and_get_type_expecting(value, env, &target_type)?;
+
+    if !can_assign(env, &target_type, &value_type) {
+        return analyzer_error!(
+            value.line,
+            "Expected value of type {:?}, got value of type {:?} instead",
+            target_type,
+            value_type
+        );
+    }
+
+    cast_value(&target_type, value, &value_type);
+
+    return Ok(Type::Null);
+}
+
+/// The operator is the one which is applied to the old value and the new one,
+/// like Plus for +=. The result has to be stored back into the same location,
+/// so it has to keep its type
+pub fn validate_compound_assignment(
+    env: &mut Environment,
+    line: usize,
+    name: &String,
+    accessors: &mut Vec<VariableAccessor>,
+    operator: &TokenData,
+    value: &mut Expression,
+) -> Result<Type, AnalyzerError> {
+    let target_type =
+        validate_assignment_target(env, line, name, accessors, true)?;
+    let value_type = validate_and_get_type(value, env)?;
+
+    match (
+        env.resolve_type(&target_type),
+        env.resolve_type(&value_type),
+    ) {
+        (Type::Float, Type::Int | Type::Float) => {}
+
+        // dividing two ints gives a float, which can't be stored in an int
+        (Type::Int, Type::Int | Type::Float)
+            if *operator == TokenData::Slash =>
+        {
+            return analyzer_error!(
+                line,
+                "Operator /= cannot be used on {}, which is an int, since \
+                 division gives a float",
+                name
+            )
+        }
+
+        (Type::Int, Type::Int) => {}
+
+        (Type::Int, Type::Float) => {
+            return analyzer_error!(
+                value.line,
+                "Cannot use a float with an operator on {}, which is an int",
+                name
+            )
+        }
+
+        _ => {
+            return analyzer_error!(
+                line,
+                "Operator {:?} only works on numbers, got values of type {:?} \
+                 and {:?}",
+                operator,
+                target_type,
+                value_type
+            )
         }
     }
 
@@ -1,21 +1,20 @@
 use crate::{
     analyzer::{
-        analyzer_error::AnalyzerError, env::Environment, util::types_equal,
+        analyzer_error::AnalyzerError,
+        env::Environment,
+        util::{can_assign, cast_value},
     },
     analyzer_error,
     parser::{expression::Expression, r#type::Type},
 };
 
-use super::validate_and_get_type;
+use super::{validate_and_get_type, validate_and_get_type_expecting};
 
-pub fn validate_variable_definition(
-    env: &mut Environment,
+pub fn check_name_is_free(
+    env: &Environment,
     line: usize,
-    type_: &Option<Box<Type>>,
-    value: &mut Expression,
     name: &String,
-    constant: bool,
-) -> Result<Type, AnalyzerError> {
+) -> Result<(), AnalyzerError> {
     if env.get_variable(name).is_some() {
         return analyzer_error!(
             line,
@@ -32,12 +31,25 @@ pub fn validate_variable_definition(
         );
     }
 
+    Ok(())
+}
+
+pub fn validate_variable_definition(
+    env: &mut Environment,
+    line: usize,
+    type_: &mut Option<Box<Type>>,
+    value: &mut Expression,
+    name: &String,
+    constant: bool,
+) -> Result<Type, AnalyzerError> {
+    check_name_is_free(env, line, name)?;
+
     if let Some(type_) = type_ {
-        if !types_equal(
-            &env.clone(),
-            type_,
-            &validate_and_get_type(value, env)?,
-        ) {
+        **type_ = env.resolve_type(type_);
+
+        let value_type = validate_and_get_type_expecting(value, env, type_)?;
+
+        if !can_assign(env, type_, &value_type) {
             return analyzer_error!(
                 value.line,
                 "Cannot cast value {:?} to type {:?}",
@@ -46,6 +58,8 @@ pub fn validate_variable_definition(
             );
         }
 
+        cast_value(type_, value, &value_type);
+
         env.add_variable(name.clone(), *type_.clone(), constant);
     } else {
         let type_ = validate_and_get_type(value, env)?;
@@ -54,3 +68,69 @@ pub fn validate_variable_definition(
 
     return Ok(Type::Null);
 }
+
+pub fn validate_tuple_definition(
+    env: &mut Environment,
+    line: usize,
+    type_: &mut Option<Box<Type>>,
+    value: &mut Expression,
+    names: &Vec<String>,
+    constant: bool,
+) -> Result<Type, AnalyzerError> {
+    for (i, name) in names.iter().enumerate() {
+        check_name_is_free(env, line, name)?;
+
+        if names[..i].contains(name) {
+            return analyzer_error!(
+                line,
+                "Variable {} is defined more than once",
+                name
+            );
+        }
+    }
+
+    let mut value_type = validate_and_get_type(value, env)?;
+
+    // the variables get their types from the given type if there is one
+    if let Some(type_) = type_ {
+        **type_ = env.resolve_type(type_);
+
+        if !can_assign(env, type_, &value_type) {
+            return analyzer_error!(
+                value.line,
+                "Cannot cast value {:?} to type {:?}",
+                value,
+                type_
+            );
+        }
+
+        value_type = *type_.clone();
+    }
+
+    let element_types = match value_type {
+        Type::Tuple(element_types) => element_types,
+
+        _ => {
+            return analyzer_error!(
+                value.line,
+                "Only tuples can be destructured, got value of type {:?}",
+                value_type
+            )
+        }
+    };
+
+    if element_types.len() != names.len() {
+        return analyzer_error!(
+            value.line,
+            "Cannot destructure a tuple with {} elements into {} variables",
+            element_types.len(),
+            names.len()
+        );
+    }
+
+    for (name, element_type) in names.iter().zip(element_types) {
+        env.add_variable(name.clone(), element_type, constant);
+    }
+
+    return Ok(Type::Null);
+}
@@ -1,6 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use crate::parser::r#type::Type;
+use crate::{
+    parser::{
+        expression::{Expression, InterfaceFunction},
+        r#type::Type,
+    },
+    vm::native_function::NativeFunction,
+};
 
 use super::operation::Operation;
 
@@ -11,14 +17,40 @@ pub struct ChunkFunctionParam {
 
 #[derive(Debug, Clone)]
 pub struct ChunkFunction {
+    pub name: Option<String>,
     pub chunk: Chunk,
     pub pre_param: Option<ChunkFunctionParam>,
     pub parameters: Vec<ChunkFunctionParam>,
 }
 
+#[derive(Debug, Clone)]
+pub struct ChunkLoop {
+    // addresses of the Jump operations added for breaks and continues, which
+    // are updated once the loop has been compiled and the addresses of its end
+    // and next iteration are known
+    pub break_jumps: Vec<usize>,
+    pub continue_jumps: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VariableSlot {
+    Local(u16),
+    Global(u16),
+
+    // index into the variables captured by the closure which is executing
+    Upvalue(u16),
+}
+
 #[derive(Debug, Clone)]
 pub struct Chunk {
     pub code: Vec<Operation>,
+
+    // source line of each operation in code, used for runtime errors
+    pub lines: Vec<usize>,
+    // line of the expression which is currently being compiled, every
+    // operation that's added gets this line
+    pub current_line: usize,
+
     pub functions: Vec<ChunkFunction>,
 
     // this is used while compiling as a mapping between global function names
@@ -28,20 +60,138 @@ pub struct Chunk {
     // same thing as function_index_from_name except for value functions, so
     // it's also indexed by a type for the pre-parameter
     pub value_function_index_from_name: HashMap<(String, Type), usize>,
+
+    // the type functions of each type, by the name of the type and then by
+    // the name of the function
+    pub type_function_index_from_name: HashMap<String, HashMap<String, usize>>,
+
+    // functions registered by the host program. They're only stored in the
+    // top level chunk, function chunks just get a copy of the mapping between
+    // their names and indices
+    pub native_functions: Vec<NativeFunction>,
+    pub native_function_index_from_name: HashMap<String, usize>,
+
+    // default values of the fields of each struct, they're compiled in place
+    // of the fields which are left out when making a struct
+    pub struct_defaults_from_name:
+        HashMap<String, BTreeMap<String, Expression>>,
+
+    // the functions of each interface, used to know which calls through an
+    // interface return a value of the type implementing it
+    pub interface_functions_from_name: HashMap<String, Vec<InterfaceFunction>>,
+
+    // mapping between the names of global variables and their slots in the
+    // VM's globals table. Globals are all the variables defined in the top
+    // level chunk, function chunks just get a copy of this so they can
+    // access them
+    pub global_slot_from_name: HashMap<String, u16>,
+    pub global_count: u16,
+
+    // mapping between the names of a function's variables (including its
+    // parameters) and their slots in the function's locals. This is always
+    // empty in the top level chunk
+    pub local_slot_from_name: HashMap<String, u16>,
+    pub local_count: u16,
+
+    // mapping between the names of variables captured from enclosing
+    // functions and their indices in the closure's upvalues
+    pub upvalue_slot_from_name: HashMap<String, u16>,
+
+    // whether new variables are defined as locals or globals
+    pub is_function_chunk: bool,
+
+    // loops which are currently being compiled, the last one is the innermost
+    pub loops: Vec<ChunkLoop>,
 }
 
 impl Chunk {
     pub fn new() -> Self {
         Self {
             code: vec![],
+            lines: vec![],
+            current_line: 0,
             functions: vec![],
             function_index_from_name: HashMap::new(),
             value_function_index_from_name: HashMap::new(),
+            type_function_index_from_name: HashMap::new(),
+            native_functions: vec![],
+            native_function_index_from_name: HashMap::new(),
+            struct_defaults_from_name: HashMap::new(),
+            interface_functions_from_name: HashMap::new(),
+            global_slot_from_name: HashMap::new(),
+            global_count: 0,
+            local_slot_from_name: HashMap::new(),
+            local_count: 0,
+            upvalue_slot_from_name: HashMap::new(),
+            is_function_chunk: false,
+            loops: vec![],
         }
     }
 
+    /// Create a chunk for a function defined inside of the given chunk, which
+    /// has access to all of its functions and globals
+    pub fn new_function(parent: &Chunk) -> Self {
+        Self {
+            function_index_from_name: parent.function_index_from_name.clone(),
+            value_function_index_from_name: parent
+                .value_function_index_from_name
+                .clone(),
+            type_function_index_from_name: parent
+                .type_function_index_from_name
+                .clone(),
+            native_function_index_from_name: parent
+                .native_function_index_from_name
+                .clone(),
+            struct_defaults_from_name: parent.struct_defaults_from_name.clone(),
+            interface_functions_from_name: parent
+                .interface_functions_from_name
+                .clone(),
+            global_slot_from_name: parent.global_slot_from_name.clone(),
+            global_count: parent.global_count,
+            current_line: parent.current_line,
+            is_function_chunk: true,
+            ..Self::new()
+        }
+    }
+
+    /// Give a new slot to a variable. Every definition gets its own slot, even
+    /// if a variable with the same name has already been defined in a
+    /// different block
+    pub fn define_variable(&mut self, name: &String) -> VariableSlot {
+        if self.is_function_chunk {
+            let slot = self.local_count;
+            self.local_slot_from_name.insert(name.clone(), slot);
+            self.local_count += 1;
+
+            VariableSlot::Local(slot)
+        } else {
+            let slot = self.global_count;
+            self.global_slot_from_name.insert(name.clone(), slot);
+            self.global_count += 1;
+
+            VariableSlot::Global(slot)
+        }
+    }
+
+    pub fn get_variable_slot(&self, name: &String) -> Option<VariableSlot> {
+        if let Some(slot) = self.local_slot_from_name.get(name) {
+            return Some(VariableSlot::Local(*slot));
+        }
+
+        if let Some(slot) = self.upvalue_slot_from_name.get(name) {
+            return Some(VariableSlot::Upvalue(*slot));
+        }
+
+        if let Some(slot) = self.global_slot_from_name.get(name) {
+            return Some(VariableSlot::Global(*slot));
+        }
+
+        None
+    }
+
     pub fn add_operation(&mut self, operation: &Operation) {
         self.code.push(operation.clone());
+        self.lines.push(self.current_line);
     }
 
     pub fn update_operation(
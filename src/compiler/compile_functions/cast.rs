@@ -0,0 +1,31 @@
+use crate::{
+    compiler::{chunk::Chunk, compile_expression, operation::Operation},
+    parser::{expression::Expression, r#type::Type},
+};
+
+pub fn compile_cast(
+    chunk: &mut Chunk,
+    is_function: bool,
+    value: &Expression,
+    type_: &Type,
+    value_type: &Option<Type>,
+) -> Result<(), String> {
+    compile_expression(chunk, value, is_function)?;
+
+    let value_type = value_type.as_ref().unwrap();
+
+    if *type_ != Type::Any {
+        chunk.add_operation(&Operation::Cast(type_.clone()));
+    } else if *value_type != Type::Any {
+        // the value of an enum variant is stored as a value of its enum, it
+        // can still be cast back to the variant
+        let stored_type = match value_type {
+            Type::EnumVariant { name, .. } => Type::UserDefined(name.clone()),
+            _ => value_type.clone(),
+        };
+
+        chunk.add_operation(&Operation::ToAny(stored_type));
+    }
+
+    Ok(())
+}
@@ -4,97 +4,129 @@ use crate::{
         compile_expression,
         operation::Operation,
     },
-    parser::expression::{Expression, FunctionParam},
+    parser::expression::{Expression, ExpressionData},
 };
 
 /// Returns function index if successful
 pub fn compile_function_definition(
     chunk: &mut Chunk,
     is_function: bool,
-    pre_param: &Option<FunctionParam>,
-    params: &Vec<FunctionParam>,
-    body: &Expression,
-    name: &Option<String>,
+    definition: &Expression,
 ) -> Result<usize, String> {
-    let mut function_chunk = Chunk::new();
+    let (pre_param, params, body, name, type_name, captures) =
+        match &definition.data {
+            ExpressionData::FunctionDefinition {
+                pre_parameter,
+                params,
+                body,
+                name,
+                type_name,
+                captures,
+                ..
+            } => (pre_parameter, params, body, name, type_name, captures),
+
+            _ => unreachable!(),
+        };
+
+    // All functions are stored in the top level chunk's functions, so a
+    // placeholder is added to reserve this function's index. Otherwise the
+    // functions defined inside of its body would take it
+    let index = chunk.functions.len();
+    chunk.functions.push(ChunkFunction {
+        name: name.clone(),
+        chunk: Chunk::new(),
+        pre_param: None,
+        parameters: vec![],
+    });
 
-    let mut chunk_function_params: Vec<ChunkFunctionParam> = vec![];
+    // add mappings to the appropriate chunk fields based on whether it's a
+    // value function, a type function or neither.
+    // This is done before creating the function's chunk so the mapping is
+    // copied into it, which allows for recursion (this index doesn't actually
+    // exist in the function's chunk, but it will be executed from the parent
+    // chunk)
+    if let Some(name) = name {
+        if let Some(pre_param) = pre_param {
+            chunk
+                .value_function_index_from_name
+                .insert((name.clone(), pre_param.type_.clone()), index);
+        } else if let Some(type_name) = type_name {
+            chunk
+                .type_function_index_from_name
+                .entry(type_name.clone())
+                .or_default()
+                .insert(name.clone(), index);
+        } else {
+            chunk.function_index_from_name.insert(name.clone(), index);
+        }
+    }
 
-    for param in params {
-        // store all params from the stack into variables
-        function_chunk.add_operation(&Operation::Store {
-            name: param.name.clone(),
-            accessors: vec![],
-        });
+    let mut function_chunk = Chunk::new_function(chunk);
+    function_chunk.functions = std::mem::take(&mut chunk.functions);
 
-        chunk_function_params.push(ChunkFunctionParam {
-            constant: param.constant,
-        });
+    for (slot, capture) in captures.iter().enumerate() {
+        function_chunk
+            .upvalue_slot_from_name
+            .insert(capture.clone(), slot as u16);
     }
 
-    chunk_function_params.reverse();
-
+    // the arguments are already on the stack when the function starts, so
+    // its parameters just need to be given the first slots (the pre-argument
+    // is pushed before the other ones, so it gets the first one)
     let mut chunk_function_pre_param: Option<ChunkFunctionParam> = None;
     if let Some(pre_param) = pre_param {
-        function_chunk.add_operation(&Operation::Store {
-            name: pre_param.name.clone(),
-            accessors: vec![],
-        });
+        function_chunk.define_variable(&pre_param.name);
 
         chunk_function_pre_param = Some(ChunkFunctionParam {
             constant: pre_param.constant,
         });
     }
 
-    // Index of this function once it's been added to the chunk (calculated in
-    // advance)
-    let index = chunk.functions.len();
-
-    if name.is_some() {
-        let name = name.clone().unwrap();
-
-        // add mappings to the appropriate chunk fields based on whether it's a
-        // value function or not (i.e. whether or not it has a pre-parameter)
-        if pre_param.is_some() {
-            let pre_param_type = pre_param.clone().unwrap().type_;
-
-            // add mapping from this function name to the precalculated index
-            chunk
-                .value_function_index_from_name
-                .insert((name.clone(), pre_param_type.clone()), index);
+    let mut chunk_function_params: Vec<ChunkFunctionParam> = vec![];
 
-            // make the same mapping in the function's chunk to allow for
-            // recursion (this index doesn't actually exist in the function's
-            // chunk, but it will be executed from the parent chunk)
-            function_chunk
-                .value_function_index_from_name
-                .insert((name, pre_param_type), index);
-        } else {
-            // add mapping from this function name to the precalculated index
-            chunk.function_index_from_name.insert(name.clone(), index);
+    for param in params {
+        function_chunk.define_variable(&param.name);
 
-            // make the same mapping in the function's chunk to allow for
-            // recursion (this index doesn't actually exist in the function's
-            // chunk, but it will be executed from the parent chunk)
-            function_chunk.function_index_from_name.insert(name, index);
-        }
+        chunk_function_params.push(ChunkFunctionParam {
+            constant: param.constant,
+        });
+    }
 
-        // also add all the other function indexes to the function's chunk
-        for (name, index) in chunk.function_index_from_name.clone() {
-            function_chunk.function_index_from_name.insert(name, index);
+    // parameters which were left out by the caller get a
+    // Value::DefaultArgument, which is replaced by their default value here.
+    // This is done in order so that a default can use the parameters before it
+    for param in params {
+        if let Some(default) = &param.default {
+            let slot = function_chunk.get_variable_slot(&param.name).unwrap();
+
+            function_chunk.add_operation(&Operation::load(slot));
+            function_chunk.add_operation(&Operation::IsDefaultArgument);
+            function_chunk.add_operation(&Operation::JumpIfFalse(0));
+            let jump_address = function_chunk.get_latest_address();
+
+            compile_expression(&mut function_chunk, default, is_function)?;
+            function_chunk.add_operation(&Operation::define(slot));
+
+            let after_default = function_chunk.code.len();
+            function_chunk.update_operation(
+                jump_address,
+                &Operation::JumpIfFalse(after_default),
+            );
         }
     }
 
     // compile function body
     compile_expression(&mut function_chunk, body, is_function)?;
-    function_chunk.add_operation(&Operation::Halt);
+    function_chunk.add_operation(&Operation::Return);
 
-    // add function to the chunk
-    chunk.functions.push(ChunkFunction {
+    // give the functions back to the chunk and replace the placeholder
+    chunk.functions = std::mem::take(&mut function_chunk.functions);
+    chunk.functions[index] = ChunkFunction {
+        name: name.clone(),
         chunk: function_chunk,
         pre_param: chunk_function_pre_param,
         parameters: chunk_function_params,
-    });
+    };
 
     Ok(index)
 }
@@ -1,5 +1,7 @@
 use crate::{
-    compiler::{chunk::Chunk, compile_expression, operation::Operation},
+    compiler::{
+        chunk::Chunk, compile_expression, operation::Operation, value::Value,
+    },
     parser::expression::Expression,
 };
 
@@ -24,6 +26,8 @@ pub fn compile_if_condition(
 
     if else_branch.is_some() {
         compile_expression(chunk, &else_branch.as_ref().unwrap(), is_function)?;
+    } else {
+        chunk.add_operation(&Operation::Push(Value::Null));
     }
 
     let end_address = chunk.get_latest_address() + 1;
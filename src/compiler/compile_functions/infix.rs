@@ -1,5 +1,7 @@
 use crate::{
-    compiler::{chunk::Chunk, compile_expression, operation::Operation},
+    compiler::{
+        chunk::Chunk, compile_expression, operation::Operation, value::Value,
+    },
     lexer::token::TokenData,
     parser::expression::Expression,
 };
@@ -42,6 +44,67 @@ pub fn compile_infix(
             chunk.add_operation(&Operation::Equal);
         }
 
+        TokenData::ExclamationEquals => {
+            compile_expression(chunk, left, is_function)?;
+            compile_expression(chunk, right, is_function)?;
+            chunk.add_operation(&Operation::Equal);
+            chunk.add_operation(&Operation::Negate);
+        }
+
+        // the right operand is only evaluated if the left one is true
+        TokenData::And => {
+            compile_expression(chunk, left, is_function)?;
+
+            chunk.add_operation(&Operation::JumpIfFalse(0));
+            let jump_to_false_address = chunk.get_latest_address();
+
+            compile_expression(chunk, right, is_function)?;
+
+            chunk.add_operation(&Operation::Jump(0));
+            let jump_to_end_address = chunk.get_latest_address();
+
+            let false_address = chunk.get_latest_address() + 1;
+            chunk.add_operation(&Operation::Push(Value::Bool(false)));
+
+            let end_address = chunk.get_latest_address() + 1;
+
+            chunk.update_operation(
+                jump_to_false_address,
+                &Operation::JumpIfFalse(false_address),
+            );
+            chunk.update_operation(
+                jump_to_end_address,
+                &Operation::Jump(end_address),
+            );
+        }
+
+        // the right operand is only evaluated if the left one is false
+        TokenData::Or => {
+            compile_expression(chunk, left, is_function)?;
+
+            chunk.add_operation(&Operation::JumpIfFalse(0));
+            let jump_to_right_address = chunk.get_latest_address();
+
+            chunk.add_operation(&Operation::Push(Value::Bool(true)));
+
+            chunk.add_operation(&Operation::Jump(0));
+            let jump_to_end_address = chunk.get_latest_address();
+
+            let right_address = chunk.get_latest_address() + 1;
+            compile_expression(chunk, right, is_function)?;
+
+            let end_address = chunk.get_latest_address() + 1;
+
+            chunk.update_operation(
+                jump_to_right_address,
+                &Operation::JumpIfFalse(right_address),
+            );
+            chunk.update_operation(
+                jump_to_end_address,
+                &Operation::Jump(end_address),
+            );
+        }
+
         TokenData::LessThan => {
             compile_expression(chunk, left, is_function)?;
             compile_expression(chunk, right, is_function)?;
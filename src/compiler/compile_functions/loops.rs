@@ -0,0 +1,182 @@
+use crate::{
+    compiler::{
+        chunk::{Chunk, ChunkLoop},
+        compile_expression,
+        operation::Operation,
+        value::Value,
+    },
+    parser::expression::Expression,
+};
+
+/// Update the jumps of the loop which was just compiled
+fn finish_loop(chunk: &mut Chunk, end_address: usize, next_address: usize) {
+    let chunk_loop = chunk.loops.pop().unwrap();
+
+    for address in chunk_loop.break_jumps {
+        chunk.update_operation(address, &Operation::Jump(end_address));
+    }
+
+    for address in chunk_loop.continue_jumps {
+        chunk.update_operation(address, &Operation::Jump(next_address));
+    }
+}
+
+fn start_loop(chunk: &mut Chunk) {
+    chunk.loops.push(ChunkLoop {
+        break_jumps: vec![],
+        continue_jumps: vec![],
+    });
+}
+
+pub fn compile_while(
+    chunk: &mut Chunk,
+    is_function: bool,
+    condition: &Expression,
+    body: &Expression,
+) -> Result<(), String> {
+    let start_address = chunk.code.len();
+
+    compile_expression(chunk, condition, is_function)?;
+
+    chunk.add_operation(&Operation::JumpIfFalse(0));
+    let jump_to_exit_address = chunk.get_latest_address();
+
+    start_loop(chunk);
+
+    compile_expression(chunk, body, is_function)?;
+    chunk.add_operation(&Operation::Pop);
+    chunk.add_operation(&Operation::Jump(start_address));
+
+    // a while loop which ends because of its condition evaluates to null,
+    // breaks push their own value and jump past this
+    let exit_address = chunk.code.len();
+    chunk.add_operation(&Operation::Push(Value::Null));
+
+    let end_address = chunk.code.len();
+
+    chunk.update_operation(
+        jump_to_exit_address,
+        &Operation::JumpIfFalse(exit_address),
+    );
+    finish_loop(chunk, end_address, start_address);
+
+    Ok(())
+}
+
+pub fn compile_loop(
+    chunk: &mut Chunk,
+    is_function: bool,
+    body: &Expression,
+) -> Result<(), String> {
+    let start_address = chunk.code.len();
+
+    start_loop(chunk);
+
+    compile_expression(chunk, body, is_function)?;
+    chunk.add_operation(&Operation::Pop);
+    chunk.add_operation(&Operation::Jump(start_address));
+
+    // the only way out of a loop is a break, which pushes the loop's value
+    let end_address = chunk.code.len();
+
+    finish_loop(chunk, end_address, start_address);
+
+    Ok(())
+}
+
+pub fn compile_for(
+    chunk: &mut Chunk,
+    is_function: bool,
+    variable: &String,
+    iterable: &Expression,
+    body: &Expression,
+) -> Result<(), String> {
+    // the iterable and the current index are kept in hidden variables, their
+    // names have spaces in them so they can't be used from the source code
+    compile_expression(chunk, iterable, is_function)?;
+    let iterable_slot = chunk.define_variable(&"for iterable".to_string());
+    chunk.add_operation(&Operation::define(iterable_slot));
+
+    chunk.add_operation(&Operation::Push(Value::Int(0)));
+    let index_slot = chunk.define_variable(&"for index".to_string());
+    chunk.add_operation(&Operation::define(index_slot));
+
+    // check if the index is still in bounds
+    let condition_address = chunk.code.len();
+
+    chunk.add_operation(&Operation::load(index_slot));
+    chunk.add_operation(&Operation::load(iterable_slot));
+    chunk.add_operation(&Operation::Length);
+    chunk.add_operation(&Operation::LessThan);
+
+    chunk.add_operation(&Operation::JumpIfFalse(0));
+    let jump_to_exit_address = chunk.get_latest_address();
+
+    // define the loop variable
+    chunk.add_operation(&Operation::load(iterable_slot));
+    chunk.add_operation(&Operation::load(index_slot));
+    chunk.add_operation(&Operation::Index);
+
+    let variable_slot = chunk.define_variable(variable);
+    chunk.add_operation(&Operation::define(variable_slot));
+
+    start_loop(chunk);
+
+    compile_expression(chunk, body, is_function)?;
+    chunk.add_operation(&Operation::Pop);
+
+    // go to the next element
+    let next_address = chunk.code.len();
+
+    chunk.add_operation(&Operation::load(index_slot));
+    chunk.add_operation(&Operation::Push(Value::Int(1)));
+    chunk.add_operation(&Operation::Add);
+    chunk.add_operation(&Operation::store(index_slot, vec![]));
+    chunk.add_operation(&Operation::Jump(condition_address));
+
+    let exit_address = chunk.code.len();
+    chunk.add_operation(&Operation::Push(Value::Null));
+
+    let end_address = chunk.code.len();
+
+    chunk.update_operation(
+        jump_to_exit_address,
+        &Operation::JumpIfFalse(exit_address),
+    );
+    finish_loop(chunk, end_address, next_address);
+
+    Ok(())
+}
+
+pub fn compile_break(
+    chunk: &mut Chunk,
+    is_function: bool,
+    value: &Option<Box<Expression>>,
+) -> Result<(), String> {
+    match value {
+        Some(value) => compile_expression(chunk, value, is_function)?,
+        None => chunk.add_operation(&Operation::Push(Value::Null)),
+    }
+
+    chunk.add_operation(&Operation::Jump(0));
+    let address = chunk.get_latest_address();
+
+    match chunk.loops.last_mut() {
+        Some(chunk_loop) => chunk_loop.break_jumps.push(address),
+        None => return Err("Cannot break outside of a loop".to_string()),
+    }
+
+    Ok(())
+}
+
+pub fn compile_continue(chunk: &mut Chunk) -> Result<(), String> {
+    chunk.add_operation(&Operation::Jump(0));
+    let address = chunk.get_latest_address();
+
+    match chunk.loops.last_mut() {
+        Some(chunk_loop) => chunk_loop.continue_jumps.push(address),
+        None => return Err("Cannot continue outside of a loop".to_string()),
+    }
+
+    Ok(())
+}
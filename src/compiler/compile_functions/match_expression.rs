@@ -0,0 +1,695 @@
+use std::{collections::HashMap, mem::discriminant};
+
+use crate::{
+    compiler::{
+        chunk::{Chunk, VariableSlot},
+        compile_expression,
+        operation::Operation,
+        value::Value,
+    },
+    parser::expression::{Expression, ExpressionData, MatchArm, Pattern},
+};
+
+/// A step from a value to the part of it a pattern is tested on
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum PathStep {
+    Field(String),
+    Index(usize),
+
+    // index counted from the end of the array, 1 is the last element
+    IndexFromEnd(usize),
+
+    // index counted from where the search with the given index found its
+    // group of elements
+    IndexFromFound { search: usize, index: usize },
+}
+
+/// The steps from the matched value to a part of it, an empty path is the
+/// matched value itself
+type Path = Vec<PathStep>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum TestKind<'a> {
+    Literal(&'a Expression),
+    Variant(String),
+
+    // whether the array's length is exactly count, or at least count if
+    // exact is false
+    Length { count: usize, exact: bool },
+
+    // whether the part is equal to the part at the other path, for names
+    // which are bound more than once in the same pattern
+    SameAs(Path),
+
+    // index of a search for the group of elements between two ...
+    Search(usize),
+}
+
+/// A check on the part of the matched value the path leads to, a pattern
+/// matches if all of its tests pass
+#[derive(Debug, Clone, PartialEq)]
+struct Test<'a> {
+    path: Path,
+    kind: TestKind<'a>,
+}
+
+/// Where a search starts looking for its group
+enum SearchStart {
+    Index(usize),
+
+    // right after the group found by an earlier search in the same array
+    AfterFound { search: usize, count: usize },
+}
+
+/// Looks for the first place in an array where the tests of a group of
+/// elements between two ... pass, and keeps its index in a hidden variable
+struct Search<'a> {
+    array: Path,
+    start: SearchStart,
+
+    // elements in the group, and after the last ... of the pattern
+    count: usize,
+    after: usize,
+
+    tests: Vec<Test<'a>>,
+    position_slot: VariableSlot,
+}
+
+struct Matcher<'a> {
+    is_function: bool,
+    value_slot: VariableSlot,
+    searches: Vec<Search<'a>>,
+}
+
+/// The tests which are left for an arm on the way through the decision tree
+struct Row<'a> {
+    arm: usize,
+    tests: Vec<Test<'a>>,
+}
+
+/// Turn a pattern into the tests it needs on the parts of the value, in an
+/// order where indexing or accessing a field is always checked to be valid
+/// first. The variables it binds are collected with the path to their value
+fn flatten_pattern<'a>(
+    chunk: &mut Chunk,
+    matcher: &mut Matcher<'a>,
+    path: Path,
+    pattern: &'a Pattern,
+    tests: &mut Vec<Test<'a>>,
+    bindings: &mut Vec<(String, Path)>,
+) {
+    let step = |step: PathStep| {
+        let mut part_path = path.clone();
+        part_path.push(step);
+        part_path
+    };
+
+    match pattern {
+        Pattern::Literal(literal) => tests.push(Test {
+            path,
+            kind: TestKind::Literal(literal),
+        }),
+
+        // a name which was already bound in this pattern needs to be equal
+        // to the earlier value
+        Pattern::Binding(name) => {
+            match bindings.iter().find(|(bound, _)| bound == name) {
+                Some((_, bound_path)) => tests.push(Test {
+                    kind: TestKind::SameAs(bound_path.clone()),
+                    path,
+                }),
+
+                None => bindings.push((name.clone(), path)),
+            }
+        }
+
+        Pattern::Struct { fields, .. } => {
+            for (field, pattern) in fields {
+                let field_path = step(PathStep::Field(field.clone()));
+                flatten_pattern(
+                    chunk, matcher, field_path, pattern, tests, bindings,
+                );
+            }
+        }
+
+        Pattern::EnumVariant {
+            variant, fields, ..
+        } => {
+            tests.push(Test {
+                path: path.clone(),
+                kind: TestKind::Variant(variant.clone()),
+            });
+
+            for (field, pattern) in fields {
+                let field_path = step(PathStep::Field(field.clone()));
+                flatten_pattern(
+                    chunk, matcher, field_path, pattern, tests, bindings,
+                );
+            }
+        }
+
+        Pattern::Tuple(elements) => {
+            for (i, element) in elements.iter().enumerate() {
+                let element_path = step(PathStep::Index(i));
+                flatten_pattern(
+                    chunk,
+                    matcher,
+                    element_path,
+                    element,
+                    tests,
+                    bindings,
+                );
+            }
+        }
+
+        Pattern::Array(groups) => {
+            let first = groups.first().unwrap();
+            let last = groups.last().unwrap();
+
+            // check the length first so that indexing the elements can't go
+            // out of bounds
+            tests.push(Test {
+                path: path.clone(),
+                kind: TestKind::Length {
+                    count: groups.iter().map(|group| group.len()).sum(),
+                    exact: groups.len() == 1,
+                },
+            });
+
+            for (i, element) in first.iter().enumerate() {
+                let element_path = step(PathStep::Index(i));
+                flatten_pattern(
+                    chunk,
+                    matcher,
+                    element_path,
+                    element,
+                    tests,
+                    bindings,
+                );
+            }
+
+            if groups.len() == 1 {
+                return;
+            }
+
+            let mut start = SearchStart::Index(first.len());
+
+            for group in &groups[1..groups.len() - 1] {
+                let search = matcher.searches.len();
+                matcher.searches.push(Search {
+                    array: path.clone(),
+                    start,
+                    count: group.len(),
+                    after: last.len(),
+                    tests: vec![],
+                    position_slot: chunk
+                        .define_variable(&"match position".to_string()),
+                });
+
+                let mut group_tests = vec![];
+                for (i, element) in group.iter().enumerate() {
+                    let element_path =
+                        step(PathStep::IndexFromFound { search, index: i });
+                    flatten_pattern(
+                        chunk,
+                        matcher,
+                        element_path,
+                        element,
+                        &mut group_tests,
+                        bindings,
+                    );
+                }
+
+                matcher.searches[search].tests = group_tests;
+                tests.push(Test {
+                    path: path.clone(),
+                    kind: TestKind::Search(search),
+                });
+
+                start = SearchStart::AfterFound {
+                    search,
+                    count: group.len(),
+                };
+            }
+
+            for (i, element) in last.iter().enumerate() {
+                let element_path = step(PathStep::IndexFromEnd(last.len() - i));
+                flatten_pattern(
+                    chunk,
+                    matcher,
+                    element_path,
+                    element,
+                    tests,
+                    bindings,
+                );
+            }
+        }
+    }
+}
+
+/// Push the part of the value in the slot that the step leads to
+fn compile_step(
+    chunk: &mut Chunk,
+    matcher: &Matcher,
+    slot: VariableSlot,
+    step: &PathStep,
+) {
+    chunk.add_operation(&Operation::load(slot));
+
+    match step {
+        PathStep::Field(field) => {
+            chunk.add_operation(&Operation::AccessField(field.clone()));
+        }
+
+        PathStep::Index(index) => {
+            chunk.add_operation(&Operation::Push(Value::Int(*index as i64)));
+            chunk.add_operation(&Operation::Index);
+        }
+
+        PathStep::IndexFromEnd(index) => {
+            chunk.add_operation(&Operation::load(slot));
+            chunk.add_operation(&Operation::Length);
+            chunk.add_operation(&Operation::Push(Value::Int(*index as i64)));
+            chunk.add_operation(&Operation::Substract);
+
+            chunk.add_operation(&Operation::Index);
+        }
+
+        PathStep::IndexFromFound { search, index } => {
+            let position_slot = matcher.searches[*search].position_slot;
+
+            chunk.add_operation(&Operation::load(position_slot));
+            chunk.add_operation(&Operation::Push(Value::Int(*index as i64)));
+            chunk.add_operation(&Operation::Add);
+
+            chunk.add_operation(&Operation::Index);
+        }
+    }
+}
+
+/// Get a hidden variable holding the part of the value the path leads to.
+/// Every part which has parts of its own tested is stored in one, so each
+/// test only takes one step from a variable instead of starting from the
+/// matched value. The slots are kept in parts, which only has the ones that
+/// were stored on the way to the code being compiled
+fn part_slot(
+    chunk: &mut Chunk,
+    matcher: &Matcher,
+    parts: &mut HashMap<Path, VariableSlot>,
+    path: &[PathStep],
+) -> VariableSlot {
+    if path.is_empty() {
+        return matcher.value_slot;
+    }
+
+    if let Some(slot) = parts.get(path) {
+        return *slot;
+    }
+
+    load_path(chunk, matcher, parts, path);
+    let slot = chunk.define_variable(&"match part".to_string());
+    chunk.add_operation(&Operation::define(slot));
+    parts.insert(path.to_vec(), slot);
+
+    slot
+}
+
+/// Push the part of the value the path leads to
+fn load_path(
+    chunk: &mut Chunk,
+    matcher: &Matcher,
+    parts: &mut HashMap<Path, VariableSlot>,
+    path: &[PathStep],
+) {
+    match path.split_last() {
+        None => chunk.add_operation(&Operation::load(matcher.value_slot)),
+
+        Some(_) if parts.contains_key(path) => {
+            chunk.add_operation(&Operation::load(parts[path]));
+        }
+
+        Some((step, parent)) => {
+            let parent_slot = part_slot(chunk, matcher, parts, parent);
+            compile_step(chunk, matcher, parent_slot, step);
+        }
+    }
+}
+
+/// Add a JumpIfFalse whose address is filled in once the code for a failed
+/// test is compiled
+fn add_test_jump(chunk: &mut Chunk, fail_jumps: &mut Vec<usize>) {
+    chunk.add_operation(&Operation::JumpIfFalse(0));
+    fail_jumps.push(chunk.get_latest_address());
+}
+
+fn compile_test(
+    chunk: &mut Chunk,
+    matcher: &Matcher,
+    parts: &mut HashMap<Path, VariableSlot>,
+    test: &Test,
+    fail_jumps: &mut Vec<usize>,
+) -> Result<(), String> {
+    match &test.kind {
+        TestKind::Literal(literal) => {
+            load_path(chunk, matcher, parts, &test.path);
+            compile_expression(chunk, literal, matcher.is_function)?;
+            chunk.add_operation(&Operation::Equal);
+        }
+
+        TestKind::Variant(variant) => {
+            load_path(chunk, matcher, parts, &test.path);
+            chunk.add_operation(&Operation::IsVariant(variant.clone()));
+        }
+
+        TestKind::Length { count, exact } => {
+            load_path(chunk, matcher, parts, &test.path);
+            chunk.add_operation(&Operation::Length);
+            chunk.add_operation(&Operation::Push(Value::Int(*count as i64)));
+            if *exact {
+                chunk.add_operation(&Operation::Equal);
+            } else {
+                chunk.add_operation(&Operation::GreaterThanOrEqual);
+            }
+        }
+
+        TestKind::SameAs(other_path) => {
+            load_path(chunk, matcher, parts, &test.path);
+            load_path(chunk, matcher, parts, other_path);
+            chunk.add_operation(&Operation::Equal);
+        }
+
+        TestKind::Search(search) => {
+            return compile_search(chunk, matcher, parts, *search, fail_jumps)
+        }
+    }
+
+    add_test_jump(chunk, fail_jumps);
+
+    Ok(())
+}
+
+/// Compile a loop which tries the tests of the search's group at each index
+/// of the array, starting from the first one it can be at. The test fails if
+/// the group would go past the elements after the last ...
+fn compile_search(
+    chunk: &mut Chunk,
+    matcher: &Matcher,
+    parts: &mut HashMap<Path, VariableSlot>,
+    search_index: usize,
+    fail_jumps: &mut Vec<usize>,
+) -> Result<(), String> {
+    let search = &matcher.searches[search_index];
+    let array_slot = part_slot(chunk, matcher, parts, &search.array);
+
+    match search.start {
+        SearchStart::Index(index) => {
+            chunk.add_operation(&Operation::Push(Value::Int(index as i64)));
+        }
+
+        SearchStart::AfterFound {
+            search: earlier,
+            count,
+        } => {
+            let earlier_slot = matcher.searches[earlier].position_slot;
+            chunk.add_operation(&Operation::load(earlier_slot));
+            chunk.add_operation(&Operation::Push(Value::Int(count as i64)));
+            chunk.add_operation(&Operation::Add);
+        }
+    }
+    chunk.add_operation(&Operation::define(search.position_slot));
+
+    let loop_start = chunk.code.len();
+
+    chunk.add_operation(&Operation::load(search.position_slot));
+    chunk.add_operation(&Operation::Push(Value::Int(search.count as i64)));
+    chunk.add_operation(&Operation::Add);
+    chunk.add_operation(&Operation::load(array_slot));
+    chunk.add_operation(&Operation::Length);
+    chunk.add_operation(&Operation::Push(Value::Int(search.after as i64)));
+    chunk.add_operation(&Operation::Substract);
+    chunk.add_operation(&Operation::LessThanOrEqual);
+    add_test_jump(chunk, fail_jumps);
+
+    // parts stored while trying one index don't belong to the next one
+    let mut group_parts = parts.clone();
+    let mut next_jumps: Vec<usize> = vec![];
+    for test in &search.tests {
+        compile_test(chunk, matcher, &mut group_parts, test, &mut next_jumps)?;
+    }
+
+    chunk.add_operation(&Operation::Jump(0));
+    let found_jump = chunk.get_latest_address();
+
+    let next_address = chunk.code.len();
+    for address in next_jumps {
+        chunk.update_operation(address, &Operation::JumpIfFalse(next_address));
+    }
+
+    chunk.add_operation(&Operation::load(search.position_slot));
+    chunk.add_operation(&Operation::Push(Value::Int(1)));
+    chunk.add_operation(&Operation::Add);
+    chunk.add_operation(&Operation::define(search.position_slot));
+    chunk.add_operation(&Operation::Jump(loop_start));
+
+    let found_address = chunk.code.len();
+    chunk.update_operation(found_jump, &Operation::Jump(found_address));
+
+    Ok(())
+}
+
+/// The result of a test which is known from the result of another one, if
+/// there is one
+fn implied_result(known: &Test, passed: bool, test: &Test) -> Option<bool> {
+    if known.path != test.path {
+        return None;
+    }
+
+    if known.kind == test.kind {
+        return Some(passed);
+    }
+
+    match (&known.kind, &test.kind) {
+        // a value can't be equal to two different literals, unless one is an
+        // int and the other a float
+        (TestKind::Literal(known_literal), TestKind::Literal(literal)) => {
+            if known_literal.data == literal.data {
+                return Some(passed);
+            }
+
+            let comparable = discriminant(&known_literal.data)
+                == discriminant(&literal.data)
+                || known_literal.data == ExpressionData::Null
+                || literal.data == ExpressionData::Null;
+
+            if passed && comparable {
+                Some(false)
+            } else {
+                None
+            }
+        }
+
+        (TestKind::Variant(_), TestKind::Variant(_)) if passed => Some(false),
+
+        (
+            TestKind::Length {
+                count: known_count,
+                exact: known_exact,
+            },
+            TestKind::Length { count, exact },
+        ) => match (passed, known_exact, exact) {
+            (true, true, true) => Some(false),
+            (true, true, false) => Some(known_count >= count),
+            (true, false, true) if count < known_count => Some(false),
+            (true, false, false) if count <= known_count => Some(true),
+            (false, false, _) if count >= known_count => Some(false),
+            _ => None,
+        },
+
+        _ => None,
+    }
+}
+
+/// The rows which are left once a test has passed or failed. The tests whose
+/// result it gives are taken out of them, and the rows with one which would
+/// fail are left out entirely
+fn rows_after_test<'a>(
+    rows: &[Row<'a>],
+    test: &Test,
+    passed: bool,
+) -> Vec<Row<'a>> {
+    rows.iter()
+        .filter_map(|row| {
+            let mut tests = vec![];
+
+            for row_test in &row.tests {
+                match implied_result(test, passed, row_test) {
+                    Some(true) => {}
+                    Some(false) => return None,
+                    None => tests.push(row_test.clone()),
+                }
+            }
+
+            Some(Row {
+                arm: row.arm,
+                tests,
+            })
+        })
+        .collect()
+}
+
+/// Compile the decision tree for the rows, in the order of their arms. The
+/// first test of the first row is done, and both of its outcomes continue
+/// with only the rows and tests that are still needed, so a test shared by
+/// several arms is only done once. A leaf jumps to the arm of its first row,
+/// its address is added to arm_jumps
+fn compile_decision_tree(
+    chunk: &mut Chunk,
+    matcher: &Matcher,
+    mut parts: HashMap<Path, VariableSlot>,
+    rows: Vec<Row>,
+    arm_jumps: &mut Vec<Vec<usize>>,
+    no_match_jumps: &mut Vec<usize>,
+) -> Result<(), String> {
+    let first = match rows.first() {
+        Some(first) => first,
+
+        None => {
+            chunk.add_operation(&Operation::Jump(0));
+            no_match_jumps.push(chunk.get_latest_address());
+            return Ok(());
+        }
+    };
+
+    let test = match first.tests.first() {
+        Some(test) => test.clone(),
+
+        None => {
+            chunk.add_operation(&Operation::Jump(0));
+            arm_jumps[first.arm].push(chunk.get_latest_address());
+            return Ok(());
+        }
+    };
+
+    let mut fail_jumps: Vec<usize> = vec![];
+    compile_test(chunk, matcher, &mut parts, &test, &mut fail_jumps)?;
+
+    compile_decision_tree(
+        chunk,
+        matcher,
+        parts.clone(),
+        rows_after_test(&rows, &test, true),
+        arm_jumps,
+        no_match_jumps,
+    )?;
+
+    let fail_address = chunk.code.len();
+    for address in fail_jumps {
+        chunk.update_operation(address, &Operation::JumpIfFalse(fail_address));
+    }
+
+    compile_decision_tree(
+        chunk,
+        matcher,
+        parts,
+        rows_after_test(&rows, &test, false),
+        arm_jumps,
+        no_match_jumps,
+    )
+}
+
+pub fn compile_match(
+    chunk: &mut Chunk,
+    is_function: bool,
+    value: &Expression,
+    arms: &Vec<MatchArm>,
+) -> Result<(), String> {
+    // the matched value is kept in a hidden variable, its slot is kept here
+    // instead of being looked up by name since a nested match would shadow it
+    compile_expression(chunk, value, is_function)?;
+    let value_slot = chunk.define_variable(&"match value".to_string());
+    chunk.add_operation(&Operation::define(value_slot));
+
+    let mut matcher = Matcher {
+        is_function,
+        value_slot,
+        searches: vec![],
+    };
+
+    let mut rows: Vec<Row> = vec![];
+    let mut arm_bindings: Vec<Vec<(String, Path)>> = vec![];
+
+    for (i, arm) in arms.iter().enumerate() {
+        let mut tests = vec![];
+        let mut bindings = vec![];
+
+        if let Some(pattern) = &arm.pattern {
+            flatten_pattern(
+                chunk,
+                &mut matcher,
+                vec![],
+                pattern,
+                &mut tests,
+                &mut bindings,
+            );
+        }
+
+        rows.push(Row { arm: i, tests });
+        arm_bindings.push(bindings);
+    }
+
+    let mut arm_jumps: Vec<Vec<usize>> = vec![vec![]; arms.len()];
+    let mut no_match_jumps: Vec<usize> = vec![];
+
+    compile_decision_tree(
+        chunk,
+        &matcher,
+        HashMap::new(),
+        rows,
+        &mut arm_jumps,
+        &mut no_match_jumps,
+    )?;
+
+    let mut end_jumps: Vec<usize> = vec![];
+
+    // the analyzer makes sure that matches are exhaustive, so this is only
+    // here to keep a value on the stack
+    if !no_match_jumps.is_empty() {
+        let no_match_address = chunk.code.len();
+        for address in no_match_jumps {
+            chunk.update_operation(address, &Operation::Jump(no_match_address));
+        }
+
+        chunk.add_operation(&Operation::Push(Value::Null));
+        chunk.add_operation(&Operation::Jump(0));
+        end_jumps.push(chunk.get_latest_address());
+    }
+
+    // the leaves of the tree can't share the variables an arm binds, so the
+    // arm defines them from the matched value before its body. The searches
+    // it needs have already found their groups on the way to it
+    for ((arm, jumps), bindings) in arms.iter().zip(arm_jumps).zip(arm_bindings)
+    {
+        let arm_address = chunk.code.len();
+        for address in jumps {
+            chunk.update_operation(address, &Operation::Jump(arm_address));
+        }
+
+        let mut parts = HashMap::new();
+        for (name, path) in bindings {
+            load_path(chunk, &matcher, &mut parts, &path);
+            let slot = chunk.define_variable(&name);
+            chunk.add_operation(&Operation::define(slot));
+        }
+
+        compile_expression(chunk, &arm.body, is_function)?;
+        chunk.add_operation(&Operation::Jump(0));
+        end_jumps.push(chunk.get_latest_address());
+    }
+
+    let end_address = chunk.code.len();
+    for address in end_jumps {
+        chunk.update_operation(address, &Operation::Jump(end_address));
+    }
+
+    Ok(())
+}
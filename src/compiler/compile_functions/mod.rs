@@ -1,7 +1,11 @@
+pub mod cast;
 pub mod function_call;
 pub mod function_definition;
 pub mod if_condition;
 pub mod infix;
+pub mod loops;
+pub mod match_expression;
+pub mod postfix;
 pub mod prefix;
 pub mod value;
 pub mod value_function_call;
@@ -0,0 +1,32 @@
+use crate::{
+    compiler::{
+        chunk::Chunk, compile_expression, operation::Operation, value::Value,
+    },
+    lexer::token::TokenData,
+    parser::expression::Expression,
+};
+
+pub fn compile_postfix(
+    chunk: &mut Chunk,
+    is_function: bool,
+    operator: &TokenData,
+    value: &Expression,
+) -> Result<(), String> {
+    compile_expression(chunk, value, is_function)?;
+
+    match operator {
+        TokenData::QuestionMark => {
+            chunk.add_operation(&Operation::Push(Value::Null));
+            chunk.add_operation(&Operation::Equal);
+            chunk.add_operation(&Operation::Negate);
+        }
+
+        TokenData::ExclamationMark => {
+            chunk.add_operation(&Operation::CheckNotNull);
+        }
+
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
@@ -11,7 +11,8 @@ pub fn compile_prefix(
     value: &Expression,
 ) -> Result<(), String> {
     match operator {
-        TokenData::Minus => {
+        // negating a boolean inverts it
+        TokenData::Minus | TokenData::Not => {
             compile_expression(chunk, value, is_function)?;
             chunk.add_operation(&Operation::Negate);
         }
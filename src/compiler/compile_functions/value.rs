@@ -25,52 +25,99 @@ pub fn compile_value(
         expression_pat!(ExpressionData::String(v)) => chunk
             .add_operation(&Operation::PushObject(Object::String(v.clone()))),
         expression_pat!(Char(v)) => {
-            chunk.add_operation(&Operation::Push(Value::Char(v.clone())))
+            chunk.add_operation(&Operation::Push(Value::Char(*v)))
         }
         expression_pat!(Int(v)) => {
-            chunk.add_operation(&Operation::Push(Value::Int(v.clone())))
+            chunk.add_operation(&Operation::Push(Value::Int(*v)))
         }
         expression_pat!(Float(v)) => {
-            chunk.add_operation(&Operation::Push(Value::Float(v.clone())))
+            chunk.add_operation(&Operation::Push(Value::Float(*v)))
         }
         expression_pat!(Bool(v)) => {
-            chunk.add_operation(&Operation::Push(Value::Bool(v.clone())))
+            chunk.add_operation(&Operation::Push(Value::Bool(*v)))
         }
 
         expression_pat!(Identifier(v)) => {
-            if is_function {
-                let function_index = chunk.function_index_from_name.get(v);
-                if function_index.is_some() {
-                    let function = Value::Function(*function_index.unwrap());
-                    chunk.add_operation(&Operation::Push(function));
-                } else {
-                    chunk.add_operation(&Operation::Load(v.clone()));
+            // functions defined in Kuon shadow native ones
+            let function = match chunk.function_index_from_name.get(v) {
+                Some(index) => Some(Value::Function(*index)),
+                None => chunk
+                    .native_function_index_from_name
+                    .get(v)
+                    .map(|index| Value::NativeFunction(*index)),
+            };
+            let slot = chunk.get_variable_slot(v);
+
+            // variables are looked up first, unless the identifier is known to
+            // be a function
+            match (function, slot) {
+                (Some(function), _) if is_function => {
+                    chunk.add_operation(&Operation::Push(function))
+                }
+
+                (_, Some(slot)) => chunk.add_operation(&Operation::load(slot)),
+
+                (Some(function), None) => {
+                    chunk.add_operation(&Operation::Push(function))
+                }
+
+                (None, None) => {
+                    return Err(format!("Unknown identifier: {}", v));
                 }
-            } else {
-                chunk.add_operation(&Operation::Load(v.clone()));
             }
         }
 
-        expression_pat!(FunctionDefinition {
-            name,
-            params,
-            body,
-            pre_parameter,
-            ..
+        expression_pat!(TypeFunction {
+            type_name,
+            function_name,
         }) => {
-            let index = compile_function_definition(
-                chunk,
-                is_function,
-                pre_parameter,
-                params,
-                body,
-                name,
-            )?;
-
-            chunk.add_operation(&Operation::Push(Value::Function(index)))
+            let index = chunk
+                .type_function_index_from_name
+                .get(type_name)
+                .and_then(|functions| functions.get(function_name));
+
+            match index {
+                Some(index) => chunk
+                    .add_operation(&Operation::Push(Value::Function(*index))),
+
+                None => {
+                    return Err(format!(
+                        "Unknown type function: {}#{}",
+                        type_name, function_name
+                    ));
+                }
+            }
+        }
+
+        definition @ expression_pat!(FunctionDefinition { captures, .. }) => {
+            let index =
+                compile_function_definition(chunk, is_function, definition)?;
+
+            // functions which don't capture anything don't need a closure
+            if captures.is_empty() {
+                chunk.add_operation(&Operation::Push(Value::Function(index)))
+            } else {
+                let mut capture_slots = vec![];
+                for capture in captures {
+                    match chunk.get_variable_slot(capture) {
+                        Some(slot) => capture_slots.push(slot),
+                        None => {
+                            return Err(format!(
+                                "Unknown identifier: {}",
+                                capture
+                            ))
+                        }
+                    }
+                }
+
+                chunk.add_operation(&Operation::MakeClosure {
+                    function: index,
+                    captures: capture_slots,
+                })
+            }
         }
 
-        expression_pat!(MakeStruct { fields, .. }) => {
+        expression_pat!(MakeStruct { name, fields }) => {
             for (name, value) in fields {
                 chunk.add_operation(&Operation::Push(Value::StructFieldName(
                     name.clone(),
@@ -79,7 +126,51 @@ pub fn compile_value(
                 compile_expression(chunk, value, is_function)?;
             }
 
-            chunk.add_operation(&Operation::MakeStruct(fields.len()));
+            // fields which were left out get their default values, the
+            // analyzer makes sure that all of them have one
+            let mut field_count = fields.len();
+
+            let defaults = match name {
+                Some(name) => {
+                    chunk.struct_defaults_from_name.get(name).cloned()
+                }
+                None => None,
+            };
+
+            for (name, default) in defaults.unwrap_or_default() {
+                if fields.contains_key(&name) {
+                    continue;
+                }
+
+                chunk.add_operation(&Operation::Push(Value::StructFieldName(
+                    name.clone(),
+                )));
+
+                compile_expression(chunk, &default, is_function)?;
+                field_count += 1;
+            }
+
+            chunk.add_operation(&Operation::MakeStruct(field_count));
+        }
+
+        expression_pat!(MakeEnum {
+            variant,
+            fields,
+            ..
+        }) => {
+            // the fields are pushed the same way as a struct's
+            for (name, value) in fields {
+                chunk.add_operation(&Operation::Push(Value::StructFieldName(
+                    name.clone(),
+                )));
+
+                compile_expression(chunk, value, is_function)?;
+            }
+
+            chunk.add_operation(&Operation::MakeEnum {
+                variant: variant.clone(),
+                field_count: fields.len(),
+            });
         }
 
         expression_pat!(FieldAccess { expression, field }) => {
@@ -88,6 +179,38 @@ pub fn compile_value(
             chunk.add_operation(&Operation::AccessField(field.clone()));
         }
 
+        expression_pat!(MakeArray { elements }) => {
+            for element in elements {
+                compile_expression(chunk, element, is_function)?;
+            }
+
+            chunk.add_operation(&Operation::MakeArray(elements.len()));
+        }
+
+        expression_pat!(MakeTuple { elements }) => {
+            for element in elements {
+                compile_expression(chunk, element, is_function)?;
+            }
+
+            chunk.add_operation(&Operation::MakeTuple(elements.len()));
+        }
+
+        expression_pat!(MakeMap { entries }) => {
+            for (key, value) in entries {
+                compile_expression(chunk, key, is_function)?;
+                compile_expression(chunk, value, is_function)?;
+            }
+
+            chunk.add_operation(&Operation::MakeMap(entries.len()));
+        }
+
+        expression_pat!(IndexAccess { expression, index }) => {
+            compile_expression(chunk, expression, is_function)?;
+            compile_expression(chunk, index, is_function)?;
+
+            chunk.add_operation(&Operation::Index);
+        }
+
         _ => unreachable!(),
     }
 
@@ -21,6 +21,36 @@ pub fn compile_value_function_call(
     }
 
     let key = (function_name.clone(), pre_argument_type.clone());
+
+    // functions defined on an interface type itself are called like any other
+    // value function, the ones the interface requires are looked up at runtime
+    if let (Type::Interface(interface_name), None) = (
+        pre_argument_type,
+        chunk.value_function_index_from_name.get(&key),
+    ) {
+        let function = chunk.interface_functions_from_name[interface_name]
+            .iter()
+            .find(|f| f.name == *function_name)
+            .unwrap();
+
+        let this_arguments = function
+            .param_types
+            .iter()
+            .enumerate()
+            .filter(|(_, type_)| **type_ == Type::This)
+            .map(|(i, _)| i)
+            .collect();
+
+        chunk.add_operation(&Operation::CallInterfaceFunction {
+            name: function_name.clone(),
+            argument_count: arguments.len(),
+            this_arguments,
+            returns_this: function.return_type == Type::This,
+        });
+
+        return Ok(());
+    }
+
     let function_index = chunk.value_function_index_from_name[&key];
 
     chunk.add_operation(&Operation::Push(Value::Function(function_index)));
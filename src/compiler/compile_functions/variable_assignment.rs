@@ -1,5 +1,9 @@
 use crate::{
-    compiler::{chunk::Chunk, compile_expression, operation::Operation},
+    compiler::{
+        chunk::Chunk, compile_expression, operation::Operation, value::Value,
+    },
+    expression,
+    lexer::token::TokenData,
     parser::expression::{Expression, VariableAccessor},
 };
 
@@ -10,11 +14,128 @@ pub fn compile_variable_assignment(
     accessors: &Vec<VariableAccessor>,
     value: &Expression,
 ) -> Result<(), String> {
-    compile_expression(chunk, value, is_function)?;
-    chunk.add_operation(&Operation::Store {
-        name: name.clone(),
-        accessors: accessors.clone(),
-    });
+    // the variable has to exist, this is ensured by the analyzer
+    let slot = chunk.get_variable_slot(name).unwrap();
+
+    let last_index = accessors
+        .iter()
+        .rposition(|a| matches!(a, VariableAccessor::Index(_)));
+
+    match last_index {
+        None => {
+            compile_expression(chunk, value, is_function)?;
+            chunk.add_operation(&Operation::store(slot, accessors.clone()));
+        }
+
+        // arrays are objects, so everything up to the last index can be
+        // loaded like a regular value and the element is then updated in the
+        // heap
+        Some(last_index) => {
+            chunk.add_operation(&Operation::load(slot));
+
+            for accessor in &accessors[..last_index] {
+                match accessor {
+                    VariableAccessor::StructField(field) => chunk
+                        .add_operation(&Operation::AccessField(field.clone())),
+
+                    VariableAccessor::Index(index) => {
+                        compile_expression(chunk, index, is_function)?;
+                        chunk.add_operation(&Operation::Index);
+                    }
+                }
+            }
+
+            match &accessors[last_index] {
+                VariableAccessor::Index(index) => {
+                    compile_expression(chunk, index, is_function)?
+                }
+
+                _ => unreachable!(),
+            }
+
+            compile_expression(chunk, value, is_function)?;
+
+            chunk.add_operation(&Operation::StoreIndex {
+                accessors: accessors[last_index + 1..].to_vec(),
+            });
+        }
+    }
+
+    // assignments evaluate to null
+    chunk.add_operation(&Operation::Push(Value::Null));
 
     Ok(())
 }
+
+/// a[i()] += b is compiled like a[index] = a[index] + b, where index is a
+/// hidden variable holding the result of i(), so that it's only called once
+pub fn compile_compound_assignment(
+    chunk: &mut Chunk,
+    is_function: bool,
+    name: &String,
+    accessors: &Vec<VariableAccessor>,
+    operator: &TokenData,
+    value: &Expression,
+) -> Result<(), String> {
+    let line = value.line;
+
+    // the location which is assigned to, read back as an expression
+    let mut target = expression!(Identifier(name.clone()), line);
+    let mut target_accessors = vec![];
+
+    for (i, accessor) in accessors.iter().enumerate() {
+        let accessor = match accessor {
+            VariableAccessor::StructField(field) => {
+                target = expression!(
+                    FieldAccess {
+                        expression: Box::new(target),
+                        field: field.clone(),
+                    },
+                    line
+                );
+
+                accessor.clone()
+            }
+
+            // the name has a space in it so it can't be used from the source
+            // code
+            VariableAccessor::Index(index) => {
+                let index_name = format!("compound index {}", i);
+
+                compile_expression(chunk, index, is_function)?;
+                let slot = chunk.define_variable(&index_name);
+                chunk.add_operation(&Operation::define(slot));
+
+                let index = Box::new(expression!(Identifier(index_name), line));
+                target = expression!(
+                    IndexAccess {
+                        expression: Box::new(target),
+                        index: index.clone(),
+                    },
+                    line
+                );
+
+                VariableAccessor::Index(index)
+            }
+        };
+
+        target_accessors.push(accessor);
+    }
+
+    let new_value = expression!(
+        Infix {
+            left: Box::new(target),
+            operator: operator.clone(),
+            right: Box::new(value.clone()),
+        },
+        line
+    );
+
+    compile_variable_assignment(
+        chunk,
+        is_function,
+        name,
+        &target_accessors,
+        &new_value,
+    )
+}
@@ -1,5 +1,7 @@
 use crate::{
-    compiler::{chunk::Chunk, compile_expression, operation::Operation},
+    compiler::{
+        chunk::Chunk, compile_expression, operation::Operation, value::Value,
+    },
     parser::expression::Expression,
 };
 
@@ -10,10 +12,40 @@ pub fn compile_variable_definition(
     value: &Expression,
 ) -> Result<(), String> {
     compile_expression(chunk, value, is_function)?;
-    chunk.add_operation(&Operation::Store {
-        name: name.clone(),
-        accessors: vec![],
-    });
+
+    let slot = chunk.define_variable(name);
+    chunk.add_operation(&Operation::define(slot));
+
+    // definitions evaluate to null
+    chunk.add_operation(&Operation::Push(Value::Null));
+
+    Ok(())
+}
+
+pub fn compile_tuple_definition(
+    chunk: &mut Chunk,
+    is_function: bool,
+    names: &Vec<String>,
+    value: &Expression,
+) -> Result<(), String> {
+    // the tuple is kept in a hidden variable while the elements are taken out
+    // of it, its name has a space in it so it can't be used from the source
+    // code
+    compile_expression(chunk, value, is_function)?;
+    let tuple_slot = chunk.define_variable(&"destructured tuple".to_string());
+    chunk.add_operation(&Operation::define(tuple_slot));
+
+    for (i, name) in names.iter().enumerate() {
+        chunk.add_operation(&Operation::load(tuple_slot));
+        chunk.add_operation(&Operation::Push(Value::Int(i as i64)));
+        chunk.add_operation(&Operation::Index);
+
+        let slot = chunk.define_variable(name);
+        chunk.add_operation(&Operation::define(slot));
+    }
+
+    // definitions evaluate to null
+    chunk.add_operation(&Operation::Push(Value::Null));
 
     Ok(())
 }
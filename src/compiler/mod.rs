@@ -1,12 +1,13 @@
 use chunk::Chunk;
 use compile_functions::{
-    function_call::compile_function_call, function_definition::compile_function_definition, if_condition::compile_if_condition, infix::compile_infix, prefix::compile_prefix, value::compile_value, value_function_call::compile_value_function_call, variable_assignment::compile_variable_assignment, variable_definition::compile_variable_definition
+    cast::compile_cast, function_call::compile_function_call, function_definition::compile_function_definition, if_condition::compile_if_condition, infix::compile_infix, loops::{compile_break, compile_continue, compile_for, compile_loop, compile_while}, match_expression::compile_match, postfix::compile_postfix, prefix::compile_prefix, value::compile_value, value_function_call::compile_value_function_call, variable_assignment::{compile_compound_assignment, compile_variable_assignment}, variable_definition::{compile_tuple_definition, compile_variable_definition}
 };
 use operation::Operation;
 
 use crate::{
     expression_pat,
     parser::expression::{Expression, ExpressionData},
+    vm::native_function::NativeFunctions,
 };
 
 pub mod chunk;
@@ -15,12 +16,41 @@ pub mod operation;
 pub mod value;
 
 pub fn compile_source(ast: &Expression) -> Result<Chunk, String> {
+    compile_source_with_natives(ast, &NativeFunctions::new())
+}
+
+/// Compile the source code, which can call the given native functions
+pub fn compile_source_with_natives(ast: &Expression, natives: &NativeFunctions) -> Result<Chunk, String> {
     let mut chunk = Chunk::new();
 
+    for (index, native) in natives.functions.iter().enumerate() {
+        chunk.native_function_index_from_name.insert(native.name.clone(), index);
+    }
+    chunk.native_functions = natives.functions.clone();
+
+    compile_top_level(&mut chunk, ast)?;
+
+    Ok(chunk)
+}
+
+/// Compile source code which comes after the code of the previous chunk. The
+/// new chunk keeps all of the previous chunk's functions and globals, so both
+/// can be run by the same VM
+pub fn compile_source_after(previous: &Chunk, ast: &Expression) -> Result<Chunk, String> {
+    let mut chunk = Chunk {
+        code: vec![],
+        lines: vec![],
+        ..previous.clone()
+    };
+
+    compile_top_level(&mut chunk, ast)?;
+
+    Ok(chunk)
+}
+
+fn compile_top_level(chunk: &mut Chunk, ast: &Expression) -> Result<(), String> {
     if let expression_pat!(ExpressionData::Block { expressions }) = ast {
-        for expression in expressions {
-            compile_expression(&mut chunk, expression, false)?;
-        }
+        compile_expressions(chunk, expressions, false)?;
     } else {
         return Err(format!(
             "Source code is expected to be a block expression"
@@ -29,7 +59,21 @@ pub fn compile_source(ast: &Expression) -> Result<Chunk, String> {
 
     chunk.add_operation(&Operation::Halt);
 
-    Ok(chunk)
+    Ok(())
+}
+
+/// Compile expressions one after the other, only keeping the value of the last
+/// one on the stack (every expression pushes exactly one value)
+fn compile_expressions(chunk: &mut Chunk, expressions: &Vec<Expression>, is_function: bool) -> Result<(), String> {
+    for (i, expression) in expressions.iter().enumerate() {
+        compile_expression(chunk, expression, is_function)?;
+
+        if i != expressions.len() - 1 {
+            chunk.add_operation(&Operation::Pop);
+        }
+    }
+
+    Ok(())
 }
 
 fn compile_expression(
@@ -41,6 +85,11 @@ fn compile_expression(
 ) -> Result<(), String> {
     use ExpressionData::*;
 
+    // all operations added while compiling this expression get its line,
+    // except for the ones added by its inner expressions
+    let previous_line = chunk.current_line;
+    chunk.current_line = expression.line;
+
     match expression {
         value @ Expression {data: ExpressionData::Null
         | ExpressionData::String(_)
@@ -49,10 +98,16 @@ fn compile_expression(
         | ExpressionData::Float(_)
         | ExpressionData::Bool(_)
         | ExpressionData::Identifier(_)
+        | ExpressionData::TypeFunction { .. }
         // function definitions without names are closures, so they are handled like all values
         | ExpressionData::FunctionDefinition { name: None, .. }
         | ExpressionData::MakeStruct { .. }
-        | ExpressionData::FieldAccess { .. }, ..} => {
+        | ExpressionData::MakeEnum { .. }
+        | ExpressionData::FieldAccess { .. }
+        | ExpressionData::MakeArray { .. }
+        | ExpressionData::MakeTuple { .. }
+        | ExpressionData::MakeMap { .. }
+        | ExpressionData::IndexAccess { .. }, ..} => {
             compile_value(chunk, is_function, value)?
         }
 
@@ -66,14 +121,11 @@ fn compile_expression(
             right,
         }) => compile_infix(chunk, is_function, left, operator, right)?,
 
-        // no Postfix expressions yet
-        expression_pat!(Postfix { .. }) => {}
+        expression_pat!(Cast { value, type_, value_type }) => compile_cast(chunk, is_function, value, type_, value_type)?,
 
-        expression_pat!(Block { expressions }) => {
-            for expression in expressions {
-                compile_expression(chunk, expression, is_function)?;
-            }
-        }
+        expression_pat!(Postfix { value, operator }) => compile_postfix(chunk, is_function, operator, value)?,
+
+        expression_pat!(Block { expressions }) => compile_expressions(chunk, expressions, is_function)?,
 
         expression_pat!(IfCondition {
             condition,
@@ -81,30 +133,69 @@ fn compile_expression(
             else_branch,
         }) => compile_if_condition(chunk, is_function, condition, true_branch, else_branch)?,
 
+        expression_pat!(Match { value, arms }) => compile_match(chunk, is_function, value, arms)?,
+
+        expression_pat!(While { condition, body }) => compile_while(chunk, is_function, condition, body)?,
+
+        expression_pat!(Loop { body }) => compile_loop(chunk, is_function, body)?,
+
+        expression_pat!(For { variable, iterable, body }) => compile_for(chunk, is_function, variable, iterable, body)?,
+
+        expression_pat!(Break { value }) => compile_break(chunk, is_function, value)?,
+
+        expression_pat!(Continue) => compile_continue(chunk)?,
+
         expression_pat!(VariableDefinition { name, value, .. }) => {
             compile_variable_definition(chunk, is_function, name, value)?
         }
 
+        expression_pat!(TupleDefinition { names, value, .. }) => {
+            compile_tuple_definition(chunk, is_function, names, value)?
+        }
+
         expression_pat!(VariableAssignment { name, accessors, value }) => {
             compile_variable_assignment(chunk, is_function, name, accessors, value)?
         }
 
-        expression_pat!(FunctionDefinition {
-            params, body, name, pre_parameter, ..
-        }) => {
-            compile_function_definition(chunk, is_function, pre_parameter, params, body, name)?;
-            ()
+        expression_pat!(CompoundAssignment { name, accessors, operator, value }) => {
+            compile_compound_assignment(chunk, is_function, name, accessors, operator, value)?
         }
 
-        expression_pat!(ExpressionData::StructDefinition { .. }) => {}
+        definition @ expression_pat!(FunctionDefinition { .. }) => {
+            compile_function_definition(chunk, is_function, definition)?;
+            chunk.add_operation(&Operation::Push(value::Value::Null));
+        }
+
+        expression_pat!(ExpressionData::StructDefinition { name, defaults, .. }) => {
+            if let Some(name) = name {
+                chunk.struct_defaults_from_name.insert(name.clone(), defaults.clone());
+            }
+            chunk.add_operation(&Operation::Push(value::Value::Null));
+        }
+
+        expression_pat!(ExpressionData::EnumDefinition { .. } | ExpressionData::TypeAlias { .. }) => chunk.add_operation(&Operation::Push(value::Value::Null)),
+
+        expression_pat!(InterfaceDefinition { name, functions }) => {
+            chunk.interface_functions_from_name.insert(name.clone(), functions.clone());
+            chunk.add_operation(&Operation::Push(value::Value::Null));
+        }
+
+        expression_pat!(VariantCast { value, variant, .. }) => {
+            compile_expression(chunk, value, is_function)?;
+            chunk.add_operation(&Operation::CheckVariant(variant.clone()));
+        }
         
-        expression_pat!(FunctionCall { function, arguments }) => compile_function_call(chunk, is_function, function, arguments)?,
+        expression_pat!(FunctionCall { function, arguments, .. }) => compile_function_call(chunk, is_function, function, arguments)?,
 
-        expression_pat!(ValueFunctionCall { pre_argument, function_name, arguments, pre_argument_type }) => compile_value_function_call(chunk, is_function, &pre_argument, function_name, arguments, pre_argument_type.as_ref().unwrap())?,
+        expression_pat!(ValueFunctionCall { pre_argument, function_name, arguments, pre_argument_type, .. }) => compile_value_function_call(chunk, is_function, &pre_argument, function_name, arguments, pre_argument_type.as_ref().unwrap())?,
+
+        expression_pat!(DefaultArgument) => chunk.add_operation(&Operation::Push(value::Value::DefaultArgument)),
 
         // this should be unreachable unless I seriously mess something up
         expression_pat!(Type { .. }) => unreachable!(),
     }
 
+    chunk.current_line = previous_line;
+
     Ok(())
 }
@@ -1,6 +1,9 @@
-use crate::parser::expression::VariableAccessor;
+use crate::parser::{expression::VariableAccessor, r#type::Type};
 
-use super::value::{Object, Value};
+use super::{
+    chunk::VariableSlot,
+    value::{Object, Value},
+};
 
 #[derive(Debug, Clone)]
 pub enum Operation {
@@ -13,19 +16,52 @@ pub enum Operation {
     // push a Value::ObjectRef to the stack
     PushObject(Object),
 
-    // Pop 1 value from the stack and store it in a variable with the given
-    // name (either creates the variable or overwrites its value).
+    // Pop 1 value from the stack and store it in the local variable with the
+    // given slot (either creates the variable or overwrites its value).
     // If accessors is not empty, they'll be traced one by one and the value
     // will be stored in the final accessor's location (this of course only
     // works if the variable already exists and is of the appropriate type
     // that contains all the right accessors - this is ensured by the
     // analyzer, same as everything else)
-    Store {
-        name: String,
+    StoreLocal {
+        slot: u16,
+        accessors: Vec<VariableAccessor>,
+    },
+    // load value of the local variable with the given slot and push it to the
+    // stack
+    LoadLocal(u16),
+
+    // Pop 1 value from the stack and put it in the local variable with the
+    // given slot. Unlike StoreLocal, this never changes the object the
+    // variable was referencing before, which is what's needed when a
+    // variable definition is executed again (for example in a loop)
+    DefineLocal(u16),
+
+    // same as StoreLocal, LoadLocal and DefineLocal, except for global
+    // variables
+    StoreGlobal {
+        slot: u16,
+        accessors: Vec<VariableAccessor>,
+    },
+    LoadGlobal(u16),
+    DefineGlobal(u16),
+
+    // same as StoreLocal and LoadLocal, except for variables captured by the
+    // closure which is executing
+    StoreUpvalue {
+        slot: u16,
         accessors: Vec<VariableAccessor>,
     },
-    // load value of the varable with the given name and push it to the stack
-    Load(String),
+    LoadUpvalue(u16),
+
+    // Create a closure for the function with the given index and push it to
+    // the stack. The captured variables are turned into shared objects first
+    // (if they aren't already), so the closure and the function which
+    // defined it both see the changes made by the other one
+    MakeClosure {
+        function: usize,
+        captures: Vec<VariableSlot>,
+    },
 
     // pop 2 values from the stack, add them and push that to the stack
     Add,
@@ -35,7 +71,8 @@ pub enum Operation {
     Multiply,
     // pop 2 values from the stack, divide them and push that to the stack
     Divide,
-    // pop 1 value from the stack, negate it and push that to the stack
+    // pop 1 value from the stack, negate it (or invert it if it's a boolean)
+    // and push that to the stack
     Negate,
 
     // pop 2 values from the stack and push a boolean value saying whether
@@ -54,15 +91,47 @@ pub enum Operation {
     // the first one is greater than or equal to the other
     GreaterThanOrEqual,
 
+    // pop 1 string or array from the stack and push the number of
+    // characters or elements in it
+    Length,
+    // pop an index and a string, array or map from the stack and push the
+    // character or element at that index (or the value stored under that key
+    // in the map, which is null if there isn't one)
+    Index,
+    // pop a value, an index and an array or map from the stack and store the
+    // value in it at that index. If accessors is not empty, the element is
+    // traced through them like with StoreLocal and the value is stored in the
+    // final accessor's location instead
+    StoreIndex {
+        accessors: Vec<VariableAccessor>,
+    },
+
     // jump to the given address in the code
     Jump(usize),
     // pop 1 value from the stack and jump to the given address in the code if
     // the value is true
     JumpIfFalse(usize),
 
-    // Pop 1 Value::Function and N values for each function parameter, then
-    // call the function
+    // Pop 1 Value::Function (or Value::Closure) and call the function. The N values for each
+    // function parameter stay on the stack and become the function's first
+    // locals
     Call,
+    // Call a function of an interface. The pre-argument is below the given
+    // number of arguments on the stack and is a Value::Any, it's replaced
+    // with the value inside of it and the value function with the given name
+    // is looked up by the value's type. The arguments at the indices in
+    // this_arguments are also Value::Any, and they're replaced the same way
+    // once they're checked to have the same type. If the function returns
+    // this, its return value is put back in a Value::Any of the same type
+    CallInterfaceFunction {
+        name: String,
+        argument_count: usize,
+        this_arguments: Vec<usize>,
+        returns_this: bool,
+    },
+    // Pop the return value, discard the current function's call frame and
+    // push the return value to the calling frame's stack
+    Return,
 
     // Pop all struct fields from the stack and push a Value::Struct. The
     // given number is the number of fields in the struct.
@@ -83,9 +152,89 @@ pub enum Operation {
     //   ]))
     MakeStruct(usize),
 
+    // Same as MakeStruct, except that it pushes a Value::Enum of the given
+    // variant
+    MakeEnum {
+        variant: String,
+        field_count: usize,
+    },
+
+    // Check that the value on top of the stack is a Value::Enum of the given
+    // variant without popping it, giving a runtime error if it isn't
+    CheckVariant(String),
+
+    // Check that the value on top of the stack isn't null without popping it,
+    // giving a runtime error if it is
+    CheckNotNull,
+
+    // Pop 1 value from the stack and push a boolean value saying whether it's
+    // a Value::Enum of the given variant
+    IsVariant(String),
+
+    // Pop 1 value from the stack and push a boolean value saying whether it's
+    // a Value::DefaultArgument
+    IsDefaultArgument,
+
     // Pop Value::Struct from stack and push the Value of the given field
     AccessField(String),
 
+    // Pop 1 value from the stack and push a Value::Any which contains it and
+    // the given type
+    ToAny(Type),
+
+    // Pop 1 value from the stack, convert it to the given type and push the
+    // result. If the value is a Value::Any, its type has to match the given
+    // one instead, giving a runtime error if it doesn't
+    Cast(Type),
+
+    // Pop the given number of values from the stack and push an object ref
+    // to a new Object::Array which contains them (in the order they were
+    // pushed in)
+    MakeArray(usize),
+
+    // Pop the given number of values from the stack and push a Value::Tuple
+    // which contains them (in the order they were pushed in)
+    MakeTuple(usize),
+
+    // Pop the given number of key-value pairs from the stack (each key is
+    // pushed before its value) and push an object ref to a new Object::Map
+    // which contains them
+    MakeMap(usize),
+
     // halt execution
     Halt,
 }
+
+impl Operation {
+    pub fn store(slot: VariableSlot, accessors: Vec<VariableAccessor>) -> Self {
+        match slot {
+            VariableSlot::Local(slot) => {
+                Operation::StoreLocal { slot, accessors }
+            }
+            VariableSlot::Global(slot) => {
+                Operation::StoreGlobal { slot, accessors }
+            }
+            VariableSlot::Upvalue(slot) => {
+                Operation::StoreUpvalue { slot, accessors }
+            }
+        }
+    }
+
+    pub fn define(slot: VariableSlot) -> Self {
+        match slot {
+            VariableSlot::Local(slot) => Operation::DefineLocal(slot),
+            VariableSlot::Global(slot) => Operation::DefineGlobal(slot),
+
+            // captured variables are always defined by an enclosing function
+            VariableSlot::Upvalue(_) => unreachable!(),
+        }
+    }
+
+    pub fn load(slot: VariableSlot) -> Self {
+        match slot {
+            VariableSlot::Local(slot) => Operation::LoadLocal(slot),
+            VariableSlot::Global(slot) => Operation::LoadGlobal(slot),
+            VariableSlot::Upvalue(slot) => Operation::LoadUpvalue(slot),
+        }
+    }
+}
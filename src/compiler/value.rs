@@ -1,12 +1,41 @@
 use std::collections::HashMap;
 
+use crate::parser::r#type::Type;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Object {
     String(String),
 
+    // arrays are always behind an object ref, so copying an array value only
+    // copies the reference and every copy sees the changes made through the
+    // others
+    Array(Vec<Value>),
+
+    // same as arrays, maps are always behind an object ref
+    Map(HashMap<MapKey, Value>),
+
     // this is for when you want to pass a type like an int by reference in a
     // function
     Value(Value),
+
+    // a function together with the variables it captured from its enclosing
+    // functions. Each captured variable is an object ref to an Object::Value
+    // which is shared with the function that defined it, so assigning to it
+    // from either of them is seen by both
+    Closure {
+        function: usize,
+        upvalues: Vec<Value>,
+    },
+}
+
+// The values which can be used as map keys. Strings are stored by their
+// content instead of by reference, so two equal strings are the same key
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MapKey {
+    String(String),
+    Int(i64),
+    Char(char),
+    Bool(bool),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -24,8 +53,33 @@ pub enum Value {
     // Chunk struct
     Function(usize),
 
+    // the usize parameter is an index into the native_functions Vec field of
+    // the top level Chunk
+    NativeFunction(usize),
+
+    // the usize parameter is the index of an Object::Closure in the VM's heap
+    Closure(usize),
+
     Struct(HashMap<String, Value>),
 
+    // a value of an enum, which has the fields of its variant. The analyzer
+    // makes sure the variant belongs to the right enum, so its name isn't
+    // stored
+    Enum {
+        variant: String,
+        fields: HashMap<String, Value>,
+    },
+
+    // tuples are copied like structs, they aren't objects
+    Tuple(Vec<Value>),
+
+    // a value stored in an any, together with the type it had before so that
+    // casting it back can be checked
+    Any {
+        type_: Type,
+        value: Box<Value>,
+    },
+
     // uhhh the reason I need this is that the way my bytecode compiler works
     // for now is that I call a function on an expression and that function
     // adds a Push operation to the chunk, meaning I can't just extract the
@@ -38,4 +92,8 @@ pub enum Value {
     // collected, so I switched to this instead.
     // (please don't hate me, I promise I'll improve on this)
     StructFieldName(String),
+
+    // passed in place of an argument which was left out, so that the function
+    // knows to compute the default value of that parameter itself
+    DefaultArgument,
 }
@@ -0,0 +1,39 @@
+use crate::{
+    analyzer::analyzer_error::AnalyzerError, parser::parser_error::ParserError,
+    vm::runtime_error::RuntimeError,
+};
+
+/// Any error which can happen while using the Engine, from parsing the source
+/// code all the way to running it
+#[derive(Debug)]
+pub enum KuonError {
+    Parser(ParserError),
+    Analyzer(AnalyzerError),
+    Compiler(String),
+    Runtime(RuntimeError),
+
+    // call_function was given the name of a function which doesn't exist
+    UnknownFunction(String),
+
+    // the arguments given to call_function don't match the function's
+    // signature
+    InvalidArguments(String),
+}
+
+impl From<ParserError> for KuonError {
+    fn from(error: ParserError) -> Self {
+        KuonError::Parser(error)
+    }
+}
+
+impl From<AnalyzerError> for KuonError {
+    fn from(error: AnalyzerError) -> Self {
+        KuonError::Analyzer(error)
+    }
+}
+
+impl From<RuntimeError> for KuonError {
+    fn from(error: RuntimeError) -> Self {
+        KuonError::Runtime(error)
+    }
+}
@@ -0,0 +1,294 @@
+use kuon_error::KuonError;
+use util::result_matches_type;
+
+use crate::{
+    analyzer::{
+        self,
+        analyzer_error::AnalyzerError,
+        env::{Environment, EnvironmentFunction},
+    },
+    compiler::{self, chunk::Chunk, value::Value},
+    lexer::Lexer,
+    parser::{self, r#type::Type},
+    vm::{
+        execution_result::ExecutionResult, native_function::NativeFunction, Vm,
+    },
+};
+
+pub mod kuon_error;
+mod util;
+
+/// Everything needed to run Kuon code from a host program. Functions, structs
+/// and global variables defined by one piece of code stay around, so code
+/// which is evaluated afterwards (or the host itself) can still use them
+pub struct Engine {
+    // analyzer environment with everything defined at the top level so far
+    env: Environment<'static>,
+
+    // the latest compiled chunk, which has all the functions and globals
+    // defined so far
+    chunk: Chunk,
+
+    vm: Vm,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Self {
+            env: Environment::new(),
+            chunk: Chunk::new(),
+            vm: Vm::new(),
+        }
+    }
+
+    pub fn vm(&self) -> &Vm {
+        &self.vm
+    }
+
+    pub fn vm_mut(&mut self) -> &mut Vm {
+        &mut self.vm
+    }
+
+    /// Register a native function with a Kuon signature such as
+    /// `fun (int, string) bool`. It can be called by all the code which is
+    /// compiled afterwards
+    pub fn register_function<F>(
+        &mut self,
+        name: &str,
+        signature: &str,
+        callback: F,
+    ) -> Result<(), KuonError>
+    where
+        F: Fn(&[ExecutionResult]) -> Result<ExecutionResult, String> + 'static,
+    {
+        let native = NativeFunction::new(name, signature, callback)?;
+
+        if self.env.get_function(name, &None).is_some() {
+            return Err(KuonError::Analyzer(AnalyzerError::new(
+                0,
+                format!("Function {} already exists", name),
+            )));
+        }
+
+        self.env.add_function(EnvironmentFunction::native(
+            native.name.clone(),
+            native.param_types.clone(),
+            native.return_type.clone(),
+        ));
+
+        self.chunk
+            .native_function_index_from_name
+            .insert(native.name.clone(), self.chunk.native_functions.len());
+        self.chunk.native_functions.push(native);
+
+        Ok(())
+    }
+
+    /// Parse, validate and compile source code. Nothing is kept if any of
+    /// those steps fail
+    pub fn compile(&mut self, source: &str) -> Result<Chunk, KuonError> {
+        let source = source.to_string();
+        let mut lexer = Lexer::from_string(&source);
+
+        let mut ast = parser::parse_source(&mut lexer)?;
+
+        let mut env = self.env.clone();
+        analyzer::validate_top_level(&mut ast, &mut env)?;
+
+        let chunk = compiler::compile_source_after(&self.chunk, &ast)
+            .map_err(KuonError::Compiler)?;
+
+        self.env = env;
+        self.chunk = chunk.clone();
+
+        Ok(chunk)
+    }
+
+    /// Run a chunk returned by compile
+    pub fn run(&mut self, chunk: &Chunk) -> Result<ExecutionResult, KuonError> {
+        Ok(self.vm.run(chunk)?)
+    }
+
+    /// Compile and run source code. If running it fails, the definitions it
+    /// added are thrown away, since some of them might never have been set
+    pub fn eval(&mut self, source: &str) -> Result<ExecutionResult, KuonError> {
+        let previous_env = self.env.clone();
+        let previous_chunk = self.chunk.clone();
+
+        let chunk = self.compile(source)?;
+
+        let result = self.run(&chunk);
+
+        if result.is_err() {
+            self.env = previous_env;
+            self.chunk = previous_chunk;
+        }
+
+        result
+    }
+
+    /// Call a top level function (or a native one) by its name. The arguments
+    /// are checked against the function's signature before calling it, see
+    /// prepare_arguments
+    pub fn call_function(
+        &mut self,
+        name: &str,
+        arguments: Vec<ExecutionResult>,
+    ) -> Result<ExecutionResult, KuonError> {
+        let function = match self.env.get_function(name, &None) {
+            Some(function) => function,
+            None => return Err(KuonError::UnknownFunction(name.to_string())),
+        };
+
+        let arguments = self.prepare_arguments(&function, arguments)?;
+
+        let function_value = if let Some(index) =
+            self.chunk.function_index_from_name.get(name)
+        {
+            Value::Function(*index)
+        } else if let Some(index) =
+            self.chunk.native_function_index_from_name.get(name)
+        {
+            Value::NativeFunction(*index)
+        } else {
+            return Err(KuonError::UnknownFunction(name.to_string()));
+        };
+
+        self.call_function_value(function_value, arguments)
+    }
+
+    /// Call a value function by its name, with pre_argument being the value
+    /// that comes before the colon (`pre_argument:name(arguments)`)
+    pub fn call_value_function(
+        &mut self,
+        name: &str,
+        pre_argument: ExecutionResult,
+        arguments: Vec<ExecutionResult>,
+    ) -> Result<ExecutionResult, KuonError> {
+        let function = self.env.functions.iter().find(|function| {
+            function.name == name
+                && match &function.pre_param_type {
+                    Some(type_) => {
+                        result_matches_type(&self.env, &pre_argument, type_)
+                    }
+                    None => false,
+                }
+        });
+
+        let function = match function {
+            Some(function) => function.clone(),
+            None => return Err(KuonError::UnknownFunction(name.to_string())),
+        };
+
+        let arguments = self.prepare_arguments(&function, arguments)?;
+
+        let key = (name.to_string(), function.pre_param_type.unwrap());
+        let index = match self.chunk.value_function_index_from_name.get(&key) {
+            Some(index) => *index,
+            None => return Err(KuonError::UnknownFunction(name.to_string())),
+        };
+
+        let mut all_arguments =
+            vec![pre_argument.into_value(self.vm.heap_mut())?];
+        all_arguments.extend(arguments);
+
+        self.call_function_value(Value::Function(index), all_arguments)
+    }
+
+    /// Check the arguments against the function's signature and turn them
+    /// into the values the function expects, the same way a call from Kuon
+    /// code does: parameters with a default value can be left out and the
+    /// arguments after the other parameters are put in the rest parameter
+    fn prepare_arguments(
+        &mut self,
+        function: &EnvironmentFunction,
+        arguments: Vec<ExecutionResult>,
+    ) -> Result<Vec<Value>, KuonError> {
+        let param_types = &function.param_types;
+        let fixed_count = if function.rest_param {
+            param_types.len() - 1
+        } else {
+            param_types.len()
+        };
+        let required_count = (0..fixed_count)
+            .filter(|i| !function.default_params[*i])
+            .count();
+
+        if arguments.len() < required_count
+            || (arguments.len() > fixed_count && !function.rest_param)
+        {
+            return Err(KuonError::InvalidArguments(format!(
+                "Function {} expects {} arguments, {} provided",
+                function.name,
+                if function.rest_param || required_count != fixed_count {
+                    format!("at least {}", required_count)
+                } else {
+                    required_count.to_string()
+                },
+                arguments.len()
+            )));
+        }
+
+        let mut arguments = arguments.into_iter();
+        let mut values = vec![];
+
+        for param_type in &param_types[..fixed_count] {
+            match arguments.next() {
+                Some(argument) => {
+                    self.check_argument(function, &argument, param_type)?;
+                    values.push(argument.into_value(self.vm.heap_mut())?);
+                }
+
+                None => values.push(Value::DefaultArgument),
+            }
+        }
+
+        if function.rest_param {
+            let element_type = match &param_types[fixed_count] {
+                Type::Array(element_type) => element_type,
+                _ => unreachable!(),
+            };
+
+            let rest: Vec<ExecutionResult> = arguments.collect();
+            for argument in rest.iter() {
+                self.check_argument(function, argument, element_type)?;
+            }
+
+            values.push(
+                ExecutionResult::Array(rest).into_value(self.vm.heap_mut())?,
+            );
+        }
+
+        Ok(values)
+    }
+
+    fn check_argument(
+        &self,
+        function: &EnvironmentFunction,
+        argument: &ExecutionResult,
+        param_type: &Type,
+    ) -> Result<(), KuonError> {
+        if !result_matches_type(&self.env, argument, param_type) {
+            return Err(KuonError::InvalidArguments(format!(
+                "Function {} expects a value of type {:?}, got {:?} instead",
+                function.name, param_type, argument
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn call_function_value(
+        &mut self,
+        function: Value,
+        arguments: Vec<Value>,
+    ) -> Result<ExecutionResult, KuonError> {
+        Ok(self.vm.run_function(&self.chunk, function, arguments)?)
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{
+    analyzer::{env::Environment, util::types_equal},
+    parser::r#type::Type,
+    vm::execution_result::ExecutionResult,
+};
+
+/// Check if a value given by the host can be passed where the given type is
+/// expected
+pub fn result_matches_type(
+    env: &Environment,
+    result: &ExecutionResult,
+    type_: &Type,
+) -> bool {
+    match (result, type_) {
+        (_, Type::Any) => true,
+
+        (ExecutionResult::Null, Type::Null)
+        | (ExecutionResult::Char(_), Type::Char)
+        | (ExecutionResult::Int(_), Type::Int)
+        | (ExecutionResult::Float(_), Type::Float)
+        | (ExecutionResult::Bool(_), Type::Bool)
+        | (ExecutionResult::String(_), Type::String) => true,
+
+        (ExecutionResult::Array(elements), Type::Array(element_type)) => {
+            elements
+                .iter()
+                .all(|element| result_matches_type(env, element, element_type))
+        }
+
+        (ExecutionResult::Tuple(elements), Type::Tuple(element_types)) => {
+            elements.len() == element_types.len()
+                && elements.iter().zip(element_types.iter()).all(
+                    |(element, element_type)| {
+                        result_matches_type(env, element, element_type)
+                    },
+                )
+        }
+
+        (
+            ExecutionResult::Map(entries),
+            Type::Map {
+                key_type,
+                value_type,
+            },
+        ) => entries.iter().all(|(key, value)| {
+            result_matches_type(env, key, key_type)
+                && result_matches_type(env, value, value_type)
+        }),
+
+        (ExecutionResult::Null, Type::Nullable(_)) => true,
+        (_, Type::Nullable(inner_type)) => {
+            result_matches_type(env, result, inner_type)
+        }
+
+        // functions can only be checked if they have a name, since that's the
+        // only way to find their type
+        (
+            ExecutionResult::Function {
+                name: Some(name), ..
+            }
+            | ExecutionResult::NativeFunction { name, .. },
+            Type::Function { .. },
+        ) => match env.get_function(name, &None) {
+            Some(function) => types_equal(env, &function.type_(), type_),
+
+            None => false,
+        },
+
+        (ExecutionResult::Struct { .. }, Type::UserDefined(name)) => {
+            match env.get_struct(name) {
+                Some(struct_) => result_matches_type(
+                    env,
+                    result,
+                    &Type::Struct {
+                        fields: struct_.fields,
+                    },
+                ),
+
+                None => false,
+            }
+        }
+
+        (
+            ExecutionResult::Enum { variant, fields },
+            Type::UserDefined(name),
+        ) => match env.get_enum_variant(name, variant) {
+            Some(field_types) => result_matches_type(
+                env,
+                &ExecutionResult::Struct {
+                    fields: fields.clone(),
+                },
+                &Type::Struct {
+                    fields: field_types,
+                },
+            ),
+
+            None => false,
+        },
+
+        (
+            ExecutionResult::Enum { variant, .. },
+            Type::EnumVariant {
+                name,
+                variant: expected_variant,
+            },
+        ) => {
+            variant == expected_variant
+                && result_matches_type(
+                    env,
+                    result,
+                    &Type::UserDefined(name.clone()),
+                )
+        }
+
+        (
+            ExecutionResult::Struct { fields },
+            Type::Struct {
+                fields: field_types,
+            },
+        ) => {
+            fields.len() == field_types.len()
+                && field_types.iter().all(|(name, field_type)| {
+                    match fields.get(name) {
+                        Some(field) => {
+                            result_matches_type(env, field, field_type)
+                        }
+                        None => false,
+                    }
+                })
+        }
+
+        _ => false,
+    }
+}
@@ -339,6 +339,13 @@ impl<'a> Iterator for Lexer<'a> {
                 })
             }
 
+            '#' => {
+                return Some(Token {
+                    data: TokenData::Hash,
+                    line: self.current_line,
+                })
+            }
+
             'T' => {
                 if self.collect_chars(3).0 == "his" {
                     self.skip_chars(3);
@@ -414,6 +421,15 @@ impl<'a> Iterator for Lexer<'a> {
                         line: self.current_line,
                     });
                 }
+
+                if next == "reak" {
+                    self.skip_chars(4);
+
+                    return Some(Token {
+                        data: TokenData::Break,
+                        line: self.current_line,
+                    });
+                }
             }
 
             'c' => {
@@ -427,6 +443,15 @@ impl<'a> Iterator for Lexer<'a> {
                         line: self.current_line,
                     });
                 }
+
+                if next == "ontinue" {
+                    self.skip_chars(7);
+
+                    return Some(Token {
+                        data: TokenData::Continue,
+                        line: self.current_line,
+                    });
+                }
             }
 
             'e' => {
@@ -480,6 +505,15 @@ impl<'a> Iterator for Lexer<'a> {
                         line: self.current_line,
                     });
                 }
+
+                if next == "or" {
+                    self.skip_chars(2);
+
+                    return Some(Token {
+                        data: TokenData::For,
+                        line: self.current_line,
+                    });
+                }
             }
 
             'i' => {
@@ -511,6 +545,28 @@ impl<'a> Iterator for Lexer<'a> {
                         line: self.current_line,
                     });
                 }
+
+                if next == "n" {
+                    self.skip_chars(1);
+
+                    return Some(Token {
+                        data: TokenData::In,
+                        line: self.current_line,
+                    });
+                }
+            }
+
+            'l' => {
+                let (next, _) = self.collect_while(|c| c.is_alphabetic());
+
+                if next == "oop" {
+                    self.skip_chars(3);
+
+                    return Some(Token {
+                        data: TokenData::Loop,
+                        line: self.current_line,
+                    });
+                }
             }
 
             'm' => {
@@ -643,6 +699,15 @@ impl<'a> Iterator for Lexer<'a> {
                         line: self.current_line,
                     });
                 }
+
+                if next == "ype" {
+                    self.skip_chars(3);
+
+                    return Some(Token {
+                        data: TokenData::Type,
+                        line: self.current_line,
+                    });
+                }
             }
 
             'v' => {
@@ -667,6 +732,19 @@ impl<'a> Iterator for Lexer<'a> {
                 }
             }
 
+            'w' => {
+                let (next, _) = self.collect_while(|c| c.is_alphabetic());
+
+                if next == "hile" {
+                    self.skip_chars(4);
+
+                    return Some(Token {
+                        data: TokenData::While,
+                        line: self.current_line,
+                    });
+                }
+            }
+
             '{' => {
                 return Some(Token {
                     data: TokenData::LeftParenCurly,
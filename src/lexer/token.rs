@@ -20,6 +20,7 @@ pub enum TokenData {
     ThreeDots,   // ...
     Colon,       // :
     DoubleColon, // ::
+    Hash,        // #
 
     Plus,  // +
     Minus, // -
@@ -58,6 +59,8 @@ pub enum TokenData {
     MkStruct, // mkstruct
     Enum,     // enum
 
+    Type, // type
+
     Interface, // interface
 
     Ref, // ref
@@ -70,6 +73,13 @@ pub enum TokenData {
     If,   // if
     Else, // else
 
+    While,    // while
+    Loop,     // loop
+    For,      // for
+    In,       // in
+    Break,    // break
+    Continue, // continue
+
     Match,      // match
     MatchArrow, // ->
 
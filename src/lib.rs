@@ -1,5 +1,8 @@
 pub mod analyzer;
 pub mod compiler;
+pub mod engine;
 pub mod lexer;
 pub mod parser;
 pub mod vm;
+
+pub use engine::{kuon_error::KuonError, Engine};
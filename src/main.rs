@@ -57,7 +57,11 @@ fn main() {
 
             println!("\n========================================\n");
 
-            println!("RESULT: {:?}", execute(&chunk));
+            match execute(&chunk) {
+                Ok(result) => println!("RESULT: {:?}", result),
+
+                Err(e) => cprintln!("<red>[Runtime error]</red> {:?}", e),
+            }
         }
 
         Err(e) => {
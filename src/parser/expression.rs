@@ -4,20 +4,85 @@ use crate::lexer::token::TokenData;
 
 use super::r#type::Type;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FunctionParam {
     pub name: String,
     pub type_: Type,
     pub constant: bool,
+
+    // parameters with a default value can be left out when calling the
+    // function, in which case the function evaluates this instead
+    pub default: Option<Box<Expression>>,
+
+    // the last parameter can be a rest parameter (...name type), which gets
+    // an array of all the remaining positional arguments. Its type_ is the
+    // array's type
+    pub rest: bool,
+}
+
+/// A value function which the types implementing an interface need to have,
+/// its pre-parameter always has the type this
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterfaceFunction {
+    pub name: String,
+    pub param_names: Vec<String>,
+    pub param_types: Vec<Type>,
+    pub var_params: Vec<bool>,
+    pub return_type: Type,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum VariableAccessor {
     StructField(String),
-    // TODO: add array indexing
+    Index(Box<Expression>),
+}
+
+/// Conversion functions (`fun (val s string) as Person { ... }`) are value
+/// functions whose name is this followed by the type they convert to, the
+/// space in it makes sure they can't be called by name
+pub const CONVERSION_FUNCTION_PREFIX: &str = "as ";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    // a literal value which is compared with the matched value
+    Literal(Box<Expression>),
+
+    // binds the matched value to a variable, or compares it with the
+    // variable's value if it's already been bound earlier in the same pattern
+    Binding(String),
+
+    // rest is true if the pattern ends with ..., which lets it leave out some
+    // of the fields
+    Struct {
+        name: Option<String>,
+        fields: Vec<(String, Pattern)>,
+        rest: bool,
+    },
+
+    EnumVariant {
+        name: String,
+        variant: String,
+        fields: Vec<(String, Pattern)>,
+        rest: bool,
+    },
+
+    Tuple(Vec<Pattern>),
+
+    // the groups of elements between each ..., so a pattern without ... has
+    // a single group. The groups in the middle can be anywhere in the array,
+    // as long as they come in order, and they match where they're found first
+    Array(Vec<Vec<Pattern>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    // None for the else arm
+    pub pattern: Option<Pattern>,
+    pub body: Expression,
+    pub line: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ExpressionData {
     Null,
 
@@ -54,6 +119,32 @@ pub enum ExpressionData {
         else_branch: Option<Box<Expression>>,
     },
 
+    While {
+        condition: Box<Expression>,
+        body: Box<Expression>,
+    },
+
+    Loop {
+        body: Box<Expression>,
+    },
+
+    For {
+        variable: String,
+        iterable: Box<Expression>,
+        body: Box<Expression>,
+    },
+
+    Break {
+        value: Option<Box<Expression>>,
+    },
+
+    Match {
+        value: Box<Expression>,
+        arms: Vec<MatchArm>,
+    },
+
+    Continue,
+
     VariableDefinition {
         constant: bool,
         name: String,
@@ -61,23 +152,52 @@ pub enum ExpressionData {
         type_: Option<Box<Type>>,
     },
 
+    // val <a, b> = <1, 2>;
+    TupleDefinition {
+        constant: bool,
+        names: Vec<String>,
+        value: Box<Expression>,
+        type_: Option<Box<Type>>,
+    },
+
     VariableAssignment {
         name: String,
         accessors: Vec<VariableAccessor>,
         value: Box<Expression>,
     },
 
+    // a += b, where operator is the Plus in +=. It isn't turned into
+    // a = a + b, since the indexes in a would then be evaluated twice
+    CompoundAssignment {
+        name: String,
+        accessors: Vec<VariableAccessor>,
+        operator: TokenData,
+        value: Box<Expression>,
+    },
+
     FunctionDefinition {
         name: Option<String>,
         pre_parameter: Option<FunctionParam>,
         params: Vec<FunctionParam>,
         return_type: Type,
         body: Box<Expression>,
+
+        // Some for type functions, the type before the # in Type#name
+        type_name: Option<String>,
+
+        // Variables of enclosing functions which are used inside of this one.
+        // This field will always be empty in the AST produced by the parser,
+        // it's filled by the analyzer instead
+        captures: Vec<String>,
     },
 
     StructDefinition {
         name: Option<String>,
         fields: BTreeMap<String, Type>,
+
+        // expressions for the fields which have default values, they're
+        // compiled in place of the missing fields wherever the struct is made
+        defaults: BTreeMap<String, Expression>,
     },
 
     MakeStruct {
@@ -85,32 +205,106 @@ pub enum ExpressionData {
         fields: HashMap<String, Expression>,
     },
 
+    EnumDefinition {
+        name: String,
+        variants: BTreeMap<String, BTreeMap<String, Type>>,
+    },
+
+    // type name = ...;
+    TypeAlias {
+        name: String,
+        type_: Type,
+    },
+
+    InterfaceDefinition {
+        name: String,
+        functions: Vec<InterfaceFunction>,
+    },
+
+    // Element:Text { value: "..." }, variants without fields have no fields
+    // given
+    MakeEnum {
+        name: String,
+        variant: String,
+        fields: HashMap<String, Expression>,
+    },
+
+    // An argument which was left out of a function call, the function uses
+    // the parameter's default value instead. The parser never produces this,
+    // the analyzer puts it in place of the missing arguments
+    DefaultArgument,
+
+    // Check that the value is the given variant of an enum, giving a runtime
+    // error if it isn't. The parser never produces this, the analyzer wraps
+    // values in it when a value of the enum is used where the variant is
+    // expected
+    VariantCast {
+        value: Box<Expression>,
+        name: String,
+        variant: String,
+    },
+
+    // value_type is None until the analyzer fills it in, the compiler needs it
+    // to know the type a value put into an any had
+    Cast {
+        value: Box<Expression>,
+        type_: Type,
+        value_type: Option<Type>,
+    },
+
+    MakeArray {
+        elements: Vec<Expression>,
+    },
+
+    MakeTuple {
+        elements: Vec<Expression>,
+    },
+
+    MakeMap {
+        entries: Vec<(Expression, Expression)>,
+    },
+
+    // The analyzer moves the keyword arguments (name: value) into arguments,
+    // which then has a value for every parameter in order
     FunctionCall {
         function: Box<Expression>,
         arguments: Vec<Expression>,
+        keyword_arguments: Vec<(String, Expression)>,
     },
 
     ValueFunctionCall {
         pre_argument: Box<Expression>,
         function_name: String,
         arguments: Vec<Expression>,
+        keyword_arguments: Vec<(String, Expression)>,
 
         // This field will always be None in the AST produced by the parser.
         // It's filled by the analyzer instead
         pre_argument_type: Option<Type>,
     },
 
+    // a type function used as a value, Type#name
+    TypeFunction {
+        type_name: String,
+        function_name: String,
+    },
+
     FieldAccess {
         expression: Box<Expression>,
         field: String,
     },
 
+    IndexAccess {
+        expression: Box<Expression>,
+        index: Box<Expression>,
+    },
+
     Type {
         type_: Type,
     },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Expression {
     pub data: ExpressionData,
     pub line: usize,
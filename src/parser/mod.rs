@@ -1,17 +1,31 @@
 use expression::{Expression, ExpressionData};
-use parse_functions::create_variable_assignment::create_variable_assignment;
+use parse_functions::array::{parse_index, parse_make_array};
+use parse_functions::create_variable_assignment::{
+    create_compound_assignment, create_variable_assignment,
+};
+use parse_functions::enum_definition::parse_enum_definition;
 use parse_functions::function_arguments::parse_function_arguments;
 use parse_functions::function_definition::parse_function_definition;
 use parse_functions::if_condition::parse_if_condition;
+use parse_functions::interface_definition::parse_interface_definition;
+use parse_functions::loops::{parse_break, parse_for, parse_loop, parse_while};
 use parse_functions::make_struct::parse_make_struct;
+use parse_functions::map::parse_make_map;
+use parse_functions::match_expression::parse_match;
+use parse_functions::r#type::{parse_function_type, parse_type};
 use parse_functions::struct_definition::parse_struct_definition;
+use parse_functions::tuple::parse_make_tuple;
+use parse_functions::type_alias::parse_type_alias;
 use parse_functions::variable_definition::parse_variable_definition;
 use parser_error::ParserError;
 use util::token_matches;
 
+use std::collections::HashMap;
+
 use crate::lexer::token::TokenData;
 use crate::lexer::{token::Token, Lexer};
 use crate::parser::parse_functions::block::parse_block;
+use crate::parser::r#type::Type;
 use crate::{expression, expression_pat, some_token_pat, token_pat};
 
 pub mod expression;
@@ -65,7 +79,21 @@ pub fn parse_source(lexer: &mut Lexer) -> Result<Expression, ParserError> {
                 }
 
                 Expression {
-                    data: ExpressionData::IfCondition { .. },
+                    data:
+                        ExpressionData::EnumDefinition { .. }
+                        | ExpressionData::InterfaceDefinition { .. },
+                    ..
+                } => {
+                    require_semicolon = false;
+                }
+
+                Expression {
+                    data:
+                        ExpressionData::IfCondition { .. }
+                        | ExpressionData::Match { .. }
+                        | ExpressionData::While { .. }
+                        | ExpressionData::Loop { .. }
+                        | ExpressionData::For { .. },
                     ..
                 } => {
                     require_semicolon = false;
@@ -95,6 +123,40 @@ pub fn parse_source(lexer: &mut Lexer) -> Result<Expression, ParserError> {
     Ok(expression!(Block { expressions }, 0))
 }
 
+/// Parse a function signature such as `fun (int, string) bool` into a
+/// Type::Function, this is used for describing native functions
+pub fn parse_function_signature(
+    lexer: &mut Lexer,
+) -> Result<Type, ParserError> {
+    let line = match lexer.next() {
+        some_token_pat!(TokenData::Fun, line) => line,
+
+        Some(t) => {
+            return parser_error!(t.line, "Expected fun, got {:?}", t.data)
+        }
+        None => return parser_error_eof!("Expected fun"),
+    };
+
+    let type_ = parse_function_type(lexer)?;
+
+    // native functions get copies of their arguments, so they can't modify
+    // them
+    if let Type::Function { var_params, .. } = &type_ {
+        if var_params.contains(&true) {
+            return parser_error!(
+                line,
+                "Native functions can't have var parameters"
+            );
+        }
+    }
+
+    if let Some(t) = lexer.next() {
+        return parser_error!(t.line, "Unexpected token: {:?}", t.data);
+    }
+
+    Ok(type_)
+}
+
 fn parse_expression_top_level(
     lexer: &mut Lexer,
 ) -> Result<Expression, ParserError> {
@@ -105,6 +167,13 @@ fn parse_expression(lexer: &mut Lexer) -> Result<Expression, ParserError> {
     expr_binding_power(lexer, 0, false)
 }
 
+/// Parse an expression which is followed by a block, like the condition of an
+/// if. An identifier followed by { isn't treated as a struct here, since the {
+/// is the start of the block
+fn parse_condition(lexer: &mut Lexer) -> Result<Expression, ParserError> {
+    expr_binding_power(lexer, 1, false)
+}
+
 fn expr_binding_power(
     lexer: &mut Lexer,
     min_binding_power: u8,
@@ -131,8 +200,38 @@ fn expr_binding_power(
         some_token_pat!(False, line) => {
             expression!(Bool(false), line)
         }
+        some_token_pat!(TokenData::Null, line) => {
+            expression!(Null, line)
+        }
         some_token_pat!(ValueIdentifier(v), line) => {
-            if matches!(lexer.peek(), some_token_pat!(LeftParenCurly))
+            if matches!(lexer.peek(), some_token_pat!(Hash)) {
+                lexer.next();
+
+                match lexer.next() {
+                    some_token_pat!(ValueIdentifier(function_name)) => {
+                        expression!(
+                            TypeFunction {
+                                type_name: v,
+                                function_name,
+                            },
+                            line
+                        )
+                    }
+
+                    Some(t) => {
+                        return parser_error!(
+                            t.line,
+                            "Expected function name after #, got {:?}",
+                            t.data
+                        )
+                    }
+                    None => {
+                        return parser_error_eof!(
+                            "Expected function name after #"
+                        )
+                    }
+                }
+            } else if matches!(lexer.peek(), some_token_pat!(LeftParenCurly))
                 && min_binding_power == 0
             {
                 parse_make_struct(lexer, line, Some(v))?
@@ -141,7 +240,9 @@ fn expr_binding_power(
             }
         }
 
-        Some(operator @ token_pat!(TokenData::Minus, line)) => {
+        Some(
+            operator @ token_pat!(TokenData::Minus | TokenData::Not, line),
+        ) => {
             let (_, right_binding_power) =
                 prefix_binding_power(&operator.data).unwrap();
             let right = expr_binding_power(lexer, right_binding_power, false)?;
@@ -170,10 +271,32 @@ fn expr_binding_power(
             parse_block(lexer, line)?
         }
 
+        some_token_pat!(TokenData::LeftParenSquare, line) => {
+            parse_make_array(lexer, line)?
+        }
+
+        some_token_pat!(TokenData::LessThan, line) => {
+            parse_make_tuple(lexer, line)?
+        }
+
         some_token_pat!(TokenData::If, line) => {
             parse_if_condition(lexer, line)?
         }
 
+        some_token_pat!(TokenData::Match, line) => parse_match(lexer, line)?,
+
+        some_token_pat!(TokenData::While, line) => parse_while(lexer, line)?,
+
+        some_token_pat!(TokenData::Loop, line) => parse_loop(lexer, line)?,
+
+        some_token_pat!(TokenData::For, line) => parse_for(lexer, line)?,
+
+        some_token_pat!(TokenData::Break, line) => parse_break(lexer, line)?,
+
+        some_token_pat!(TokenData::Continue, line) => {
+            expression!(Continue, line)
+        }
+
         token @ some_token_pat!(TokenData::Val | TokenData::Var) => {
             parse_variable_definition(lexer, token.unwrap())?
         }
@@ -186,10 +309,24 @@ fn expr_binding_power(
             parse_struct_definition(lexer, top_level, line)?
         }
 
+        some_token_pat!(TokenData::Enum, line) => {
+            parse_enum_definition(lexer, top_level, line)?
+        }
+
+        some_token_pat!(TokenData::Type, line) => {
+            parse_type_alias(lexer, top_level, line)?
+        }
+
+        some_token_pat!(TokenData::Interface, line) => {
+            parse_interface_definition(lexer, top_level, line)?
+        }
+
         some_token_pat!(TokenData::MkStruct, line) => {
             parse_make_struct(lexer, line, None)?
         }
 
+        some_token_pat!(TokenData::Map, line) => parse_make_map(lexer, line)?,
+
         None => return parser_error_eof!("Expected expression"),
         Some(t) => {
             return parser_error!(t.line, "Unexpected token: {:?}", t.data)
@@ -213,13 +350,22 @@ fn expr_binding_power(
 
                 left = match operator.data {
                     // function call
-                    TokenData::LeftParenNormal => expression!(
-                        FunctionCall {
-                            function: Box::new(left.clone()),
-                            arguments: parse_function_arguments(lexer)?,
-                        },
-                        left.line
-                    ),
+                    TokenData::LeftParenNormal => {
+                        let (arguments, keyword_arguments) =
+                            parse_function_arguments(lexer)?;
+
+                        expression!(
+                            FunctionCall {
+                                function: Box::new(left.clone()),
+                                arguments,
+                                keyword_arguments,
+                            },
+                            left.line
+                        )
+                    }
+
+                    // indexing
+                    TokenData::LeftParenSquare => parse_index(lexer, left)?,
 
                     // else
                     _ => expression!(
@@ -244,6 +390,21 @@ fn expr_binding_power(
                 }
 
                 lexer.next();
+
+                // the right side of as is a type instead of an expression
+                if operator.data == TokenData::As {
+                    left = expression!(
+                        Cast {
+                            value: Box::new(left.clone()),
+                            type_: parse_type(lexer)?,
+                            value_type: None,
+                        },
+                        left.line
+                    );
+
+                    continue;
+                }
+
                 let right =
                     expr_binding_power(lexer, right_binding_power, false)?;
 
@@ -253,6 +414,16 @@ fn expr_binding_power(
                         create_variable_assignment(&left, &right)?
                     }
 
+                    // compound assignment
+                    TokenData::PlusEquals
+                    | TokenData::MinusEquals
+                    | TokenData::StarEquals
+                    | TokenData::SlashEquals => create_compound_assignment(
+                        &left,
+                        &operator.data,
+                        &right,
+                    )?,
+
                     // Field access
                     TokenData::Dot => {
                         let field = match right {
@@ -278,44 +449,100 @@ fn expr_binding_power(
                         )
                     }
 
-                    // Value function call
-                    TokenData::Colon => {
-                        let (function_name, arguments) = match right {
-                            // match all function calls which have an identifier
-                            // as the function expression and extract that
-                            // identifier along with the arguments (rust can't
-                            // match a Box so I had to do something ugly like
-                            // this...)
-                            expression_pat!(ExpressionData::FunctionCall {
-                                function,
-                                arguments,
-                            }) if matches!(
-                                &function.data,
+                    // Enum variant
+                    TokenData::Colon
+                        if matches!(
+                            (&left.data, &right.data),
+                            (
+                                ExpressionData::Identifier(_),
                                 ExpressionData::Identifier(_)
-                            ) =>
-                            {
-                                match function.data {
-                                    ExpressionData::Identifier(
-                                        function_name,
-                                    ) => (function_name, arguments),
-
-                                    _ => unreachable!(),
-                                }
-                            }
+                            )
+                        ) =>
+                    {
+                        let (name, variant) = match (left.data, right.data) {
+                            (
+                                ExpressionData::Identifier(name),
+                                ExpressionData::Identifier(variant),
+                            ) => (name, variant),
+
+                            _ => unreachable!(),
+                        };
 
-                            _ => {
-                                return parser_error!(
-                                    right.line,
-                                    "Expected function call"
-                                );
+                        // same as with structs, the fields can't be given in
+                        // a condition since the { is the start of the block
+                        let fields = if matches!(
+                            lexer.peek(),
+                            some_token_pat!(LeftParenCurly)
+                        ) && min_binding_power == 0
+                        {
+                            match parse_make_struct(lexer, left.line, None)? {
+                                expression_pat!(
+                                    ExpressionData::MakeStruct { fields, .. }
+                                ) => fields,
+
+                                _ => unreachable!(),
                             }
+                        } else {
+                            HashMap::new()
                         };
 
+                        expression!(
+                            MakeEnum {
+                                name,
+                                variant,
+                                fields
+                            },
+                            left.line
+                        )
+                    }
+
+                    // Value function call
+                    TokenData::Colon => {
+                        let (function_name, arguments, keyword_arguments) =
+                            match right {
+                                // match all function calls which have an identifier
+                                // as the function expression and extract that
+                                // identifier along with the arguments (rust can't
+                                // match a Box so I had to do something ugly like
+                                // this...)
+                                expression_pat!(
+                                    ExpressionData::FunctionCall {
+                                        function,
+                                        arguments,
+                                        keyword_arguments,
+                                    }
+                                ) if matches!(
+                                    &function.data,
+                                    ExpressionData::Identifier(_)
+                                ) =>
+                                {
+                                    match function.data {
+                                        ExpressionData::Identifier(
+                                            function_name,
+                                        ) => (
+                                            function_name,
+                                            arguments,
+                                            keyword_arguments,
+                                        ),
+
+                                        _ => unreachable!(),
+                                    }
+                                }
+
+                                _ => {
+                                    return parser_error!(
+                                        right.line,
+                                        "Expected function call"
+                                    );
+                                }
+                            };
+
                         expression!(
                             ValueFunctionCall {
                                 pre_argument: Box::new(left.clone()),
                                 function_name,
                                 arguments,
+                                keyword_arguments,
                                 pre_argument_type: None
                             },
                             left.line
@@ -349,6 +576,10 @@ fn prefix_binding_power(op: &TokenData) -> Option<((), u8)> {
     match op {
         TokenData::Minus => Some(((), 40)),
 
+        // not has a lower precedence than comparisons, so `not a == b` is the
+        // same as `not (a == b)`
+        TokenData::Not => Some(((), 7)),
+
         _ => None,
     }
 }
@@ -357,14 +588,23 @@ fn infix_binding_power(op: &TokenData) -> Option<(u8, u8)> {
     match op {
         TokenData::Plus | TokenData::Minus => Some((20, 21)),
         TokenData::Star | TokenData::Slash => Some((30, 31)),
+        TokenData::As => Some((35, 36)),
+
+        TokenData::Or => Some((3, 4)),
+        TokenData::And => Some((5, 6)),
 
         TokenData::EqualsEquals
+        | TokenData::ExclamationEquals
         | TokenData::LessThan
         | TokenData::LessThanOrEqual
         | TokenData::GreaterThan
         | TokenData::GreaterThanOrEqual => Some((10, 11)),
 
-        TokenData::Equals => Some((0, 0)),
+        TokenData::Equals
+        | TokenData::PlusEquals
+        | TokenData::MinusEquals
+        | TokenData::StarEquals
+        | TokenData::SlashEquals => Some((0, 0)),
 
         TokenData::Dot => Some((50, 51)),
         TokenData::Colon => Some((60, 61)),
@@ -376,6 +616,12 @@ fn infix_binding_power(op: &TokenData) -> Option<(u8, u8)> {
 fn postfix_binding_power(op: &TokenData) -> Option<(u8, ())> {
     match op {
         TokenData::LeftParenNormal => Some((70, ())),
+
+        // lower than the one of ., so that a.b[0] indexes a.b instead of b
+        TokenData::LeftParenSquare => Some((48, ())),
+
+        // lower than the one of ., so that a.b? checks a.b instead of b
+        TokenData::QuestionMark | TokenData::ExclamationMark => Some((45, ())),
         _ => None,
     }
 }
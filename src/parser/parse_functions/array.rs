@@ -0,0 +1,74 @@
+use crate::{
+    expression,
+    lexer::{
+        token::{Token, TokenData},
+        Lexer,
+    },
+    parser::parser_error::ParserError,
+    parser_error, parser_error_eof, some_token_pat,
+};
+
+use super::super::{expression::Expression, parse_expression};
+
+/// Called after Token::LeftParenSquare at the start of an expression
+pub fn parse_make_array(
+    lexer: &mut Lexer,
+    line: usize,
+) -> Result<Expression, ParserError> {
+    let mut elements: Vec<Expression> = vec![];
+
+    loop {
+        // this also allows a trailing comma
+        if matches!(lexer.peek(), some_token_pat!(TokenData::RightParenSquare))
+        {
+            lexer.next();
+            break;
+        }
+
+        elements.push(parse_expression(lexer)?);
+
+        match lexer.next() {
+            some_token_pat!(TokenData::Comma) => {}
+
+            some_token_pat!(TokenData::RightParenSquare) => break,
+
+            Some(t) => {
+                return parser_error!(
+                    t.line,
+                    "Expected , or ], got {:?}",
+                    t.data
+                )
+            }
+            None => return parser_error_eof!("Expected , or ]"),
+        }
+    }
+
+    Ok(expression!(MakeArray { elements }, line))
+}
+
+/// Called after Token::LeftParenSquare which follows an expression
+pub fn parse_index(
+    lexer: &mut Lexer,
+    left: Expression,
+) -> Result<Expression, ParserError> {
+    let index = parse_expression(lexer)?;
+
+    match lexer.next() {
+        some_token_pat!(TokenData::RightParenSquare) => {}
+
+        Some(t) => {
+            return parser_error!(t.line, "Expected ], got {:?}", t.data)
+        }
+        None => return parser_error_eof!("Expected ]"),
+    }
+
+    let line = left.line;
+
+    Ok(expression!(
+        IndexAccess {
+            expression: Box::new(left),
+            index: Box::new(index),
+        },
+        line
+    ))
+}
@@ -59,9 +59,15 @@ pub fn parse_block(
         let mut require_semicolon = true;
 
         match expression {
-            // semicolons aren't required after if conditions
+            // semicolons aren't required after if conditions, matches and
+            // loops
             Expression {
-                data: ExpressionData::IfCondition { .. },
+                data:
+                    ExpressionData::IfCondition { .. }
+                    | ExpressionData::Match { .. }
+                    | ExpressionData::While { .. }
+                    | ExpressionData::Loop { .. }
+                    | ExpressionData::For { .. },
                 ..
             } => {
                 require_semicolon = false;
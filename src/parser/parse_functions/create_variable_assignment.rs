@@ -1,9 +1,11 @@
 use crate::{
     expression, expression_pat,
+    lexer::token::TokenData,
     parser::{
         expression::{ExpressionData, VariableAccessor},
         parser_error::ParserError,
     },
+    parser_error,
 };
 
 use super::super::expression::Expression;
@@ -28,12 +30,19 @@ fn parse_accessors(
             return Ok((name, new_accessors));
         }
 
+        expression_pat!(ExpressionData::IndexAccess { expression, index }) => {
+            let (name, mut accessors) = parse_accessors(expression)?;
+            accessors.push(VariableAccessor::Index(index.clone()));
+
+            return Ok((name, accessors));
+        }
+
         _ => {
-            todo!("idk this error doesn't fit anymore")
-            // return parser_error!(
-            //     left.line,
-            //     "Variable name should be an identifier"
-            // );
+            return parser_error!(
+                left.line,
+                "Can only assign to a variable, a struct field or an array \
+                 element"
+            );
         }
     };
 }
@@ -54,3 +63,32 @@ pub fn create_variable_assignment(
         left.line
     ))
 }
+
+/// Called on infix TokenData::PlusEquals, MinusEquals, StarEquals and
+/// SlashEquals operators
+pub fn create_compound_assignment(
+    left: &Expression,
+    operator: &TokenData,
+    right: &Expression,
+) -> Result<Expression, ParserError> {
+    let operator = match operator {
+        TokenData::PlusEquals => TokenData::Plus,
+        TokenData::MinusEquals => TokenData::Minus,
+        TokenData::StarEquals => TokenData::Star,
+        TokenData::SlashEquals => TokenData::Slash,
+
+        _ => unreachable!(),
+    };
+
+    let (name, accessors) = parse_accessors(left)?;
+
+    Ok(expression!(
+        CompoundAssignment {
+            name,
+            accessors,
+            operator,
+            value: Box::new(right.clone()),
+        },
+        left.line
+    ))
+}
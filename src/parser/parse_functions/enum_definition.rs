@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    expression,
+    lexer::{
+        token::{Token, TokenData},
+        Lexer,
+    },
+    parser::{parser_error::ParserError, r#type::Type},
+    parser_error, parser_error_eof, some_token_pat,
+};
+
+use super::{super::expression::Expression, struct_definition::parse_fields};
+
+/// Called after Token::Enum
+pub fn parse_enum_definition(
+    lexer: &mut Lexer,
+    top_level: bool,
+    line: usize,
+) -> Result<Expression, ParserError> {
+    if !top_level {
+        return parser_error!(
+            line,
+            "Enum definitions are only allowed at the top level"
+        );
+    }
+
+    let name = match lexer.next() {
+        some_token_pat!(TokenData::ValueIdentifier(identifier)) => identifier,
+
+        Some(t) => {
+            return parser_error!(
+                t.line,
+                "Expected enum name, got {:?}",
+                t.data
+            )
+        }
+        None => return parser_error_eof!("Expected enum name"),
+    };
+
+    match lexer.next() {
+        some_token_pat!(TokenData::LeftParenCurly) => {}
+
+        Some(t) => {
+            return parser_error!(t.line, "Expected {{, got {:?}", t.data)
+        }
+        None => return parser_error_eof!("Expected {{"),
+    }
+
+    // variants without fields are stored with an empty map of fields
+    let mut variants: BTreeMap<String, BTreeMap<String, Type>> =
+        BTreeMap::new();
+
+    loop {
+        match lexer.next() {
+            some_token_pat!(TokenData::RightParenCurly) => break,
+
+            some_token_pat!(TokenData::ValueIdentifier(variant), line) => {
+                let mut fields = BTreeMap::new();
+
+                if matches!(
+                    lexer.peek(),
+                    some_token_pat!(TokenData::LeftParenCurly)
+                ) {
+                    lexer.next();
+                    (fields, _) = parse_fields(lexer, false)?;
+                }
+
+                // don't allow duplicate variants
+                if variants.contains_key(&variant) {
+                    return parser_error!(
+                        line,
+                        "Duplicate enum variant: {}",
+                        variant
+                    );
+                }
+
+                variants.insert(variant, fields);
+
+                // skip optional comma
+                if matches!(lexer.peek(), some_token_pat!(TokenData::Comma)) {
+                    lexer.next();
+                }
+            }
+
+            Some(t) => {
+                return parser_error!(
+                    t.line,
+                    "Expected enum variant, got {:?}",
+                    t.data
+                )
+            }
+            None => return parser_error_eof!("Expected enum variant or }}"),
+        }
+    }
+
+    Ok(expression!(EnumDefinition { name, variants }, line))
+}
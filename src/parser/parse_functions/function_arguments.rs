@@ -9,22 +9,64 @@ use crate::{
 
 use super::super::{expression::Expression, parse_expression};
 
-/// Called after Token::LeftParenNormal
+/// Check if the next argument is a keyword argument (name: value). This is
+/// ambiguous with enum variants and value function calls, so names starting
+/// with an uppercase letter are treated as enums (Element:Empty) and anything
+/// else is a keyword argument, even n: abs(x). A value function call has to be
+/// put in parentheses to be passed as an argument this way, like (n:abs(x))
+fn is_keyword_argument(lexer: &Lexer) -> bool {
+    let mut lookahead = lexer.clone();
+
+    match (lookahead.next(), lookahead.next()) {
+        (
+            some_token_pat!(TokenData::ValueIdentifier(name)),
+            some_token_pat!(TokenData::Colon),
+        ) => !name.starts_with(|c: char| c.is_uppercase()),
+
+        _ => false,
+    }
+}
+
+/// The positional arguments of a call and its keyword arguments with their
+/// names
+pub type FunctionArguments = (Vec<Expression>, Vec<(String, Expression)>);
+
+/// Called after Token::LeftParenNormal. Returns the positional arguments and
+/// the keyword arguments, which have to come after all the positional ones
 pub fn parse_function_arguments(
     lexer: &mut Lexer,
-) -> Result<Vec<Expression>, ParserError> {
+) -> Result<FunctionArguments, ParserError> {
     let mut arguments: Vec<Expression> = vec![];
+    let mut keyword_arguments: Vec<(String, Expression)> = vec![];
 
     // immediately return if no arguments
     if matches!(lexer.peek(), some_token_pat!(TokenData::RightParenNormal)) {
         lexer.next();
 
-        return Ok(arguments);
+        return Ok((arguments, keyword_arguments));
     }
 
     loop {
-        let argument = parse_expression(lexer)?;
-        arguments.push(argument);
+        if is_keyword_argument(lexer) {
+            let name = match lexer.next() {
+                some_token_pat!(TokenData::ValueIdentifier(name)) => name,
+                _ => unreachable!(),
+            };
+            lexer.next();
+
+            keyword_arguments.push((name, parse_expression(lexer)?));
+        } else {
+            let argument = parse_expression(lexer)?;
+
+            if !keyword_arguments.is_empty() {
+                return parser_error!(
+                    argument.line,
+                    "Positional arguments can't come after keyword arguments"
+                );
+            }
+
+            arguments.push(argument);
+        }
 
         match lexer.peek() {
             some_token_pat!(TokenData::RightParenNormal) => {
@@ -48,5 +90,5 @@ pub fn parse_function_arguments(
         }
     }
 
-    Ok(arguments)
+    Ok((arguments, keyword_arguments))
 }
@@ -5,7 +5,10 @@ use crate::{
         Lexer,
     },
     parser::{
-        expression::FunctionParam, parse_expression, parser_error::ParserError,
+        expression::{FunctionParam, CONVERSION_FUNCTION_PREFIX},
+        parse_expression,
+        parser_error::ParserError,
+        r#type::Type,
         util::token_matches,
     },
     parser_error, parser_error_eof, some_token_pat,
@@ -29,24 +32,75 @@ pub fn parse_params(
             true
         };
 
+        let rest = token_matches(&lexer.peek(), &TokenData::ThreeDots);
+        if rest {
+            lexer.next();
+        }
+
         let next = lexer.next();
 
-        if token_matches(&next, &TokenData::RightParenNormal) {
+        if token_matches(&next, &TokenData::RightParenNormal) && !rest {
             break;
         }
 
         match next {
-            some_token_pat!(TokenData::ValueIdentifier(identifier)) => {
+            some_token_pat!(TokenData::ValueIdentifier(identifier), line) => {
                 let name = identifier;
-                let type_ = parse_type(lexer)?;
+                let mut type_ = parse_type(lexer)?;
+
+                let mut default: Option<Box<Expression>> = None;
+                if token_matches(&lexer.peek(), &TokenData::Equals) {
+                    lexer.next();
+                    default = Some(Box::new(parse_expression(lexer)?));
+                }
+
+                if rest {
+                    type_ = Type::Array(Box::new(type_));
+                }
+
+                if (rest || default.is_some()) && !constant {
+                    return parser_error!(
+                        line,
+                        "A var parameter can't have a default value or be a rest parameter"
+                    );
+                }
+
+                if rest && default.is_some() {
+                    return parser_error!(
+                        line,
+                        "A rest parameter can't have a default value"
+                    );
+                }
+
+                // the arguments are given in order, so a parameter without a
+                // default value can't be left out if the ones before it are
+                if !rest
+                    && default.is_none()
+                    && params.iter().any(|p| p.default.is_some())
+                {
+                    return parser_error!(
+                        line,
+                        "Parameter {} needs a default value since the parameters before it have one",
+                        name
+                    );
+                }
 
                 params.push(FunctionParam {
                     name,
                     type_,
                     constant,
+                    default,
+                    rest,
                 });
 
                 match lexer.next() {
+                    some_token_pat!(TokenData::Comma) if rest => {
+                        return parser_error!(
+                            line,
+                            "The rest parameter has to be the last one"
+                        );
+                    }
+
                     some_token_pat!(TokenData::Comma) => {}
 
                     some_token_pat!(TokenData::RightParenNormal) => break,
@@ -83,6 +137,7 @@ pub fn parse_function_definition(
     line: usize,
 ) -> Result<Expression, ParserError> {
     let mut name: Option<String> = None;
+    let mut type_name: Option<String> = None;
 
     // get name if it exists
     let first_token = lexer.next();
@@ -90,7 +145,33 @@ pub fn parse_function_definition(
         some_token_pat!(TokenData::LeftParenNormal) => {}
 
         some_token_pat!(TokenData::ValueIdentifier(identifier)) => {
-            name = Some(identifier);
+            // type function, the identifier is the type and the name comes
+            // after the #
+            if token_matches(&lexer.peek(), &TokenData::Hash) {
+                lexer.next();
+
+                match lexer.next() {
+                    some_token_pat!(TokenData::ValueIdentifier(n)) => {
+                        type_name = Some(identifier);
+                        name = Some(n);
+                    }
+
+                    Some(t) => {
+                        return parser_error!(
+                            t.line,
+                            "Expected function name after #, got {:?}",
+                            t.data
+                        )
+                    }
+                    None => {
+                        return parser_error_eof!(
+                            "Expected function name after #"
+                        )
+                    }
+                }
+            } else {
+                name = Some(identifier);
+            }
 
             let next = lexer.next();
             if !token_matches(&next, &TokenData::LeftParenNormal) {
@@ -120,7 +201,10 @@ pub fn parse_function_definition(
 
     let params = parse_params(lexer)?;
 
-    if matches!(lexer.peek(), some_token_pat!(TokenData::Colon)) {
+    if matches!(
+        lexer.peek(),
+        some_token_pat!(TokenData::Colon | TokenData::As)
+    ) {
         if params.len() != 1 {
             return parser_error!(
                 first_token.unwrap().line,
@@ -135,6 +219,13 @@ pub fn parse_function_definition(
             );
         }
 
+        if type_name.is_some() {
+            return parser_error!(
+                first_token.unwrap().line,
+                "A type function can't have a pre-parameter",
+            );
+        }
+
         if name.is_some() {
             return parser_error!(
                 first_token.unwrap().line,
@@ -142,10 +233,38 @@ pub fn parse_function_definition(
             );
         }
 
-        lexer.next();
-
         let pre_parameter = params[0].clone();
 
+        if pre_parameter.default.is_some() || pre_parameter.rest {
+            return parser_error!(
+                first_token.unwrap().line,
+                "A pre-parameter can't have a default value or be a rest parameter",
+            );
+        }
+
+        // conversion function, which doesn't have any other parameters and
+        // returns the type it converts to
+        if matches!(lexer.next(), some_token_pat!(TokenData::As)) {
+            let return_type = parse_type(lexer)?;
+            let body = parse_expression(lexer)?;
+
+            return Ok(expression!(
+                FunctionDefinition {
+                    name: Some(format!(
+                        "{}{:?}",
+                        CONVERSION_FUNCTION_PREFIX, return_type
+                    )),
+                    pre_parameter: Some(pre_parameter),
+                    params: vec![],
+                    return_type,
+                    body: Box::new(body),
+                    type_name: None,
+                    captures: vec![],
+                },
+                line
+            ));
+        }
+
         let name = match lexer.next() {
             some_token_pat!(TokenData::ValueIdentifier(n)) => n,
 
@@ -179,6 +298,8 @@ pub fn parse_function_definition(
                 params,
                 return_type,
                 body: Box::new(body),
+                type_name: None,
+                captures: vec![],
             },
             line
         ))
@@ -193,6 +314,8 @@ pub fn parse_function_definition(
                 params,
                 return_type,
                 body: Box::new(body),
+                type_name,
+                captures: vec![],
             },
             line
         ))
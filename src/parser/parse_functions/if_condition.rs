@@ -4,7 +4,8 @@ use crate::{
 };
 
 use super::super::{
-    expression::Expression, parse_expression, util::token_matches,
+    expression::Expression, parse_condition, parse_expression,
+    util::token_matches,
 };
 
 /// Called after Token::LeftParenCurly
@@ -12,7 +13,7 @@ pub fn parse_if_condition(
     lexer: &mut Lexer,
     line: usize,
 ) -> Result<Expression, ParserError> {
-    let condition = Box::new(parse_expression(lexer)?);
+    let condition = Box::new(parse_condition(lexer)?);
     let true_branch = Box::new(parse_expression(lexer)?);
     let mut else_branch: Option<Box<Expression>> = None;
 
@@ -0,0 +1,130 @@
+use crate::{
+    expression,
+    lexer::{
+        token::{Token, TokenData},
+        Lexer,
+    },
+    parser::{
+        expression::InterfaceFunction, parser_error::ParserError, r#type::Type,
+    },
+    parser_error, parser_error_eof, some_token_pat,
+};
+
+use super::{
+    super::expression::Expression,
+    function_definition::parse_params,
+    r#type::{expect_token, parse_type},
+};
+
+/// Called after Token::Interface
+pub fn parse_interface_definition(
+    lexer: &mut Lexer,
+    top_level: bool,
+    line: usize,
+) -> Result<Expression, ParserError> {
+    if !top_level {
+        return parser_error!(
+            line,
+            "Interface definitions are only allowed at the top level"
+        );
+    }
+
+    let name = match lexer.next() {
+        some_token_pat!(TokenData::ValueIdentifier(identifier)) => identifier,
+
+        Some(t) => {
+            return parser_error!(
+                t.line,
+                "Expected interface name, got {:?}",
+                t.data
+            )
+        }
+        None => return parser_error_eof!("Expected interface name"),
+    };
+
+    expect_token(lexer, TokenData::LeftParenCurly, "{")?;
+
+    let mut functions: Vec<InterfaceFunction> = vec![];
+
+    loop {
+        match lexer.next() {
+            some_token_pat!(TokenData::RightParenCurly) => break,
+
+            some_token_pat!(TokenData::Fun, line) => {
+                functions.push(parse_interface_function(lexer, line)?)
+            }
+
+            Some(t) => {
+                return parser_error!(
+                    t.line,
+                    "Expected fun or }}, got {:?}",
+                    t.data
+                )
+            }
+            None => return parser_error_eof!("Expected }}"),
+        }
+    }
+
+    Ok(expression!(InterfaceDefinition { name, functions }, line))
+}
+
+/// Called after Token::Fun inside of an interface, parses a value function
+/// signature like (val n this):negate() this
+fn parse_interface_function(
+    lexer: &mut Lexer,
+    line: usize,
+) -> Result<InterfaceFunction, ParserError> {
+    expect_token(lexer, TokenData::LeftParenNormal, "(")?;
+
+    let pre_params = parse_params(lexer)?;
+
+    if pre_params.len() != 1 {
+        return parser_error!(
+            line,
+            "Interface functions need exactly one pre-parameter"
+        );
+    }
+
+    if pre_params[0].type_ != Type::This || !pre_params[0].constant {
+        return parser_error!(
+            line,
+            "The pre-parameter of an interface function has to be val and have the type this"
+        );
+    }
+
+    expect_token(lexer, TokenData::Colon, ":")?;
+
+    let name = match lexer.next() {
+        some_token_pat!(TokenData::ValueIdentifier(n)) => n,
+
+        Some(t) => {
+            return parser_error!(
+                t.line,
+                "Expected function name, got {:?}",
+                t.data
+            )
+        }
+        None => return parser_error_eof!("Expected function name"),
+    };
+
+    expect_token(lexer, TokenData::LeftParenNormal, "(")?;
+
+    let params = parse_params(lexer)?;
+
+    if params.iter().any(|p| p.default.is_some() || p.rest) {
+        return parser_error!(
+            line,
+            "Interface functions can't have default values or rest parameters"
+        );
+    }
+
+    let return_type = parse_type(lexer)?;
+
+    Ok(InterfaceFunction {
+        name,
+        param_names: params.iter().map(|p| p.name.clone()).collect(),
+        param_types: params.iter().map(|p| p.type_.clone()).collect(),
+        var_params: params.iter().map(|p| !p.constant).collect(),
+        return_type,
+    })
+}
@@ -0,0 +1,91 @@
+use crate::{
+    expression,
+    lexer::{
+        token::{Token, TokenData},
+        Lexer,
+    },
+    parser::{expression::Expression, parser_error::ParserError},
+    parser_error, parser_error_eof, some_token_pat,
+};
+
+use super::super::{parse_condition, parse_expression};
+
+/// Called after Token::While
+pub fn parse_while(
+    lexer: &mut Lexer,
+    line: usize,
+) -> Result<Expression, ParserError> {
+    let condition = Box::new(parse_condition(lexer)?);
+    let body = Box::new(parse_expression(lexer)?);
+
+    Ok(expression!(While { condition, body }, line))
+}
+
+/// Called after Token::Loop
+pub fn parse_loop(
+    lexer: &mut Lexer,
+    line: usize,
+) -> Result<Expression, ParserError> {
+    let body = Box::new(parse_expression(lexer)?);
+
+    Ok(expression!(Loop { body }, line))
+}
+
+/// Called after Token::For
+pub fn parse_for(
+    lexer: &mut Lexer,
+    line: usize,
+) -> Result<Expression, ParserError> {
+    let variable = match lexer.next() {
+        some_token_pat!(TokenData::ValueIdentifier(name)) => name,
+
+        Some(t) => {
+            return parser_error!(
+                t.line,
+                "Expected identifier, got {:?}",
+                t.data
+            )
+        }
+        None => return parser_error_eof!("Expected identifier"),
+    };
+
+    match lexer.next() {
+        some_token_pat!(TokenData::In) => {}
+
+        Some(t) => {
+            return parser_error!(t.line, "Expected in, got {:?}", t.data)
+        }
+        None => return parser_error_eof!("Expected in"),
+    };
+
+    let iterable = Box::new(parse_condition(lexer)?);
+    let body = Box::new(parse_expression(lexer)?);
+
+    Ok(expression!(
+        For {
+            variable,
+            iterable,
+            body
+        },
+        line
+    ))
+}
+
+/// Called after Token::Break
+pub fn parse_break(
+    lexer: &mut Lexer,
+    line: usize,
+) -> Result<Expression, ParserError> {
+    // break only has a value if there's something other than the end of the
+    // expression after it
+    let value = match lexer.peek() {
+        some_token_pat!(
+            TokenData::Semicolon | TokenData::RightParenCurly | TokenData::Else
+        )
+        | None => None,
+
+        Some(_) => Some(Box::new(parse_expression(lexer)?)),
+    };
+
+    Ok(expression!(Break { value }, line))
+}
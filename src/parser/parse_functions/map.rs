@@ -0,0 +1,70 @@
+use crate::{
+    expression,
+    lexer::{
+        token::{Token, TokenData},
+        Lexer,
+    },
+    parser::{expr_binding_power, parser_error::ParserError},
+    parser_error, parser_error_eof, some_token_pat,
+};
+
+use super::super::{expression::Expression, parse_expression};
+
+/// Called after Token::Map
+pub fn parse_make_map(
+    lexer: &mut Lexer,
+    line: usize,
+) -> Result<Expression, ParserError> {
+    match lexer.next() {
+        some_token_pat!(TokenData::LeftParenCurly) => {}
+
+        Some(t) => {
+            return parser_error!(t.line, "Expected {{, got {:?}", t.data)
+        }
+        None => return parser_error_eof!("Expected {{"),
+    }
+
+    let mut entries: Vec<(Expression, Expression)> = vec![];
+
+    loop {
+        // this also allows a trailing comma
+        if matches!(lexer.peek(), some_token_pat!(TokenData::RightParenCurly)) {
+            lexer.next();
+            break;
+        }
+
+        // the key is parsed with a binding power higher than the one of the
+        // colon, so the colon isn't treated as a value function call (keys
+        // which are more complicated than that need to be put in parentheses)
+        let key = expr_binding_power(lexer, 61, false)?;
+
+        match lexer.next() {
+            some_token_pat!(TokenData::Colon) => {}
+
+            Some(t) => {
+                return parser_error!(t.line, "Expected :, got {:?}", t.data)
+            }
+            None => return parser_error_eof!("Expected :"),
+        }
+
+        let value = parse_expression(lexer)?;
+        entries.push((key, value));
+
+        match lexer.next() {
+            some_token_pat!(TokenData::Comma) => {}
+
+            some_token_pat!(TokenData::RightParenCurly) => break,
+
+            Some(t) => {
+                return parser_error!(
+                    t.line,
+                    "Expected , or }}, got {:?}",
+                    t.data
+                )
+            }
+            None => return parser_error_eof!("Expected , or }}"),
+        }
+    }
+
+    Ok(expression!(MakeMap { entries }, line))
+}
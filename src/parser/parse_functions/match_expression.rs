@@ -0,0 +1,327 @@
+use crate::{
+    expression,
+    lexer::{
+        token::{Token, TokenData},
+        Lexer,
+    },
+    parser::{parser_error::ParserError, util::token_matches},
+    parser_error, parser_error_eof, some_token_pat,
+};
+
+use super::super::{
+    expression::{Expression, MatchArm, Pattern},
+    parse_condition, parse_expression,
+};
+
+/// Called after Token::Match
+pub fn parse_match(
+    lexer: &mut Lexer,
+    line: usize,
+) -> Result<Expression, ParserError> {
+    let value = Box::new(parse_condition(lexer)?);
+
+    match lexer.next() {
+        some_token_pat!(TokenData::LeftParenCurly) => {}
+
+        Some(t) => {
+            return parser_error!(t.line, "Expected {{, got {:?}", t.data)
+        }
+        None => return parser_error_eof!("Expected {{"),
+    }
+
+    let mut arms: Vec<MatchArm> = vec![];
+
+    loop {
+        let arm_line = match lexer.peek() {
+            some_token_pat!(TokenData::RightParenCurly) => {
+                lexer.next();
+                break;
+            }
+
+            Some(t) => t.line,
+            None => return parser_error_eof!("Expected }}"),
+        };
+
+        let pattern = if token_matches(&lexer.peek(), &TokenData::Else) {
+            lexer.next();
+            None
+        } else {
+            Some(parse_pattern(lexer)?)
+        };
+
+        match lexer.next() {
+            some_token_pat!(TokenData::MatchArrow) => {}
+
+            Some(t) => {
+                return parser_error!(t.line, "Expected ->, got {:?}", t.data)
+            }
+            None => return parser_error_eof!("Expected ->"),
+        }
+
+        let body = parse_expression(lexer)?;
+
+        arms.push(MatchArm {
+            pattern,
+            body,
+            line: arm_line,
+        });
+
+        if token_matches(&lexer.peek(), &TokenData::Comma) {
+            lexer.next();
+        }
+    }
+
+    Ok(expression!(Match { value, arms }, line))
+}
+
+fn parse_pattern(lexer: &mut Lexer) -> Result<Pattern, ParserError> {
+    use TokenData::*;
+
+    let pattern = match lexer.next() {
+        some_token_pat!(ValueString(v), line) => {
+            Pattern::Literal(Box::new(expression!(String(v), line)))
+        }
+        some_token_pat!(ValueChar(v), line) => {
+            Pattern::Literal(Box::new(expression!(Char(v), line)))
+        }
+        some_token_pat!(ValueInt(v), line) => {
+            Pattern::Literal(Box::new(expression!(Int(v), line)))
+        }
+        some_token_pat!(ValueFloat(v), line) => {
+            Pattern::Literal(Box::new(expression!(Float(v), line)))
+        }
+        some_token_pat!(True, line) => {
+            Pattern::Literal(Box::new(expression!(Bool(true), line)))
+        }
+        some_token_pat!(False, line) => {
+            Pattern::Literal(Box::new(expression!(Bool(false), line)))
+        }
+        some_token_pat!(TokenData::Null, line) => {
+            Pattern::Literal(Box::new(expression!(Null, line)))
+        }
+
+        // negative numbers
+        some_token_pat!(Minus, line) => match lexer.next() {
+            some_token_pat!(ValueInt(v)) => {
+                Pattern::Literal(Box::new(expression!(Int(-v), line)))
+            }
+            some_token_pat!(ValueFloat(v)) => {
+                Pattern::Literal(Box::new(expression!(Float(-v), line)))
+            }
+
+            Some(t) => {
+                return parser_error!(
+                    t.line,
+                    "Expected a number after -, got {:?}",
+                    t.data
+                )
+            }
+            None => return parser_error_eof!("Expected a number after -"),
+        },
+
+        some_token_pat!(ValueIdentifier(name)) => match lexer.peek() {
+            // enum variant, both Element:Empty and Element::Empty work
+            some_token_pat!(Colon | DoubleColon) => {
+                lexer.next();
+
+                let variant = match lexer.next() {
+                    some_token_pat!(ValueIdentifier(v)) => v,
+
+                    Some(t) => {
+                        return parser_error!(
+                            t.line,
+                            "Expected variant name, got {:?}",
+                            t.data
+                        )
+                    }
+                    None => return parser_error_eof!("Expected variant name"),
+                };
+
+                // a variant without {} matches all of its values
+                let (fields, rest) =
+                    if token_matches(&lexer.peek(), &LeftParenCurly) {
+                        lexer.next();
+                        parse_field_patterns(lexer)?
+                    } else {
+                        (vec![], true)
+                    };
+
+                Pattern::EnumVariant {
+                    name,
+                    variant,
+                    fields,
+                    rest,
+                }
+            }
+
+            some_token_pat!(LeftParenCurly) => {
+                lexer.next();
+                let (fields, rest) = parse_field_patterns(lexer)?;
+
+                Pattern::Struct {
+                    name: Some(name),
+                    fields,
+                    rest,
+                }
+            }
+
+            _ => Pattern::Binding(name),
+        },
+
+        some_token_pat!(LeftParenCurly) => {
+            let (fields, rest) = parse_field_patterns(lexer)?;
+
+            Pattern::Struct {
+                name: None,
+                fields,
+                rest,
+            }
+        }
+
+        some_token_pat!(LessThan) => {
+            let mut elements: Vec<Pattern> = vec![];
+
+            loop {
+                elements.push(parse_pattern(lexer)?);
+
+                match lexer.next() {
+                    some_token_pat!(Comma) => {}
+
+                    some_token_pat!(GreaterThan) => break,
+
+                    Some(t) => {
+                        return parser_error!(
+                            t.line,
+                            "Expected , or >, got {:?}",
+                            t.data
+                        )
+                    }
+                    None => return parser_error_eof!("Expected , or >"),
+                }
+            }
+
+            Pattern::Tuple(elements)
+        }
+
+        some_token_pat!(LeftParenSquare) => parse_array_pattern(lexer)?,
+
+        Some(t) => {
+            return parser_error!(t.line, "Expected pattern, got {:?}", t.data)
+        }
+        None => return parser_error_eof!("Expected pattern"),
+    };
+
+    Ok(pattern)
+}
+
+/// Called after Token::LeftParenCurly in a struct or enum variant pattern.
+/// Returns the fields and whether the pattern ends with ...
+fn parse_field_patterns(
+    lexer: &mut Lexer,
+) -> Result<(Vec<(String, Pattern)>, bool), ParserError> {
+    let mut fields: Vec<(String, Pattern)> = vec![];
+    let mut rest = false;
+
+    loop {
+        match lexer.next() {
+            some_token_pat!(TokenData::RightParenCurly) => break,
+
+            some_token_pat!(TokenData::ThreeDots) => {
+                rest = true;
+
+                match lexer.next() {
+                    some_token_pat!(TokenData::RightParenCurly) => break,
+
+                    Some(t) => {
+                        return parser_error!(
+                            t.line,
+                            "Expected }} after ..., got {:?}",
+                            t.data
+                        )
+                    }
+                    None => return parser_error_eof!("Expected }}"),
+                }
+            }
+
+            some_token_pat!(TokenData::ValueIdentifier(field)) => {
+                // a field without a pattern binds it to a variable with the
+                // same name
+                let pattern = if token_matches(&lexer.peek(), &TokenData::Colon)
+                {
+                    lexer.next();
+                    parse_pattern(lexer)?
+                } else {
+                    Pattern::Binding(field.clone())
+                };
+
+                fields.push((field, pattern));
+
+                if token_matches(&lexer.peek(), &TokenData::Comma) {
+                    lexer.next();
+                }
+            }
+
+            Some(t) => {
+                return parser_error!(
+                    t.line,
+                    "Expected field name, got {:?}",
+                    t.data
+                )
+            }
+            None => return parser_error_eof!("Expected }}"),
+        }
+    }
+
+    Ok((fields, rest))
+}
+
+/// Called after Token::LeftParenSquare in a pattern
+fn parse_array_pattern(lexer: &mut Lexer) -> Result<Pattern, ParserError> {
+    let mut groups: Vec<Vec<Pattern>> = vec![vec![]];
+
+    if token_matches(&lexer.peek(), &TokenData::RightParenSquare) {
+        lexer.next();
+        return Ok(Pattern::Array(groups));
+    }
+
+    loop {
+        match lexer.peek() {
+            some_token_pat!(TokenData::ThreeDots, line) => {
+                lexer.next();
+
+                // the group before this ... would be empty, so the two of
+                // them wouldn't mean anything different from a single one
+                if groups.len() > 1 && groups.last().unwrap().is_empty() {
+                    return parser_error!(
+                        line,
+                        "An array pattern cannot have two ... in a row"
+                    );
+                }
+
+                groups.push(vec![]);
+            }
+
+            _ => {
+                let element = parse_pattern(lexer)?;
+                groups.last_mut().unwrap().push(element);
+            }
+        }
+
+        match lexer.next() {
+            some_token_pat!(TokenData::Comma) => {}
+
+            some_token_pat!(TokenData::RightParenSquare) => break,
+
+            Some(t) => {
+                return parser_error!(
+                    t.line,
+                    "Expected , or ], got {:?}",
+                    t.data
+                )
+            }
+            None => return parser_error_eof!("Expected , or ]"),
+        }
+    }
+
+    Ok(Pattern::Array(groups))
+}
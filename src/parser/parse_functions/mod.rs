@@ -1,9 +1,17 @@
+pub mod array;
 pub mod block;
 pub mod create_variable_assignment;
+pub mod enum_definition;
 pub mod function_arguments;
 pub mod function_definition;
 pub mod if_condition;
+pub mod interface_definition;
+pub mod loops;
 pub mod make_struct;
+pub mod map;
+pub mod match_expression;
 pub mod struct_definition;
+pub mod tuple;
 pub mod r#type;
+pub mod type_alias;
 pub mod variable_definition;
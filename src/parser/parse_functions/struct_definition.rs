@@ -6,16 +6,27 @@ use crate::{
         token::{Token, TokenData},
         Lexer,
     },
-    parser::{parser_error::ParserError, r#type::Type, util::token_matches},
+    parser::{
+        parse_expression, parser_error::ParserError, r#type::Type,
+        util::token_matches,
+    },
     parser_error, parser_error_eof, some_token_pat,
 };
 
 use super::{super::expression::Expression, r#type::parse_type};
 
+/// The types of a struct's fields and the default values of the ones which
+/// have them
+type Fields = (BTreeMap<String, Type>, BTreeMap<String, Expression>);
+
+/// Returns the fields and the default values of the fields which have them,
+/// default values are an error if they aren't allowed
 pub fn parse_fields(
     lexer: &mut Lexer,
-) -> Result<BTreeMap<String, Type>, ParserError> {
+    allow_defaults: bool,
+) -> Result<Fields, ParserError> {
     let mut fields: BTreeMap<String, Type> = BTreeMap::new();
+    let mut defaults: BTreeMap<String, Expression> = BTreeMap::new();
 
     loop {
         let next = lexer.next();
@@ -38,6 +49,18 @@ pub fn parse_fields(
                     );
                 }
 
+                if let some_token_pat!(TokenData::Equals, line) = lexer.peek() {
+                    if !allow_defaults {
+                        return parser_error!(
+                            line,
+                            "Only named structs can have default values"
+                        );
+                    }
+
+                    lexer.next();
+                    defaults.insert(name.clone(), parse_expression(lexer)?);
+                }
+
                 fields.insert(name, type_);
 
                 // skip optional comma
@@ -57,7 +80,7 @@ pub fn parse_fields(
         }
     }
 
-    return Ok(fields);
+    return Ok((fields, defaults));
 }
 
 /// Called after Token::Struct
@@ -102,7 +125,14 @@ pub fn parse_struct_definition(
         );
     }
 
-    let fields = parse_fields(lexer)?;
+    let (fields, defaults) = parse_fields(lexer, name.is_some())?;
 
-    Ok(expression!(StructDefinition { name, fields }, line))
+    Ok(expression!(
+        StructDefinition {
+            name,
+            fields,
+            defaults
+        },
+        line
+    ))
 }
@@ -0,0 +1,44 @@
+use crate::{
+    expression,
+    lexer::{
+        token::{Token, TokenData},
+        Lexer,
+    },
+    parser::{expr_binding_power, parser_error::ParserError},
+    parser_error, parser_error_eof, some_token_pat,
+};
+
+use super::super::expression::Expression;
+
+/// Called after Token::LessThan at the start of an expression
+pub fn parse_make_tuple(
+    lexer: &mut Lexer,
+    line: usize,
+) -> Result<Expression, ParserError> {
+    let mut elements: Vec<Expression> = vec![];
+
+    loop {
+        // the elements are parsed with a binding power higher than the one of
+        // the comparison operators, so the > at the end isn't treated as a
+        // greater than (elements which use comparisons or and/or need to be
+        // put in parentheses)
+        elements.push(expr_binding_power(lexer, 12, false)?);
+
+        match lexer.next() {
+            some_token_pat!(TokenData::Comma) => {}
+
+            some_token_pat!(TokenData::GreaterThan) => break,
+
+            Some(t) => {
+                return parser_error!(
+                    t.line,
+                    "Expected , or >, got {:?}",
+                    t.data
+                )
+            }
+            None => return parser_error_eof!("Expected , or >"),
+        }
+    }
+
+    Ok(expression!(MakeTuple { elements }, line))
+}
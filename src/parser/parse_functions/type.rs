@@ -1,13 +1,14 @@
 use crate::{
     expression_pat,
     lexer::{
-        token::{Token, TokenData::*},
+        token::{Token, TokenData, TokenData::*},
         Lexer,
     },
     parser::{
         expression::{Expression, ExpressionData},
         parser_error::ParserError,
         r#type::Type,
+        util::token_matches,
     },
     parser_error, parser_error_eof, some_token_pat,
 };
@@ -24,7 +25,81 @@ pub fn parse_type(lexer: &mut Lexer) -> Result<Type, ParserError> {
         some_token_pat!(Bool) => Type::Bool,
         some_token_pat!(Char) => Type::Char,
         some_token_pat!(String) => Type::String,
-        some_token_pat!(ValueIdentifier(name)) => Type::UserDefined(name),
+        some_token_pat!(ThisNoncapital) => Type::This,
+        some_token_pat!(ValueIdentifier(name)) => {
+            if matches!(lexer.peek(), some_token_pat!(Colon)) {
+                lexer.next();
+
+                match lexer.next() {
+                    some_token_pat!(ValueIdentifier(variant)) => {
+                        Type::EnumVariant { name, variant }
+                    }
+
+                    Some(t) => {
+                        return parser_error!(
+                            t.line,
+                            "Expected enum variant, got {:?}",
+                            t.data
+                        )
+                    }
+                    None => return parser_error_eof!("Expected enum variant"),
+                }
+            } else {
+                Type::UserDefined(name)
+            }
+        }
+
+        some_token_pat!(Array) => {
+            expect_token(lexer, LeftParenSquare, "[")?;
+            let element_type = parse_type(lexer)?;
+            expect_token(lexer, RightParenSquare, "]")?;
+
+            Type::Array(Box::new(element_type))
+        }
+
+        some_token_pat!(LessThan) => {
+            let mut element_types = vec![parse_type(lexer)?];
+
+            loop {
+                match lexer.next() {
+                    some_token_pat!(Comma) => {
+                        element_types.push(parse_type(lexer)?)
+                    }
+
+                    some_token_pat!(GreaterThan) => break,
+
+                    Some(t) => {
+                        return parser_error!(
+                            t.line,
+                            "Expected , or >, got {:?}",
+                            t.data
+                        )
+                    }
+                    None => return parser_error_eof!("Expected , or >"),
+                }
+            }
+
+            Type::Tuple(element_types)
+        }
+
+        some_token_pat!(Map) => {
+            expect_token(lexer, LeftParenSquare, "[")?;
+            let key_type = parse_type(lexer)?;
+            expect_token(lexer, Comma, ",")?;
+            let value_type = parse_type(lexer)?;
+            expect_token(lexer, RightParenSquare, "]")?;
+
+            Type::Map {
+                key_type: Box::new(key_type),
+                value_type: Box::new(value_type),
+            }
+        }
+
+        some_token_pat!(Nullable) => {
+            Type::Nullable(Box::new(parse_type(lexer)?))
+        }
+
+        some_token_pat!(Fun) => parse_function_type(lexer)?,
 
         some_token_pat!(Struct, line) => {
             let struct_definition =
@@ -48,3 +123,73 @@ pub fn parse_type(lexer: &mut Lexer) -> Result<Type, ParserError> {
 
     Ok(type_)
 }
+
+pub fn expect_token(
+    lexer: &mut Lexer,
+    expected: TokenData,
+    name: &str,
+) -> Result<(), ParserError> {
+    match lexer.next() {
+        Some(t) if t.data == expected => Ok(()),
+
+        Some(t) => parser_error!(t.line, "Expected {}, got {:?}", name, t.data),
+        None => parser_error_eof!("Expected {}", name),
+    }
+}
+
+/// Called after Token::Fun
+pub fn parse_function_type(lexer: &mut Lexer) -> Result<Type, ParserError> {
+    match lexer.next() {
+        some_token_pat!(LeftParenNormal) => {}
+
+        Some(t) => {
+            return parser_error!(t.line, "Expected (, got {:?}", t.data)
+        }
+        None => return parser_error_eof!("Expected ("),
+    }
+
+    let mut param_types: Vec<Type> = vec![];
+    let mut var_params: Vec<bool> = vec![];
+
+    if token_matches(&lexer.peek(), &RightParenNormal) {
+        lexer.next();
+    } else {
+        loop {
+            // parameters can have an optional val/var, same as in function
+            // definitions
+            let var = match lexer.peek() {
+                some_token_pat!(Val | Var) => {
+                    matches!(lexer.next(), some_token_pat!(Var))
+                }
+
+                _ => false,
+            };
+
+            param_types.push(parse_type(lexer)?);
+            var_params.push(var);
+
+            match lexer.next() {
+                some_token_pat!(Comma) => {}
+
+                some_token_pat!(RightParenNormal) => break,
+
+                Some(t) => {
+                    return parser_error!(
+                        t.line,
+                        "Expected , or ), got {:?}",
+                        t.data
+                    )
+                }
+                None => return parser_error_eof!("Expected , or )"),
+            }
+        }
+    }
+
+    let return_type = parse_type(lexer)?;
+
+    Ok(Type::Function {
+        param_types,
+        var_params,
+        return_type: Box::new(return_type),
+    })
+}
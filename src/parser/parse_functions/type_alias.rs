@@ -0,0 +1,51 @@
+use crate::{
+    expression,
+    lexer::{
+        token::{Token, TokenData},
+        Lexer,
+    },
+    parser::parser_error::ParserError,
+    parser_error, parser_error_eof, some_token_pat,
+};
+
+use super::{super::expression::Expression, r#type::parse_type};
+
+/// Called after Token::Type
+pub fn parse_type_alias(
+    lexer: &mut Lexer,
+    top_level: bool,
+    line: usize,
+) -> Result<Expression, ParserError> {
+    if !top_level {
+        return parser_error!(
+            line,
+            "Type aliases are only allowed at the top level"
+        );
+    }
+
+    let name = match lexer.next() {
+        some_token_pat!(TokenData::ValueIdentifier(identifier)) => identifier,
+
+        Some(t) => {
+            return parser_error!(
+                t.line,
+                "Expected type name, got {:?}",
+                t.data
+            )
+        }
+        None => return parser_error_eof!("Expected type name"),
+    };
+
+    match lexer.next() {
+        some_token_pat!(TokenData::Equals) => {}
+
+        Some(t) => {
+            return parser_error!(t.line, "Expected =, got {:?}", t.data)
+        }
+        None => return parser_error_eof!("Expected ="),
+    }
+
+    let type_ = parse_type(lexer)?;
+
+    Ok(expression!(TypeAlias { name, type_ }, line))
+}
@@ -13,7 +13,64 @@ use super::{
     r#type::parse_type,
 };
 
-/// Called after Token::LeftParenCurly
+/// Parse the names in `val <a, b> = ...`, called after Token::LessThan
+fn parse_destructured_names(
+    lexer: &mut Lexer,
+) -> Result<Vec<std::string::String>, ParserError> {
+    let mut names = vec![];
+
+    loop {
+        match lexer.next() {
+            some_token_pat!(ValueIdentifier(identifier)) => {
+                names.push(identifier)
+            }
+
+            None => return parser_error_eof!("Expected variable name"),
+            Some(t) => {
+                return parser_error!(
+                    t.line,
+                    "Variable name should be an identifier"
+                )
+            }
+        }
+
+        match lexer.next() {
+            some_token_pat!(Comma) => {}
+
+            some_token_pat!(GreaterThan) => break,
+
+            Some(t) => {
+                return parser_error!(
+                    t.line,
+                    "Expected , or >, got {:?}",
+                    t.data
+                )
+            }
+            None => return parser_error_eof!("Expected , or >"),
+        }
+    }
+
+    Ok(names)
+}
+
+/// Parse the optional type and the value which come after the variable name
+fn parse_type_and_value(
+    lexer: &mut Lexer,
+) -> Result<(Option<Box<Type>>, Box<Expression>), ParserError> {
+    let mut type_: Option<Box<Type>> = None;
+
+    if !token_matches(&lexer.peek(), &Equals) {
+        type_ = Some(Box::new(parse_type(lexer)?));
+    }
+
+    lexer.next();
+
+    let value = Box::new(parse_expression(lexer)?);
+
+    Ok((type_, value))
+}
+
+/// Called after Token::Val or Token::Var
 pub fn parse_variable_definition(
     lexer: &mut Lexer,
     start_token: Token,
@@ -23,6 +80,22 @@ pub fn parse_variable_definition(
     let name = match name {
         some_token_pat!(ValueIdentifier(identifier)) => identifier,
 
+        // destructuring a tuple
+        some_token_pat!(LessThan) => {
+            let names = parse_destructured_names(lexer)?;
+            let (type_, value) = parse_type_and_value(lexer)?;
+
+            return Ok(expression!(
+                TupleDefinition {
+                    constant: start_token.data == Val,
+                    names,
+                    value,
+                    type_,
+                },
+                start_token.line
+            ));
+        }
+
         None => return parser_error_eof!("Expected variable name"),
         Some(t) => {
             return parser_error!(
@@ -32,15 +105,7 @@ pub fn parse_variable_definition(
         }
     };
 
-    let mut type_: Option<Box<Type>> = None;
-
-    if !token_matches(&lexer.peek(), &Equals) {
-        type_ = Some(Box::new(parse_type(lexer)?));
-    }
-
-    lexer.next();
-
-    let value = Box::new(parse_expression(lexer)?);
+    let (type_, value) = parse_type_and_value(lexer)?;
 
     Ok(expression!(
         VariableDefinition {
@@ -10,8 +10,22 @@ pub enum Type {
     Char,
     String,
 
+    Array(Box<Type>),
+    Tuple(Vec<Type>),
+    Map {
+        key_type: Box<Type>,
+        value_type: Box<Type>,
+    },
+
+    // either a value of the inner type or null
+    Nullable(Box<Type>),
+
     Function {
         param_types: Vec<Type>,
+
+        // one for each parameter, true if the parameter is declared with var
+        var_params: Vec<bool>,
+
         return_type: Box<Type>,
     },
 
@@ -23,4 +37,19 @@ pub enum Type {
     },
 
     UserDefined(String),
+
+    // a specific variant of an enum, like Element:Text
+    EnumVariant {
+        name: String,
+        variant: String,
+    },
+
+    // the type which implements an interface, this is only used in the
+    // signatures of the interface's functions
+    This,
+
+    // The parser gives interfaces as UserDefined types, the analyzer replaces
+    // them with this one (see Environment::resolve_type). Values of an
+    // interface are stored as a Value::Any, so they keep their actual type
+    Interface(String),
 }
@@ -1,11 +1,14 @@
 use std::collections::HashMap;
 
-use crate::compiler::{
-    chunk::Chunk,
-    value::{Object, Value},
+use crate::{
+    compiler::{
+        chunk::Chunk,
+        value::{MapKey, Object, Value},
+    },
+    runtime_error,
 };
 
-use super::heap::VmHeap;
+use super::{heap::VmHeap, runtime_error::RuntimeError};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExecutionResult {
@@ -17,14 +20,31 @@ pub enum ExecutionResult {
 
     String(String),
 
+    Array(Vec<ExecutionResult>),
+    Tuple(Vec<ExecutionResult>),
+
+    // the entries are sorted by key, so the result doesn't depend on the
+    // order in which the map stores them
+    Map(Vec<(ExecutionResult, ExecutionResult)>),
+
     Function {
         name: Option<String>,
         index: usize,
     },
 
+    NativeFunction {
+        name: String,
+        index: usize,
+    },
+
     Struct {
         fields: HashMap<String, ExecutionResult>,
     },
+
+    Enum {
+        variant: String,
+        fields: HashMap<String, ExecutionResult>,
+    },
 }
 
 impl ExecutionResult {
@@ -53,6 +73,16 @@ impl ExecutionResult {
                 ExecutionResult::Function { name, index }
             }
 
+            Value::Closure(index) => {
+                let object = heap.get_object(index);
+                ExecutionResult::from_object(chunk, heap, object)
+            }
+
+            Value::NativeFunction(index) => ExecutionResult::NativeFunction {
+                name: chunk.native_functions[index].name.clone(),
+                index,
+            },
+
             Value::Struct(struct_fields) => {
                 let mut result_fields: HashMap<String, ExecutionResult> =
                     HashMap::new();
@@ -69,9 +99,124 @@ impl ExecutionResult {
                 }
             }
 
-            // A StructFieldName is a component of a struct, so it shouldn't
-            // ever be an execution result
-            Value::StructFieldName(_) => panic!("Wtf this shouldn't happen"),
+            Value::Enum { variant, fields } => ExecutionResult::Enum {
+                variant,
+                fields: fields
+                    .into_iter()
+                    .map(|(name, v)| {
+                        (name, ExecutionResult::from_value(chunk, heap, v))
+                    })
+                    .collect(),
+            },
+
+            Value::Tuple(elements) => ExecutionResult::Tuple(
+                elements
+                    .into_iter()
+                    .map(|v| ExecutionResult::from_value(chunk, heap, v))
+                    .collect(),
+            ),
+
+            // the host gets the value itself, without the type it had
+            Value::Any { value, .. } => {
+                ExecutionResult::from_value(chunk, heap, *value)
+            }
+
+            // A StructFieldName is a component of a struct and a
+            // DefaultArgument is replaced as soon as the function starts, so
+            // they shouldn't ever be an execution result
+            Value::StructFieldName(_) | Value::DefaultArgument => {
+                panic!("Wtf this shouldn't happen")
+            }
+        }
+    }
+
+    /// Convert back into a value, adding an object to the heap if needed.
+    /// This fails if a map has a key which can't be used as one
+    pub fn into_value(self, heap: &mut VmHeap) -> Result<Value, RuntimeError> {
+        let value = match self {
+            ExecutionResult::Null => Value::Null,
+            ExecutionResult::Char(v) => Value::Char(v),
+            ExecutionResult::Int(v) => Value::Int(v),
+            ExecutionResult::Float(v) => Value::Float(v),
+            ExecutionResult::Bool(v) => Value::Bool(v),
+
+            ExecutionResult::String(v) => {
+                Value::ObjectRef(heap.add_object(Object::String(v)))
+            }
+
+            ExecutionResult::Array(elements) => {
+                let elements = elements
+                    .into_iter()
+                    .map(|element| element.into_value(heap))
+                    .collect::<Result<_, _>>()?;
+
+                Value::ObjectRef(heap.add_object(Object::Array(elements)))
+            }
+
+            ExecutionResult::Enum { variant, fields } => Value::Enum {
+                variant,
+                fields: fields
+                    .into_iter()
+                    .map(|(name, v)| Ok((name, v.into_value(heap)?)))
+                    .collect::<Result<_, RuntimeError>>()?,
+            },
+
+            ExecutionResult::Tuple(elements) => Value::Tuple(
+                elements
+                    .into_iter()
+                    .map(|element| element.into_value(heap))
+                    .collect::<Result<_, _>>()?,
+            ),
+
+            ExecutionResult::Map(entries) => {
+                let entries = entries
+                    .into_iter()
+                    .map(|(key, v)| {
+                        Ok((key.into_map_key()?, v.into_value(heap)?))
+                    })
+                    .collect::<Result<_, RuntimeError>>()?;
+
+                Value::ObjectRef(heap.add_object(Object::Map(entries)))
+            }
+
+            ExecutionResult::Function { index, .. } => Value::Function(index),
+
+            ExecutionResult::NativeFunction { index, .. } => {
+                Value::NativeFunction(index)
+            }
+
+            ExecutionResult::Struct { fields } => Value::Struct(
+                fields
+                    .into_iter()
+                    .map(|(name, v)| Ok((name, v.into_value(heap)?)))
+                    .collect::<Result<_, RuntimeError>>()?,
+            ),
+        };
+
+        Ok(value)
+    }
+
+    fn from_map_key(key: MapKey) -> Self {
+        match key {
+            MapKey::String(v) => ExecutionResult::String(v),
+            MapKey::Int(v) => ExecutionResult::Int(v),
+            MapKey::Char(v) => ExecutionResult::Char(v),
+            MapKey::Bool(v) => ExecutionResult::Bool(v),
+        }
+    }
+
+    fn into_map_key(self) -> Result<MapKey, RuntimeError> {
+        match self {
+            ExecutionResult::String(v) => Ok(MapKey::String(v)),
+            ExecutionResult::Int(v) => Ok(MapKey::Int(v)),
+            ExecutionResult::Char(v) => Ok(MapKey::Char(v)),
+            ExecutionResult::Bool(v) => Ok(MapKey::Bool(v)),
+
+            v => runtime_error!(
+                TypeMismatch,
+                "{:?} cannot be used as a map key",
+                v
+            ),
         }
     }
 
@@ -80,6 +225,81 @@ impl ExecutionResult {
             Object::String(v) => ExecutionResult::String(v),
 
             Object::Value(v) => ExecutionResult::from_value(chunk, heap, v),
+
+            Object::Array(elements) => ExecutionResult::Array(
+                elements
+                    .into_iter()
+                    .map(|v| ExecutionResult::from_value(chunk, heap, v))
+                    .collect(),
+            ),
+
+            Object::Map(entries) => {
+                let mut entries: Vec<(MapKey, Value)> =
+                    entries.into_iter().collect();
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+                ExecutionResult::Map(
+                    entries
+                        .into_iter()
+                        .map(|(key, v)| {
+                            (
+                                ExecutionResult::from_map_key(key),
+                                ExecutionResult::from_value(chunk, heap, v),
+                            )
+                        })
+                        .collect(),
+                )
+            }
+
+            // the captured variables can't be given to the host, so this is
+            // just the function itself
+            Object::Closure { function, .. } => ExecutionResult::Function {
+                name: chunk.functions[function].name.clone(),
+                index: function,
+            },
         }
     }
 }
+
+// conversions between Rust values and execution results, so that the host can
+// pass arguments to Kuon functions and read their results without matching on
+// every variant by hand
+macro_rules! impl_conversions {
+    ($rust_type:ty, $variant:ident) => {
+        impl From<$rust_type> for ExecutionResult {
+            fn from(value: $rust_type) -> Self {
+                ExecutionResult::$variant(value)
+            }
+        }
+
+        impl TryFrom<ExecutionResult> for $rust_type {
+            // the result is given back if it's not the right variant
+            type Error = ExecutionResult;
+
+            fn try_from(result: ExecutionResult) -> Result<Self, Self::Error> {
+                match result {
+                    ExecutionResult::$variant(value) => Ok(value),
+                    other => Err(other),
+                }
+            }
+        }
+    };
+}
+
+impl_conversions!(char, Char);
+impl_conversions!(i64, Int);
+impl_conversions!(f64, Float);
+impl_conversions!(bool, Bool);
+impl_conversions!(String, String);
+
+impl From<&str> for ExecutionResult {
+    fn from(value: &str) -> Self {
+        ExecutionResult::String(value.to_string())
+    }
+}
+
+impl From<()> for ExecutionResult {
+    fn from(_: ()) -> Self {
+        ExecutionResult::Null
+    }
+}
@@ -1,27 +1,283 @@
-use crate::compiler::value::Object;
+use std::mem::size_of;
+
+use crate::compiler::value::{MapKey, Object, Value};
+
+// Number of bytes that can be allocated before the first collection happens
+const DEFAULT_GC_THRESHOLD: usize = 1024 * 1024;
+
+// After a collection, the next one will happen once the heap has grown to
+// this many times the size of the objects that survived
+const GC_HEAP_GROW_FACTOR: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeapStats {
+    pub live_objects: usize,
+    pub bytes: usize,
+    pub collections: usize,
+}
 
 #[derive(Debug)]
 pub struct VmHeap {
-    pub objects: Vec<Object>,
+    // None means the slot has been freed by the garbage collector and its
+    // index is waiting in free_slots to be reused
+    objects: Vec<Option<Object>>,
+    marks: Vec<bool>,
+    free_slots: Vec<usize>,
+
+    live_objects: usize,
+    bytes_allocated: usize,
+    collections: usize,
+
+    threshold: usize,
+    next_collection: usize,
+}
+
+impl Default for VmHeap {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl VmHeap {
     pub fn new() -> Self {
-        Self { objects: vec![] }
+        Self::with_gc_threshold(DEFAULT_GC_THRESHOLD)
+    }
+
+    pub fn with_gc_threshold(threshold: usize) -> Self {
+        Self {
+            objects: vec![],
+            marks: vec![],
+            free_slots: vec![],
+            live_objects: 0,
+            bytes_allocated: 0,
+            collections: 0,
+            threshold,
+            next_collection: threshold,
+        }
+    }
+
+    /// Set the minimum number of allocated bytes which triggers a collection
+    pub fn set_gc_threshold(&mut self, threshold: usize) {
+        self.threshold = threshold;
+        self.next_collection = threshold.max(self.bytes_allocated);
+    }
+
+    pub fn stats(&self) -> HeapStats {
+        HeapStats {
+            live_objects: self.live_objects,
+            bytes: self.bytes_allocated,
+            collections: self.collections,
+        }
     }
 
     // Add an object and return its index
     pub fn add_object(&mut self, object: Object) -> usize {
-        self.objects.push(object);
+        self.live_objects += 1;
+        self.bytes_allocated += object_size(&object);
+
+        if let Some(index) = self.free_slots.pop() {
+            self.objects[index] = Some(object);
+
+            return index;
+        }
+
+        self.objects.push(Some(object));
+        self.marks.push(false);
 
         return self.objects.len() - 1;
     }
 
     pub fn get_object(&self, index: usize) -> Object {
-        self.objects[index].clone()
+        self.get_object_ref(index).clone()
+    }
+
+    /// Borrow an object instead of copying it, which matters for big arrays
+    /// and maps when only one of their elements is needed
+    pub fn get_object_ref(&self, index: usize) -> &Object {
+        match &self.objects[index] {
+            Some(object) => object,
+            None => panic!("Object {} has already been freed", index),
+        }
+    }
+
+    /// Borrow an object to update it in place. Its size is what the heap
+    /// accounts for, so the update mustn't make it grow (insert_map_entry
+    /// takes care of that for maps)
+    pub fn get_object_mut(&mut self, index: usize) -> &mut Object {
+        match &mut self.objects[index] {
+            Some(object) => object,
+            None => panic!("Object {} has already been freed", index),
+        }
+    }
+
+    /// Insert an entry into a map object in place, keeping track of how much
+    /// the map has grown
+    pub fn insert_map_entry(
+        &mut self,
+        index: usize,
+        key: MapKey,
+        value: Value,
+    ) {
+        let object = self.get_object_mut(index);
+        let old_size = object_size(object);
+
+        match object {
+            Object::Map(entries) => {
+                entries.insert(key, value);
+            }
+            o => panic!("Object is not a map: {:?}", o),
+        }
+
+        let new_size = object_size(object);
+        self.bytes_allocated = self.bytes_allocated - old_size + new_size;
+    }
+
+    /// Whether the object is an Object::Value, which is what captured
+    /// variables and var parameters hold a reference to
+    pub fn holds_value(&self, index: usize) -> bool {
+        matches!(self.objects[index], Some(Object::Value(_)))
     }
 
     pub fn update_object(&mut self, index: usize, object: Object) {
-        self.objects[index] = object;
+        let old_size = match &self.objects[index] {
+            Some(old_object) => object_size(old_object),
+            None => panic!("Object {} has already been freed", index),
+        };
+
+        self.bytes_allocated =
+            self.bytes_allocated - old_size + object_size(&object);
+        self.objects[index] = Some(object);
+    }
+
+    pub fn should_collect(&self) -> bool {
+        self.bytes_allocated >= self.next_collection
+    }
+
+    /// Mark every object reachable from the given values (and from the object
+    /// which is about to be allocated, if there is one), then free all the
+    /// other ones
+    pub fn collect_garbage<'a>(
+        &mut self,
+        roots: impl Iterator<Item = &'a Value>,
+        allocating: Option<&Object>,
+    ) {
+        // mark
+        let mut gray: Vec<usize> = vec![];
+
+        for value in roots {
+            value_references(value, &mut gray);
+        }
+
+        if let Some(object) = allocating {
+            object_references(object, &mut gray);
+        }
+
+        while let Some(index) = gray.pop() {
+            if self.marks[index] {
+                continue;
+            }
+
+            self.marks[index] = true;
+
+            if let Some(object) = &self.objects[index] {
+                object_references(object, &mut gray);
+            }
+        }
+
+        // sweep
+        for index in 0..self.objects.len() {
+            if self.marks[index] {
+                self.marks[index] = false;
+                continue;
+            }
+
+            if let Some(object) = self.objects[index].take() {
+                self.live_objects -= 1;
+                self.bytes_allocated -= object_size(&object);
+                self.free_slots.push(index);
+            }
+        }
+
+        self.collections += 1;
+        self.next_collection = self
+            .threshold
+            .max(self.bytes_allocated * GC_HEAP_GROW_FACTOR);
+    }
+}
+
+fn object_size(object: &Object) -> usize {
+    match object {
+        Object::String(s) => size_of::<Object>() + s.capacity(),
+        Object::Value(_) => size_of::<Object>(),
+        Object::Array(elements) => {
+            size_of::<Object>() + elements.capacity() * size_of::<Value>()
+        }
+        Object::Map(entries) => {
+            size_of::<Object>()
+                + entries.capacity()
+                    * (size_of::<MapKey>() + size_of::<Value>())
+        }
+        Object::Closure { upvalues, .. } => {
+            size_of::<Object>() + upvalues.capacity() * size_of::<Value>()
+        }
+    }
+}
+
+/// Push the indices of all objects directly referenced by a value
+fn value_references(value: &Value, references: &mut Vec<usize>) {
+    match value {
+        Value::ObjectRef(index) | Value::Closure(index) => {
+            references.push(*index)
+        }
+
+        Value::Struct(fields) => {
+            for field_value in fields.values() {
+                value_references(field_value, references);
+            }
+        }
+
+        Value::Enum { fields, .. } => {
+            for field_value in fields.values() {
+                value_references(field_value, references);
+            }
+        }
+
+        Value::Tuple(elements) => {
+            for element in elements {
+                value_references(element, references);
+            }
+        }
+
+        Value::Any { value, .. } => value_references(value, references),
+
+        _ => {}
+    }
+}
+
+/// Push the indices of all objects directly referenced by an object
+fn object_references(object: &Object, references: &mut Vec<usize>) {
+    match object {
+        Object::String(_) => {}
+
+        Object::Value(v) => value_references(v, references),
+
+        Object::Array(elements) => {
+            for element in elements {
+                value_references(element, references);
+            }
+        }
+
+        // keys are stored by value, so only the values can reference objects
+        Object::Map(entries) => {
+            for value in entries.values() {
+                value_references(value, references);
+            }
+        }
+
+        Object::Closure { upvalues, .. } => {
+            for upvalue in upvalues {
+                value_references(upvalue, references);
+            }
+        }
     }
 }
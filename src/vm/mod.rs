@@ -2,240 +2,889 @@ use std::collections::HashMap;
 
 use execution_result::ExecutionResult;
 use heap::VmHeap;
-use operations::{call::execute_call, store::execute_store};
-use stack_value_wrapper::StackValueWrapper;
+use operations::store::{execute_store, execute_store_index};
+use runtime_error::{RuntimeError, StackTraceEntry};
+use stack_value_wrapper::{StackValueWrapper, StackValueWrapperCameFrom};
 use util::{
-    add, divide, equal, greater_than, greater_than_or_equal, is_true,
-    less_than, less_than_or_equal, multiply, negate, substract,
+    add, cast, divide, equal, get_index, greater_than, greater_than_or_equal,
+    is_true, length, less_than, less_than_or_equal, map_key, multiply, negate,
+    read_variable, substract, CastResult,
 };
 
 use crate::{
     compiler::{
-        chunk::Chunk,
+        chunk::{Chunk, VariableSlot},
         operation::Operation,
         value::{Object, Value},
     },
-    parser::expression::VariableAccessor,
+    parser::{expression::VariableAccessor, r#type::Type},
+    runtime_error,
 };
 
 pub mod execution_result;
-mod heap;
+pub mod heap;
+pub mod native_function;
 mod operations;
+pub mod runtime_error;
 pub mod stack_value_wrapper;
 mod util;
 
-pub fn execute(chunk: &Chunk) -> ExecutionResult {
-    let mut heap = VmHeap::new();
+// Default maximum number of nested function calls before a stack overflow
+// error is raised
+const DEFAULT_MAX_FRAMES: usize = 10_000;
 
-    let value = execute_chunk(chunk, &mut heap, None, &vec![]);
+// Maximum number of entries in a stack trace, a longer one only keeps its
+// innermost and outermost entries
+pub const MAX_STACK_TRACE_ENTRIES: usize = 64;
 
-    ExecutionResult::from_value(chunk, &heap, value)
+#[derive(Debug)]
+struct CallFrame {
+    // index of the function in the chunk's functions field, None for the top
+    // level chunk
+    function: Option<usize>,
+
+    // address of the next operation to execute in the function's code
+    ip: usize,
+
+    // index in the stack where this frame's locals start. The function's
+    // arguments are the first locals, followed by its variables
+    base: usize,
+
+    // Array of bindings between where var arguments came from in the calling
+    // frame and object ref indexes. When the function returns, the variables
+    // will be updated to the values inside those objects
+    variables_to_be_updated: Vec<(StackValueWrapperCameFrom, usize)>,
+
+    // variables captured by the closure which is executing, empty for regular
+    // functions
+    upvalues: Vec<Value>,
+
+    // Some for calls through an interface to a function which returns this,
+    // the return value is put in a Value::Any of this type
+    result_type: Option<Type>,
 }
 
-/// If function is Some, the function with that index in the chunk will be
-/// executed instead of the chunk
-fn execute_chunk(
-    chunk: &Chunk,
-    heap: &mut VmHeap,
-    function: Option<usize>,
-    default_stack: &Vec<StackValueWrapper>,
-) -> Value {
-    let mut stack: Vec<StackValueWrapper> = vec![];
-    stack.append(&mut default_stack.clone());
-    let mut variables: HashMap<String, Value> = HashMap::new();
-
-    let code = match function {
-        Some(index) => &chunk.functions[index].chunk.code,
-        None => &chunk.code,
-    };
-
-    let mut i = 0;
-    loop {
-        if i == code.len() {
-            break;
+#[derive(Debug)]
+pub struct Vm {
+    heap: VmHeap,
+    globals: Vec<Value>,
+    stack: Vec<StackValueWrapper>,
+    frames: Vec<CallFrame>,
+    max_frames: usize,
+}
+
+pub fn execute(chunk: &Chunk) -> Result<ExecutionResult, RuntimeError> {
+    Vm::new().run(chunk)
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        Self {
+            heap: VmHeap::new(),
+            globals: vec![],
+            stack: vec![],
+            frames: vec![],
+            max_frames: DEFAULT_MAX_FRAMES,
         }
+    }
 
-        let operation = &code[i];
+    pub fn heap(&self) -> &VmHeap {
+        &self.heap
+    }
 
-        match operation.clone() {
-            Operation::Push(v) => {
-                stack.push(StackValueWrapper::new(v));
-            }
-            Operation::Pop => {
-                stack.pop();
-            }
+    pub fn heap_mut(&mut self) -> &mut VmHeap {
+        &mut self.heap
+    }
 
-            Operation::PushObject(o) => {
-                let index = heap.add_object(o);
-                let object_ref = Value::ObjectRef(index);
-                stack.push(StackValueWrapper::new(object_ref));
-            }
+    /// Set the maximum number of nested function calls, going over it results
+    /// in a stack overflow error
+    pub fn set_max_frames(&mut self, max_frames: usize) {
+        self.max_frames = max_frames;
+    }
 
-            Operation::Store { name, accessors } => {
-                execute_store(
-                    heap,
-                    &mut stack,
-                    &mut variables,
-                    &name,
-                    &accessors,
-                );
-            }
-            Operation::Load(name) => {
-                let value = variables[&name].clone();
+    /// Execute the chunk's top level code. Globals are kept in the VM, so they
+    /// can still be used by chunks which are run afterwards
+    pub fn run(
+        &mut self,
+        chunk: &Chunk,
+    ) -> Result<ExecutionResult, RuntimeError> {
+        // get rid of anything left over from a previous run which failed
+        self.stack.clear();
+        self.frames.clear();
+
+        self.frames.push(CallFrame {
+            function: None,
+            ip: 0,
+            base: 0,
+            variables_to_be_updated: vec![],
+            upvalues: vec![],
+            result_type: None,
+        });
+
+        let value = match self.execute(chunk) {
+            Ok(value) => value,
+            Err(error) => return Err(self.locate_error(chunk, error)),
+        };
+
+        Ok(ExecutionResult::from_value(chunk, &self.heap, value))
+    }
 
-                stack.push(StackValueWrapper::new_from_name(value, name));
-            }
+    /// Call a function value (a Kuon function or a native one) defined in the
+    /// chunk with the given arguments and return its result
+    pub fn run_function(
+        &mut self,
+        chunk: &Chunk,
+        function: Value,
+        arguments: Vec<Value>,
+    ) -> Result<ExecutionResult, RuntimeError> {
+        self.stack.clear();
+        self.frames.clear();
+
+        // the top level frame starts at the end of the chunk's code, so
+        // execution stops as soon as the function returns to it
+        self.frames.push(CallFrame {
+            function: None,
+            ip: chunk.code.len(),
+            base: 0,
+            variables_to_be_updated: vec![],
+            upvalues: vec![],
+            result_type: None,
+        });
+
+        for argument in arguments {
+            self.stack.push(StackValueWrapper::new(argument));
+        }
+        self.stack.push(StackValueWrapper::new(function));
 
-            Operation::Add => {
-                let b = stack.pop().unwrap();
-                let a = stack.pop().unwrap();
-                stack.push(add(heap, &a, &b));
-            }
-            Operation::Substract => {
-                let b = stack.pop().unwrap();
-                let a = stack.pop().unwrap();
-                stack.push(substract(heap, &a, &b));
-            }
-            Operation::Multiply => {
-                let b = stack.pop().unwrap();
-                let a = stack.pop().unwrap();
-                stack.push(multiply(heap, &a, &b));
-            }
-            Operation::Divide => {
-                let b = stack.pop().unwrap();
-                let a = stack.pop().unwrap();
-                stack.push(divide(heap, &a, &b));
-            }
-            Operation::Negate => {
-                let value = stack.pop().unwrap();
-                stack.push(negate(heap, &value));
-            }
-            Operation::Equal => {
-                let b = stack.pop().unwrap();
-                let a = stack.pop().unwrap();
-                stack.push(equal(heap, &a, &b));
-            }
-            Operation::LessThan => {
-                let b = stack.pop().unwrap();
-                let a = stack.pop().unwrap();
-                stack.push(less_than(heap, &a, &b));
-            }
-            Operation::LessThanOrEqual => {
-                let b = stack.pop().unwrap();
-                let a = stack.pop().unwrap();
-                stack.push(less_than_or_equal(heap, &a, &b));
-            }
-            Operation::GreaterThan => {
-                let b = stack.pop().unwrap();
-                let a = stack.pop().unwrap();
-                stack.push(greater_than(heap, &a, &b));
-            }
-            Operation::GreaterThanOrEqual => {
-                let b = stack.pop().unwrap();
-                let a = stack.pop().unwrap();
-                stack.push(greater_than_or_equal(heap, &a, &b));
+        let value = match self.call(chunk).and_then(|_| self.execute(chunk)) {
+            Ok(value) => value,
+
+            Err(error) => {
+                let mut error = self.locate_error(chunk, error);
+
+                // no top level code was running, so it isn't a part of the
+                // stack trace
+                error.stack_trace.pop();
+                error.line = match error.stack_trace.first() {
+                    Some(entry) => entry.line,
+                    None => 0,
+                };
+
+                return Err(error);
             }
+        };
+
+        Ok(ExecutionResult::from_value(chunk, &self.heap, value))
+    }
 
-            Operation::Jump(address) => {
-                i = address;
-                continue;
+    /// Fill in the line and stack trace of an error returned while executing,
+    /// using the call frames which were active when it happened
+    fn locate_error(
+        &self,
+        chunk: &Chunk,
+        mut error: RuntimeError,
+    ) -> RuntimeError {
+        let entries = self.frames.iter().rev().map(|frame| {
+            let (function, frame_chunk) = match frame.function {
+                Some(index) => (
+                    chunk.functions[index].name.clone(),
+                    &chunk.functions[index].chunk,
+                ),
+                None => (None, chunk),
+            };
+
+            // the ip already points to the operation after the one which was
+            // executing
+            let line = match frame.ip {
+                0 => 0,
+                ip => frame_chunk.lines[ip - 1],
+            };
+
+            StackTraceEntry {
+                function,
+                line,
+                repeated: 1,
             }
-            Operation::JumpIfFalse(address) => {
-                if !is_true(heap, &stack.pop().unwrap()) {
-                    i = address;
-                    continue;
+        });
+
+        let mut stack_trace: Vec<StackTraceEntry> = vec![];
+
+        for entry in entries {
+            match stack_trace.last_mut() {
+                Some(last)
+                    if last.function == entry.function
+                        && last.line == entry.line =>
+                {
+                    last.repeated += 1
                 }
+                _ => stack_trace.push(entry),
             }
+        }
+
+        // recursion through several functions doesn't collapse, so keep the
+        // innermost and outermost entries and only count the rest
+        if stack_trace.len() > MAX_STACK_TRACE_ENTRIES {
+            let kept = MAX_STACK_TRACE_ENTRIES / 2;
+            let end = stack_trace.len() - kept;
+
+            error.omitted_frames = stack_trace
+                .drain(kept..end)
+                .map(|entry| entry.repeated)
+                .sum();
+        }
+
+        error.stack_trace = stack_trace;
+
+        error.line = match error.stack_trace.first() {
+            Some(entry) => entry.line,
+            None => 0,
+        };
 
-            Operation::Call => {
-                execute_call(chunk, heap, &mut stack, &mut variables);
+        error
+    }
+
+    /// Execute operations until the top level chunk halts
+    fn execute(&mut self, chunk: &Chunk) -> Result<Value, RuntimeError> {
+        loop {
+            let frame = self.frames.last_mut().unwrap();
+
+            let code = match frame.function {
+                Some(index) => &chunk.functions[index].chunk.code,
+                None => &chunk.code,
+            };
+
+            if frame.ip == code.len() {
+                break;
             }
 
-            Operation::MakeStruct(field_count) => {
-                let mut fields: HashMap<String, Value> = HashMap::new();
-                for _ in 0..field_count {
-                    let value = match stack.pop() {
-                        Some(v) => v,
-                        None => panic!("Expected Value"),
-                    };
+            let operation = &code[frame.ip];
+            frame.ip += 1;
+
+            let base = frame.base;
+
+            match operation {
+                Operation::Push(v) => {
+                    self.stack.push(StackValueWrapper::new(v.clone()));
+                }
+                Operation::Pop => {
+                    self.stack.pop();
+                }
+
+                Operation::PushObject(o) => {
+                    let index = self.allocate(o.clone());
+                    let object_ref = Value::ObjectRef(index);
+                    self.stack.push(StackValueWrapper::new(object_ref));
+                }
 
-                    let name = match stack.pop() {
-                        Some(StackValueWrapper{value:Value::StructFieldName(s),..}) => s,
+                Operation::StoreLocal { slot, accessors } => {
+                    let value = self.stack.pop().unwrap();
+
+                    execute_store(
+                        &mut self.heap,
+                        &mut self.stack[base + *slot as usize].value,
+                        accessors,
+                        value.value,
+                    );
+                }
+                Operation::LoadLocal(slot) => {
+                    let value = read_variable(
+                        &self.heap,
+                        self.stack[base + *slot as usize].value.clone(),
+                    );
+
+                    self.stack.push(StackValueWrapper::new_from_slot(
+                        value,
+                        VariableSlot::Local(*slot),
+                    ));
+                }
+
+                Operation::DefineLocal(slot) => {
+                    let value = self.stack.pop().unwrap();
+
+                    self.stack[base + *slot as usize].value = value.value;
+                }
+
+                Operation::StoreGlobal { slot, accessors } => {
+                    let value = self.stack.pop().unwrap();
+
+                    self.ensure_global_exists(*slot);
+
+                    execute_store(
+                        &mut self.heap,
+                        &mut self.globals[*slot as usize],
+                        accessors,
+                        value.value,
+                    );
+                }
+                Operation::DefineGlobal(slot) => {
+                    let value = self.stack.pop().unwrap();
+
+                    self.ensure_global_exists(*slot);
+                    self.globals[*slot as usize] = value.value;
+                }
 
-                        _ => panic!(
-                            "Expected a Value::StructFieldName with the field name"
-                        ),
+                Operation::StoreUpvalue { slot, accessors } => {
+                    let value = self.stack.pop().unwrap();
+                    let mut upvalue = self.frames.last().unwrap().upvalues
+                        [*slot as usize]
+                        .clone();
+
+                    // the upvalue is an object ref, so this updates the
+                    // shared object
+                    execute_store(
+                        &mut self.heap,
+                        &mut upvalue,
+                        accessors,
+                        value.value,
+                    );
+                }
+                Operation::LoadUpvalue(slot) => {
+                    let upvalue = self.frames.last().unwrap().upvalues
+                        [*slot as usize]
+                        .clone();
+
+                    self.stack.push(StackValueWrapper::new_from_slot(
+                        read_variable(&self.heap, upvalue),
+                        VariableSlot::Upvalue(*slot),
+                    ));
+                }
+
+                Operation::MakeClosure { function, captures } => {
+                    let upvalues = captures
+                        .iter()
+                        .map(|slot| self.capture_variable(*slot, base))
+                        .collect();
+
+                    let index = self.allocate(Object::Closure {
+                        function: *function,
+                        upvalues,
+                    });
+                    self.stack
+                        .push(StackValueWrapper::new(Value::Closure(index)));
+                }
+
+                Operation::LoadGlobal(slot) => {
+                    let value = match self.globals.get(*slot as usize) {
+                        Some(value) => read_variable(&self.heap, value.clone()),
+                        None => {
+                            return runtime_error!(
+                                UndefinedVariable,
+                                "Global variable in slot {} was never set",
+                                slot
+                            )
+                        }
                     };
 
-                    fields.insert(name, value.value);
+                    self.stack.push(StackValueWrapper::new_from_slot(
+                        value,
+                        VariableSlot::Global(*slot),
+                    ));
                 }
 
-                stack.push(StackValueWrapper::new(Value::Struct(fields)));
-            }
+                Operation::Add => {
+                    let b = self.stack.pop().unwrap();
+                    let a = self.stack.pop().unwrap();
+                    self.stack.push(add(&self.heap, &a, &b)?);
+                }
+                Operation::Substract => {
+                    let b = self.stack.pop().unwrap();
+                    let a = self.stack.pop().unwrap();
+                    self.stack.push(substract(&self.heap, &a, &b)?);
+                }
+                Operation::Multiply => {
+                    let b = self.stack.pop().unwrap();
+                    let a = self.stack.pop().unwrap();
+                    self.stack.push(multiply(&self.heap, &a, &b)?);
+                }
+                Operation::Divide => {
+                    let b = self.stack.pop().unwrap();
+                    let a = self.stack.pop().unwrap();
+                    self.stack.push(divide(&self.heap, &a, &b)?);
+                }
+                Operation::Negate => {
+                    let value = self.stack.pop().unwrap();
+                    self.stack.push(negate(&self.heap, &value)?);
+                }
+                Operation::Equal => {
+                    let b = self.stack.pop().unwrap();
+                    let a = self.stack.pop().unwrap();
+                    self.stack.push(equal(&self.heap, &a, &b)?);
+                }
+                Operation::Length => {
+                    let value = self.stack.pop().unwrap();
+                    self.stack.push(length(&self.heap, &value)?);
+                }
+                Operation::Index => {
+                    let index = self.stack.pop().unwrap();
+                    let value = self.stack.pop().unwrap();
+                    self.stack.push(get_index(&self.heap, &value, &index)?);
+                }
+                Operation::StoreIndex { accessors } => {
+                    let value = self.stack.pop().unwrap();
+                    let index = self.stack.pop().unwrap();
+                    let array = self.stack.pop().unwrap();
+
+                    execute_store_index(
+                        &mut self.heap,
+                        &array.value,
+                        &index.value,
+                        accessors,
+                        value.value,
+                    )?;
+                }
 
-            Operation::AccessField(name) => {
-                let value = stack.pop().unwrap();
+                Operation::LessThan => {
+                    let b = self.stack.pop().unwrap();
+                    let a = self.stack.pop().unwrap();
+                    self.stack.push(less_than(&self.heap, &a, &b)?);
+                }
+                Operation::LessThanOrEqual => {
+                    let b = self.stack.pop().unwrap();
+                    let a = self.stack.pop().unwrap();
+                    self.stack.push(less_than_or_equal(&self.heap, &a, &b)?);
+                }
+                Operation::GreaterThan => {
+                    let b = self.stack.pop().unwrap();
+                    let a = self.stack.pop().unwrap();
+                    self.stack.push(greater_than(&self.heap, &a, &b)?);
+                }
+                Operation::GreaterThanOrEqual => {
+                    let b = self.stack.pop().unwrap();
+                    let a = self.stack.pop().unwrap();
+                    self.stack.push(greater_than_or_equal(&self.heap, &a, &b)?);
+                }
 
-                let fields = match value {
-                    StackValueWrapper {
-                        value: Value::Struct(f),
-                        ..
-                    } => f,
+                Operation::Jump(address) => {
+                    self.frames.last_mut().unwrap().ip = *address;
+                }
+                Operation::JumpIfFalse(address) => {
+                    if !is_true(&self.heap, &self.stack.pop().unwrap())? {
+                        self.frames.last_mut().unwrap().ip = *address;
+                    }
+                }
 
-                    StackValueWrapper {
-                        value: Value::ObjectRef(index),
-                        ..
-                    } => {
-                        let object = heap.get_object(index);
-                        match object {
-                            Object::Value(Value::Struct(f)) => f,
+                Operation::Call => {
+                    self.call(chunk)?;
+                }
+                Operation::CallInterfaceFunction {
+                    name,
+                    argument_count,
+                    this_arguments,
+                    returns_this,
+                } => {
+                    self.call_interface_function(
+                        chunk,
+                        name,
+                        *argument_count,
+                        this_arguments,
+                        *returns_this,
+                    )?;
+                }
+                Operation::Return => {
+                    self.return_from_call();
+                }
+
+                Operation::MakeStruct(field_count) => {
+                    let fields = self.pop_struct_fields(*field_count);
+
+                    self.stack
+                        .push(StackValueWrapper::new(Value::Struct(fields)));
+                }
 
-                            _ => panic!("Expected Value::Struct"),
+                Operation::MakeEnum {
+                    variant,
+                    field_count,
+                } => {
+                    let fields = self.pop_struct_fields(*field_count);
+
+                    self.stack.push(StackValueWrapper::new(Value::Enum {
+                        variant: variant.clone(),
+                        fields,
+                    }));
+                }
+
+                Operation::CheckVariant(expected_variant) => {
+                    let mut value = self.stack.last().unwrap().value.clone();
+
+                    if let Value::ObjectRef(index) = value {
+                        if let Object::Value(v) =
+                            self.heap.get_object_ref(index)
+                        {
+                            value = v.clone();
                         }
                     }
 
-                    _ => panic!("Expected Value::Struct"),
-                };
+                    match value {
+                        Value::Enum { variant, .. }
+                            if variant == *expected_variant => {}
+
+                        Value::Enum { variant, .. } => {
+                            return runtime_error!(
+                                InvalidCast,
+                                "Expected enum variant {}, got {}",
+                                expected_variant,
+                                variant
+                            )
+                        }
 
-                let field_value = match fields.get(&name) {
-                    Some(v) => v,
+                        _ => {
+                            return runtime_error!(
+                                TypeMismatch,
+                                "Value is not an enum"
+                            )
+                        }
+                    }
+                }
 
-                    None => panic!("Field {} does not exist", name),
-                };
+                Operation::CheckNotNull => {
+                    let mut value = self.stack.last().unwrap().value.clone();
 
-                match value {
-                    StackValueWrapper {
-                        came_from: Some(came_from),
-                        ..
-                    } => {
-                        let mut accessors = came_from.accessors;
-                        accessors.push(VariableAccessor::StructField(name));
+                    if let Value::ObjectRef(index) = value {
+                        if let Object::Value(v) =
+                            self.heap.get_object_ref(index)
+                        {
+                            value = v.clone();
+                        }
+                    }
 
-                        stack.push(
-                            StackValueWrapper::new_from_name_and_accessors(
-                                field_value.clone(),
-                                came_from.name,
-                                accessors,
-                            ),
+                    if let Value::Null = value {
+                        return runtime_error!(
+                            NullUnwrap,
+                            "Cannot unwrap a null value"
                         );
                     }
+                }
+
+                Operation::ToAny(type_) => {
+                    let value = self.stack.pop().unwrap().value;
+
+                    self.stack.push(StackValueWrapper::new(Value::Any {
+                        type_: type_.clone(),
+                        value: Box::new(value),
+                    }));
+                }
+
+                Operation::Cast(type_) => {
+                    let value = self.stack.pop().unwrap();
+                    let result = cast(&self.heap, &value, type_)?;
+
+                    // casting to a string creates its text, which still has
+                    // to be put on the heap
+                    let result = match result {
+                        CastResult::Value(v) => v,
+                        CastResult::String(s) => {
+                            Value::ObjectRef(self.allocate(Object::String(s)))
+                        }
+                    };
+                    self.stack.push(StackValueWrapper::new(result));
+                }
+
+                Operation::IsVariant(expected_variant) => {
+                    let mut value = self.stack.pop().unwrap().value;
+
+                    if let Value::ObjectRef(index) = value {
+                        if let Object::Value(v) =
+                            self.heap.get_object_ref(index)
+                        {
+                            value = v.clone();
+                        }
+                    }
+
+                    let is_variant = matches!(
+                        value,
+                        Value::Enum { variant, .. } if variant == *expected_variant
+                    );
+                    self.stack
+                        .push(StackValueWrapper::new(Value::Bool(is_variant)));
+                }
+
+                Operation::IsDefaultArgument => {
+                    let value = self.stack.pop().unwrap().value;
+
+                    self.stack.push(StackValueWrapper::new(Value::Bool(
+                        value == Value::DefaultArgument,
+                    )));
+                }
+
+                Operation::MakeArray(element_count) => {
+                    let start = self.stack.len() - *element_count;
+                    let elements = self
+                        .stack
+                        .drain(start..)
+                        .map(|element| element.value)
+                        .collect();
+
+                    let index = self.allocate(Object::Array(elements));
+                    self.stack
+                        .push(StackValueWrapper::new(Value::ObjectRef(index)));
+                }
+
+                Operation::MakeTuple(element_count) => {
+                    let start = self.stack.len() - *element_count;
+                    let elements = self
+                        .stack
+                        .drain(start..)
+                        .map(|element| element.value)
+                        .collect();
+
+                    self.stack
+                        .push(StackValueWrapper::new(Value::Tuple(elements)));
+                }
+
+                Operation::MakeMap(entry_count) => {
+                    let start = self.stack.len() - *entry_count * 2;
+                    let mut entries = HashMap::new();
+
+                    for i in 0..*entry_count {
+                        let key = &self.stack[start + i * 2].value;
+                        let value = &self.stack[start + i * 2 + 1].value;
+
+                        let key = match map_key(&self.heap, key) {
+                            Some(key) => key,
+
+                            None => {
+                                return runtime_error!(
+                                    TypeMismatch,
+                                    "Value cannot be used as a map key"
+                                )
+                            }
+                        };
+
+                        entries.insert(key, value.clone());
+                    }
+
+                    self.stack.truncate(start);
+
+                    let index = self.allocate(Object::Map(entries));
+                    self.stack
+                        .push(StackValueWrapper::new(Value::ObjectRef(index)));
+                }
+
+                Operation::AccessField(name) => {
+                    let value = self.stack.pop().unwrap();
+
+                    let fields = match value {
+                        StackValueWrapper {
+                            value:
+                                Value::Struct(f) | Value::Enum { fields: f, .. },
+                            ..
+                        } => f,
+
+                        StackValueWrapper {
+                            value: Value::ObjectRef(index),
+                            ..
+                        } => {
+                            let object = self.heap.get_object(index);
+                            match object {
+                                Object::Value(
+                                    Value::Struct(f)
+                                    | Value::Enum { fields: f, .. },
+                                ) => f,
+
+                                _ => {
+                                    return runtime_error!(
+                                        TypeMismatch,
+                                        "Cannot access field {} on a value \
+                                         which is not a struct",
+                                        name
+                                    )
+                                }
+                            }
+                        }
+
+                        _ => {
+                            return runtime_error!(
+                                TypeMismatch,
+                                "Cannot access field {} on a value which is \
+                                 not a struct",
+                                name
+                            )
+                        }
+                    };
+
+                    let field_value = match fields.get(name) {
+                        Some(v) => v,
+
+                        None => {
+                            return runtime_error!(
+                                UnknownField,
+                                "Field {} does not exist",
+                                name
+                            )
+                        }
+                    };
+
+                    match value {
+                        StackValueWrapper {
+                            came_from: Some(came_from),
+                            ..
+                        } => {
+                            let mut accessors = came_from.accessors;
+                            accessors.push(VariableAccessor::StructField(
+                                name.clone(),
+                            ));
+
+                            self.stack.push(
+                                StackValueWrapper::new_from_slot_and_accessors(
+                                    field_value.clone(),
+                                    came_from.slot,
+                                    accessors,
+                                ),
+                            );
+                        }
 
-                    _ => {
-                        stack.push(StackValueWrapper::new(field_value.clone()));
+                        _ => {
+                            self.stack.push(StackValueWrapper::new(
+                                field_value.clone(),
+                            ));
+                        }
                     }
                 }
+
+                Operation::Halt => break,
             }
+        }
+
+        Ok(self
+            .stack
+            .pop()
+            .unwrap_or(StackValueWrapper::new(Value::Null))
+            .value)
+    }
+
+    /// Pop the fields pushed for a MakeStruct or MakeEnum operation. Each
+    /// field is a Value::StructFieldName with its name followed by its value
+    fn pop_struct_fields(
+        &mut self,
+        field_count: usize,
+    ) -> HashMap<String, Value> {
+        let mut fields: HashMap<String, Value> = HashMap::new();
+        for _ in 0..field_count {
+            let value = match self.stack.pop() {
+                Some(v) => v,
+                None => panic!("Expected Value"),
+            };
+
+            let name = match self.stack.pop() {
+                Some(StackValueWrapper {
+                    value: Value::StructFieldName(s),
+                    ..
+                }) => s,
+
+                _ => panic!(
+                    "Expected a Value::StructFieldName with the field name"
+                ),
+            };
+
+            fields.insert(name, value.value);
+        }
 
-            Operation::Halt => {
-                return stack
-                    .pop()
-                    .unwrap_or(StackValueWrapper::new(Value::Null))
-                    .value
+        fields
+    }
+
+    /// Globals are only added to the table once they're stored into for the
+    /// first time, so the table might need to grow
+    fn ensure_global_exists(&mut self, slot: u16) {
+        let slot = slot as usize;
+
+        if slot >= self.globals.len() {
+            self.globals.resize(slot + 1, Value::Null);
+        }
+    }
+
+    /// Turn a variable into an object ref to an Object::Value (if it isn't an
+    /// object ref already) and return the object ref, so it can be shared with
+    /// a closure
+    fn capture_variable(&mut self, slot: VariableSlot, base: usize) -> Value {
+        let variable = match slot {
+            VariableSlot::Local(slot) => {
+                self.stack[base + slot as usize].value.clone()
+            }
+            VariableSlot::Global(slot) => {
+                self.ensure_global_exists(slot);
+                self.globals[slot as usize].clone()
+            }
+            VariableSlot::Upvalue(slot) => {
+                return self.frames.last().unwrap().upvalues[slot as usize]
+                    .clone()
+            }
+        };
+
+        if let Value::ObjectRef(index) = variable {
+            if self.heap.holds_value(index) {
+                return variable;
             }
         }
 
-        i += 1;
+        // the variable still holds its value while the object is allocated
+        let object_ref =
+            Value::ObjectRef(self.allocate(Object::Value(variable)));
+
+        match slot {
+            VariableSlot::Local(slot) => {
+                self.stack[base + slot as usize].value = object_ref.clone();
+            }
+            VariableSlot::Global(slot) => {
+                self.globals[slot as usize] = object_ref.clone();
+            }
+            VariableSlot::Upvalue(_) => unreachable!(),
+        }
+
+        object_ref
     }
 
-    Value::Null
+    /// Add an object to the heap. Every allocation made while executing goes
+    /// through here, so that the garbage collector can run before it. The
+    /// values inside the object are kept alive, but any other value which is
+    /// still needed has to be on the stack, in a global or in a call frame
+    pub(in crate::vm) fn allocate(&mut self, object: Object) -> usize {
+        if self.heap.should_collect() {
+            self.collect_garbage(Some(&object));
+        }
+
+        self.heap.add_object(object)
+    }
+
+    /// Run the garbage collector if enough has been allocated, for when
+    /// several objects are about to be added directly to the heap instead of
+    /// going through allocate
+    pub(in crate::vm) fn collect_garbage_if_needed(&mut self) {
+        if self.heap.should_collect() {
+            self.collect_garbage(None);
+        }
+    }
+
+    fn collect_garbage(&mut self, allocating: Option<&Object>) {
+        // objects created for var arguments need to stay alive until they're
+        // copied back when the function returns, even if the function has
+        // reassigned its parameter
+        let pending_updates: Vec<Value> = self
+            .frames
+            .iter()
+            .flat_map(|frame| frame.variables_to_be_updated.iter())
+            .map(|(_, index)| Value::ObjectRef(*index))
+            .collect();
+
+        let upvalues: Vec<Value> = self
+            .frames
+            .iter()
+            .flat_map(|frame| frame.upvalues.iter().cloned())
+            .collect();
+
+        self.heap.collect_garbage(
+            self.stack
+                .iter()
+                .map(|v| &v.value)
+                .chain(self.globals.iter())
+                .chain(pending_updates.iter())
+                .chain(upvalues.iter()),
+            allocating,
+        );
+    }
 }
@@ -0,0 +1,186 @@
+use std::{fmt::Debug, rc::Rc};
+
+use crate::{
+    lexer::Lexer,
+    parser::{self, parser_error::ParserError, r#type::Type},
+};
+
+use super::execution_result::ExecutionResult;
+
+/// A Rust function which can be called from Kuon code. It gets the call's
+/// arguments and returns either the call's result or an error message, which
+/// is turned into a runtime error
+pub type NativeCallback =
+    dyn Fn(&[ExecutionResult]) -> Result<ExecutionResult, String>;
+
+#[derive(Clone)]
+pub struct NativeFunction {
+    pub name: String,
+    pub param_types: Vec<Type>,
+    pub return_type: Type,
+    pub callback: Rc<NativeCallback>,
+}
+
+impl Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NativeFunction")
+            .field("name", &self.name)
+            .field("param_types", &self.param_types)
+            .field("return_type", &self.return_type)
+            .finish()
+    }
+}
+
+/// All the native functions that are exposed to a Kuon program. The same
+/// NativeFunctions need to be passed to the analyzer (so it can type check
+/// calls to them) and the compiler (which stores them in the chunk so the VM
+/// can call them)
+#[derive(Debug, Clone)]
+pub struct NativeFunctions {
+    pub functions: Vec<NativeFunction>,
+}
+
+impl NativeFunction {
+    /// Create a native function with a Kuon signature such as
+    /// `fun (int, string) bool`
+    pub fn new<F>(
+        name: &str,
+        signature: &str,
+        callback: F,
+    ) -> Result<Self, ParserError>
+    where
+        F: Fn(&[ExecutionResult]) -> Result<ExecutionResult, String> + 'static,
+    {
+        let signature = signature.to_string();
+        let mut lexer = Lexer::from_string(&signature);
+
+        let (param_types, return_type) =
+            match parser::parse_function_signature(&mut lexer)? {
+                Type::Function {
+                    param_types,
+                    return_type,
+                    ..
+                } => (param_types, *return_type),
+
+                _ => unreachable!(),
+            };
+
+        Ok(Self {
+            name: name.to_string(),
+            param_types,
+            return_type,
+            callback: Rc::new(callback),
+        })
+    }
+
+    /// Check if a value returned by the callback has the function's return
+    /// type
+    pub fn returns_type(&self, result: &ExecutionResult) -> bool {
+        result_has_type(result, &self.return_type)
+    }
+}
+
+/// The VM doesn't know how structs, enums and type aliases are defined, so
+/// values of a named type aren't checked
+fn result_has_type(result: &ExecutionResult, type_: &Type) -> bool {
+    match (result, type_) {
+        (_, Type::Any) => true,
+
+        (ExecutionResult::Null, Type::Null)
+        | (ExecutionResult::Char(_), Type::Char)
+        | (ExecutionResult::Int(_), Type::Int)
+        | (ExecutionResult::Float(_), Type::Float)
+        | (ExecutionResult::Bool(_), Type::Bool)
+        | (ExecutionResult::String(_), Type::String) => true,
+
+        (ExecutionResult::Array(elements), Type::Array(element_type)) => {
+            elements
+                .iter()
+                .all(|element| result_has_type(element, element_type))
+        }
+
+        (ExecutionResult::Tuple(elements), Type::Tuple(element_types)) => {
+            elements.len() == element_types.len()
+                && elements
+                    .iter()
+                    .zip(element_types.iter())
+                    .all(|(element, type_)| result_has_type(element, type_))
+        }
+
+        (
+            ExecutionResult::Map(entries),
+            Type::Map {
+                key_type,
+                value_type,
+            },
+        ) => entries.iter().all(|(key, value)| {
+            result_has_type(key, key_type) && result_has_type(value, value_type)
+        }),
+
+        (ExecutionResult::Null, Type::Nullable(_)) => true,
+        (_, Type::Nullable(inner_type)) => result_has_type(result, inner_type),
+
+        (
+            ExecutionResult::Function { .. }
+            | ExecutionResult::NativeFunction { .. },
+            Type::Function { .. },
+        ) => true,
+
+        (
+            ExecutionResult::Struct { fields },
+            Type::Struct {
+                fields: field_types,
+            },
+        ) => {
+            fields.len() == field_types.len()
+                && field_types.iter().all(|(name, field_type)| {
+                    match fields.get(name) {
+                        Some(field) => result_has_type(field, field_type),
+                        None => false,
+                    }
+                })
+        }
+
+        // this could also be a type alias for any other type
+        (_, Type::UserDefined(_)) => true,
+
+        (
+            ExecutionResult::Enum { variant, .. },
+            Type::EnumVariant {
+                variant: expected_variant,
+                ..
+            },
+        ) => variant == expected_variant,
+
+        _ => false,
+    }
+}
+
+impl Default for NativeFunctions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NativeFunctions {
+    pub fn new() -> Self {
+        Self { functions: vec![] }
+    }
+
+    /// Register a native function with a Kuon signature such as
+    /// `fun (int, string) bool`
+    pub fn register<F>(
+        &mut self,
+        name: &str,
+        signature: &str,
+        callback: F,
+    ) -> Result<(), ParserError>
+    where
+        F: Fn(&[ExecutionResult]) -> Result<ExecutionResult, String> + 'static,
+    {
+        self.functions
+            .push(NativeFunction::new(name, signature, callback)?);
+
+        Ok(())
+    }
+}
@@ -1,100 +1,329 @@
-use std::collections::HashMap;
-
 use crate::{
     compiler::{
-        chunk::Chunk,
+        chunk::{Chunk, VariableSlot},
         value::{Object, Value},
     },
+    parser::r#type::Type,
+    runtime_error,
     vm::{
-        execute_chunk, heap::VmHeap,
-        operations::store::execute_store_with_value,
-        stack_value_wrapper::StackValueWrapperCameFrom, StackValueWrapper,
+        execution_result::ExecutionResult, operations::store::execute_store,
+        runtime_error::RuntimeError, CallFrame, StackValueWrapper, Vm,
     },
 };
 
-pub fn execute_call(
-    chunk: &Chunk,
-    heap: &mut VmHeap,
-    stack: &mut Vec<StackValueWrapper>,
-    variables: &mut HashMap<String, Value>,
-) {
-    let function_index = match stack.pop() {
-        Some(StackValueWrapper {
-            value: Value::Function(index),
+impl Vm {
+    /// Pop a Value::Function (or a Value::Closure) and push a new call frame
+    /// for it. The function's
+    /// arguments stay on the stack and become the first locals of the frame
+    pub(in crate::vm) fn call(
+        &mut self,
+        chunk: &Chunk,
+    ) -> Result<(), RuntimeError> {
+        let mut callee = self.stack.pop();
+
+        // captured variables and var parameters hold an object ref to the
+        // function instead of the function itself
+        if let Some(StackValueWrapper {
+            value: Value::ObjectRef(index),
             ..
-        }) => index,
-
-        _ => unreachable!(),
-    };
-    let function = &chunk.functions[function_index];
-
-    // default stack which will be passed to the function
-    let mut default_stack: Vec<StackValueWrapper> = vec![];
-
-    // Array of bindings between where variables came from and object ref
-    // indexes. When the function has finished executing, the variables will be
-    // updated to the values inside those objects
-    let mut variables_to_be_updated: Vec<(StackValueWrapperCameFrom, usize)> =
-        vec![];
-
-    // pop variables from stack into default_stack
-    for param in &function.parameters {
-        let value = stack.pop().unwrap();
-
-        // if the parameter is not a constant (has a var in front in
-        // the function definition) and it's not already an object
-        // ref, create an Object::Value and push its object ref.
-        // Otherwise, just push the value
-        if !param.constant && !matches!(value.value, Value::ObjectRef(_)) {
-            let index = heap.add_object(Object::Value(value.value));
-
-            let object_ref = Value::ObjectRef(index);
-            default_stack.push(StackValueWrapper::new(object_ref));
-
-            // add to variables_to_be_updated
-            if let Some(came_from) = value.came_from {
-                variables_to_be_updated.push((came_from, index));
+        }) = callee
+        {
+            if let Object::Value(value) = self.heap.get_object_ref(index) {
+                callee = Some(StackValueWrapper::new(value.clone()));
+            }
+        }
+
+        let (function_index, upvalues) = match callee {
+            Some(StackValueWrapper {
+                value: Value::Function(index),
+                ..
+            }) => (index, vec![]),
+
+            Some(StackValueWrapper {
+                value: Value::Closure(index),
+                ..
+            }) => match self.heap.get_object_ref(index) {
+                Object::Closure { function, upvalues } => {
+                    (*function, upvalues.clone())
+                }
+                _ => unreachable!(),
+            },
+
+            Some(StackValueWrapper {
+                value: Value::NativeFunction(index),
+                ..
+            }) => return self.call_native(chunk, index),
+
+            Some(StackValueWrapper { value, .. }) => {
+                return runtime_error!(
+                    NotAFunction,
+                    "Cannot call a value which is not a function: {:?}",
+                    value
+                )
+            }
+            None => return runtime_error!(NotAFunction, "Nothing to call"),
+        };
+        let function = &chunk.functions[function_index];
+
+        if self.frames.len() >= self.max_frames {
+            return runtime_error!(
+                StackOverflow,
+                "Stack overflow: more than {} nested function calls",
+                self.max_frames
+            );
+        }
+
+        // the pre-parameter is pushed before all the other arguments, so it's
+        // the first one in the frame
+        let params: Vec<_> = function
+            .pre_param
+            .iter()
+            .chain(function.parameters.iter())
+            .collect();
+
+        let base = self.stack.len() - params.len();
+
+        let mut variables_to_be_updated = vec![];
+
+        for (i, param) in params.iter().enumerate() {
+            // if the parameter is not a constant (has a var in front in
+            // the function definition), create an Object::Value and put its
+            // object ref in the argument's place. Otherwise, just leave the
+            // value there
+            let is_value_object = matches!(
+                self.stack[base + i].value,
+                Value::ObjectRef(index) if self.heap.holds_value(index)
+            );
+
+            if !param.constant && !is_value_object {
+                // the argument stays on the stack until the object holding
+                // it has been allocated
+                let object = Object::Value(self.stack[base + i].value.clone());
+                let index = self.allocate(object);
+
+                let argument = &mut self.stack[base + i];
+
+                // add to variables_to_be_updated
+                if let Some(came_from) = argument.came_from.take() {
+                    variables_to_be_updated.push((came_from, index));
+                }
+
+                argument.value = Value::ObjectRef(index);
+            }
+        }
+
+        // make room for the rest of the function's locals
+        let local_count = function.chunk.local_count as usize;
+        for _ in params.len()..local_count {
+            self.stack.push(StackValueWrapper::new(Value::Null));
+        }
+
+        self.frames.push(CallFrame {
+            function: Some(function_index),
+            ip: 0,
+            base,
+            variables_to_be_updated,
+            upvalues,
+            result_type: None,
+        });
+
+        Ok(())
+    }
+
+    /// Split a value of an interface into its actual type and the value
+    /// inside of it
+    fn unwrap_interface_value(
+        &self,
+        value: &Value,
+    ) -> Result<(Type, Value), RuntimeError> {
+        let mut value = value.clone();
+
+        // captured variables hold an object ref to the value instead
+        if let Value::ObjectRef(index) = value {
+            if let Object::Value(inner) = self.heap.get_object_ref(index) {
+                value = inner.clone();
             }
-        } else {
-            default_stack.push(value);
+        }
+
+        match value {
+            Value::Any { type_, value } => Ok((type_, *value)),
+            value => runtime_error!(
+                TypeMismatch,
+                "Expected a value of an interface, got {:?}",
+                value
+            ),
         }
     }
-    // then do the same thing for the pre-parameter
-    if let Some(pre_param) = &function.pre_param {
-        let value = stack.pop().unwrap();
 
-        if !pre_param.constant && !matches!(value.value, Value::ObjectRef(_)) {
-            let index = heap.add_object(Object::Value(value.value));
+    /// Find the value function with the given name for the actual type of the
+    /// pre-argument, which is a Value::Any below the other arguments, and call
+    /// it with the value inside of the any. The arguments whose parameter is
+    /// this need to have the same actual type, since the function was defined
+    /// for values of that type
+    pub(in crate::vm) fn call_interface_function(
+        &mut self,
+        chunk: &Chunk,
+        name: &String,
+        argument_count: usize,
+        this_arguments: &Vec<usize>,
+        returns_this: bool,
+    ) -> Result<(), RuntimeError> {
+        let position = self.stack.len() - 1 - argument_count;
+        let (type_, value) =
+            self.unwrap_interface_value(&self.stack[position].value)?;
 
-            let object_ref = Value::ObjectRef(index);
-            default_stack.push(StackValueWrapper::new(object_ref));
+        let key = (name.clone(), type_.clone());
+        let function_index =
+            match chunk.value_function_index_from_name.get(&key) {
+                Some(index) => *index,
+                None => {
+                    return runtime_error!(
+                        NotAFunction,
+                        "Cannot find function {:?}:{}",
+                        type_,
+                        name
+                    )
+                }
+            };
 
-            if let Some(came_from) = value.came_from {
-                variables_to_be_updated.push((came_from, index));
+        for argument_index in this_arguments {
+            let argument_position = position + 1 + argument_index;
+            let (argument_type, argument) = self
+                .unwrap_interface_value(&self.stack[argument_position].value)?;
+
+            if argument_type != type_ {
+                return runtime_error!(
+                    TypeMismatch,
+                    "Function {} was called on a value of type {:?}, so argument {} has to have the same type, got a value of type {:?}",
+                    name,
+                    type_,
+                    argument_index + 1,
+                    argument_type
+                );
             }
-        } else {
-            default_stack.push(value);
+
+            self.stack[argument_position] = StackValueWrapper::new(argument);
+        }
+
+        self.stack[position] = StackValueWrapper::new(value);
+        self.stack
+            .push(StackValueWrapper::new(Value::Function(function_index)));
+        self.call(chunk)?;
+
+        if returns_this {
+            self.frames.last_mut().unwrap().result_type = Some(type_);
         }
+
+        Ok(())
     }
 
-    let return_value =
-        execute_chunk(chunk, heap, Some(function_index), &default_stack);
+    /// Pop a native function's arguments, call it and push its return value.
+    /// No call frame is needed since it doesn't run any Kuon code
+    fn call_native(
+        &mut self,
+        chunk: &Chunk,
+        native_index: usize,
+    ) -> Result<(), RuntimeError> {
+        let native = &chunk.native_functions[native_index];
+
+        let base = self.stack.len() - native.param_types.len();
+        let arguments: Vec<ExecutionResult> = self
+            .stack
+            .drain(base..)
+            .map(|argument| {
+                ExecutionResult::from_value(chunk, &self.heap, argument.value)
+            })
+            .collect();
 
-    // update variables
-    for (came_from, index) in variables_to_be_updated {
-        let new_value = match heap.get_object(index) {
-            Object::Value(v) => v,
-            _ => unreachable!(),
+        let result = match (native.callback)(&arguments) {
+            Ok(result) => result,
+
+            Err(message) => {
+                return runtime_error!(
+                    NativeError,
+                    "Native function {} failed: {}",
+                    native.name,
+                    message
+                )
+            }
         };
 
-        execute_store_with_value(
-            heap,
-            variables,
-            &came_from.name,
-            &came_from.accessors,
-            new_value,
-        );
+        if !native.returns_type(&result) {
+            return runtime_error!(
+                NativeError,
+                "Native function {} returned {:?}, expected a value of type {:?}",
+                native.name,
+                result,
+                native.return_type
+            );
+        }
+
+        // the objects making up the result are only referenced by each other
+        // until the whole value has been built, so the collector gets its
+        // chance to run before instead of in between them
+        self.collect_garbage_if_needed();
+        let return_value = result.into_value(&mut self.heap)?;
+        self.stack.push(StackValueWrapper::new(return_value));
+
+        Ok(())
     }
 
-    stack.push(StackValueWrapper::new(return_value));
+    /// Pop the current call frame and push its return value to the calling
+    /// frame's stack
+    pub(in crate::vm) fn return_from_call(&mut self) {
+        let mut return_value = self
+            .stack
+            .pop()
+            .unwrap_or(StackValueWrapper::new(Value::Null))
+            .value;
+
+        let frame = self.frames.pop().unwrap();
+        self.stack.truncate(frame.base);
+
+        if let Some(type_) = frame.result_type {
+            return_value = Value::Any {
+                type_,
+                value: Box::new(return_value),
+            };
+        }
+
+        let caller_base = self.frames.last().unwrap().base;
+
+        // update variables
+        for (came_from, index) in frame.variables_to_be_updated {
+            let new_value = match self.heap.get_object_ref(index) {
+                Object::Value(v) => v.clone(),
+                _ => unreachable!(),
+            };
+
+            // captured variables are object refs, so storing into a copy of
+            // one updates the shared object
+            let mut upvalue: Value;
+
+            let variable = match came_from.slot {
+                VariableSlot::Local(slot) => {
+                    &mut self.stack[caller_base + slot as usize].value
+                }
+                VariableSlot::Global(slot) => {
+                    self.ensure_global_exists(slot);
+                    &mut self.globals[slot as usize]
+                }
+                VariableSlot::Upvalue(slot) => {
+                    upvalue = self.frames.last().unwrap().upvalues
+                        [slot as usize]
+                        .clone();
+                    &mut upvalue
+                }
+            };
+
+            execute_store(
+                &mut self.heap,
+                variable,
+                &came_from.accessors,
+                new_value,
+            );
+        }
+
+        self.stack.push(StackValueWrapper::new(return_value));
+    }
 }
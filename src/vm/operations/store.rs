@@ -1,34 +1,27 @@
-use std::collections::HashMap;
-
 use crate::{
     compiler::value::{Object, Value},
     parser::expression::VariableAccessor,
-    vm::{heap::VmHeap, StackValueWrapper},
+    runtime_error,
+    vm::{heap::VmHeap, runtime_error::RuntimeError, util::map_key},
 };
 
 fn execute_store_without_accessors(
     heap: &mut VmHeap,
-    variables: &mut HashMap<String, Value>,
-    name: &String,
+    variable: &mut Value,
     value: Value,
 ) {
-    // if you're storing into a variable which already holds an
-    // object ref, then update the object it's referencing with that
-    // value
-    if let Some(Value::ObjectRef(dest_index)) = variables.get(name) {
-        let object = match &value {
-            // if you're storing an object ref, get the object from
-            // the heap
-            Value::ObjectRef(src_index) => heap.get_object(*src_index),
-
-            // otherwise, create an Object::Value
-            v => Object::Value(v.clone()),
-        };
-
-        heap.update_object(*dest_index, object);
+    // if you're storing into a variable which holds a reference to an
+    // Object::Value, then update that object instead (the variable keeps
+    // referencing the same object, which is what makes var parameters and
+    // captured variables work). Any other object ref, like an array, is just
+    // replaced since other variables might be referencing the same object
+    match variable {
+        Value::ObjectRef(dest_index) if heap.holds_value(*dest_index) => {
+            heap.update_object(*dest_index, Object::Value(value));
+        }
+
+        _ => *variable = value,
     }
-
-    variables.insert(name.clone(), value);
 }
 
 /// Create a new value which is the same as old_value except one specific field
@@ -47,7 +40,7 @@ fn update_value_field(
 
     match &accessors[0] {
         VariableAccessor::StructField(field) => match &mut new_value {
-            Value::Struct(fields) => {
+            Value::Struct(fields) | Value::Enum { fields, .. } => {
                 fields.insert(
                     field.to_string(),
                     update_value_field(
@@ -66,7 +59,9 @@ fn update_value_field(
                 let mut object = heap.get_object(*index);
 
                 match &mut object {
-                    Object::Value(Value::Struct(fields)) => {
+                    Object::Value(
+                        Value::Struct(fields) | Value::Enum { fields, .. },
+                    ) => {
                         fields.insert(
                             field.to_string(),
                             update_value_field(
@@ -86,6 +81,10 @@ fn update_value_field(
 
             v => panic!("Value is not a struct: {:?}", v),
         },
+
+        // the compiler stores into array elements with StoreIndex, which
+        // only passes the accessors that come after the last index
+        VariableAccessor::Index(_) => unreachable!(),
     }
 
     new_value
@@ -93,43 +92,116 @@ fn update_value_field(
 
 fn execute_store_with_accessors(
     heap: &mut VmHeap,
-    variables: &mut HashMap<String, Value>,
-    name: &String,
+    variable: &mut Value,
     accessors: &Vec<VariableAccessor>,
     value: Value,
 ) {
-    // if accessors is not empty, a variable needs to already exist, so
-    // unwrapping here
-    let current_variable_value = variables.get(name).unwrap();
-
-    variables.insert(
-        name.clone(),
-        update_value_field(heap, current_variable_value, accessors, &value),
-    );
+    *variable = update_value_field(heap, variable, accessors, &value);
 }
 
-pub fn execute_store_with_value(
+/// Store a value into a variable, which is either one of the locals of a call
+/// frame or a global
+pub fn execute_store(
     heap: &mut VmHeap,
-    variables: &mut HashMap<String, Value>,
-    name: &String,
+    variable: &mut Value,
     accessors: &Vec<VariableAccessor>,
     value: Value,
 ) {
     if accessors.is_empty() {
-        execute_store_without_accessors(heap, variables, name, value);
+        execute_store_without_accessors(heap, variable, value);
     } else {
-        execute_store_with_accessors(heap, variables, name, accessors, value);
+        execute_store_with_accessors(heap, variable, accessors, value);
     }
 }
 
-pub fn execute_store(
+/// Store a value into an element of an array or an entry of a map (or into a
+/// field of that element if there are accessors)
+pub fn execute_store_index(
     heap: &mut VmHeap,
-    stack: &mut Vec<StackValueWrapper>,
-    variables: &mut HashMap<String, Value>,
-    name: &String,
+    indexed: &Value,
+    index: &Value,
     accessors: &Vec<VariableAccessor>,
-) {
-    let value = stack.pop().unwrap();
+    value: Value,
+) -> Result<(), RuntimeError> {
+    let object_index = match indexed {
+        Value::ObjectRef(object_index) => *object_index,
+
+        _ => {
+            return runtime_error!(TypeMismatch, "Value is not an array or map")
+        }
+    };
+
+    // only the element being stored into is copied, the array or map itself
+    // is updated in place
+    match heap.get_object_ref(object_index) {
+        Object::Array(elements) => {
+            // the index might be a variable which has been captured by a
+            // closure
+            let index = match index {
+                Value::ObjectRef(index) => match heap.get_object_ref(*index) {
+                    Object::Value(v) => v.clone(),
+                    _ => Value::Null,
+                },
+                v => v.clone(),
+            };
+
+            let index = match index {
+                Value::Int(i) => i,
+
+                _ => {
+                    return runtime_error!(TypeMismatch, "Index must be an int")
+                }
+            };
+
+            let length = elements.len();
+            let (i, element) = match usize::try_from(index) {
+                Ok(i) if i < length => (i, elements[i].clone()),
+
+                _ => {
+                    return runtime_error!(
+                        IndexOutOfBounds,
+                        "Index {} is out of bounds for an array of length {}",
+                        index,
+                        length
+                    )
+                }
+            };
+
+            let new_element =
+                update_value_field(heap, &element, accessors, &value);
+
+            if let Object::Array(elements) = heap.get_object_mut(object_index) {
+                elements[i] = new_element;
+            }
+        }
+
+        // storing into a key which isn't in the map inserts it
+        Object::Map(entries) => {
+            let key = match map_key(heap, index) {
+                Some(key) => key,
+
+                None => {
+                    return runtime_error!(
+                        TypeMismatch,
+                        "Value cannot be used as a map key"
+                    )
+                }
+            };
+
+            let new_value = match entries.get(&key).cloned() {
+                Some(old_value) => {
+                    update_value_field(heap, &old_value, accessors, &value)
+                }
+                None => value,
+            };
+
+            heap.insert_map_entry(object_index, key, new_value);
+        }
+
+        _ => {
+            return runtime_error!(TypeMismatch, "Value is not an array or map")
+        }
+    }
 
-    execute_store_with_value(heap, variables, name, accessors, value.value);
+    Ok(())
 }
@@ -0,0 +1,77 @@
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeErrorKind {
+    TypeMismatch,
+    DivisionByZero,
+
+    // the result of an arithmetic operation on ints doesn't fit in an int
+    IntegerOverflow,
+    UnknownField,
+    NotAFunction,
+    StackOverflow,
+    IndexOutOfBounds,
+
+    // a global variable was read before it was ever set
+    UndefinedVariable,
+
+    // a value couldn't be cast to the type it was expected to have
+    InvalidCast,
+
+    // a null value was unwrapped with !
+    NullUnwrap,
+
+    // a native function returned an error
+    NativeError,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackTraceEntry {
+    // None for the top level code
+    pub function: Option<String>,
+    pub line: usize,
+
+    // how many frames in a row were in the same function at the same line,
+    // so deep recursion only takes up a single entry
+    pub repeated: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub message: String,
+
+    // line of the operation which failed
+    pub line: usize,
+
+    // all the call frames which were active when the error happened, starting
+    // with the one where it happened. The line of every other entry is the
+    // line of the call to the entry before it
+    pub stack_trace: Vec<StackTraceEntry>,
+
+    // frames left out of the middle of the stack trace when it had more than
+    // MAX_STACK_TRACE_ENTRIES entries
+    pub omitted_frames: usize,
+}
+
+/// Creates a runtime error without a location, the VM fills in the line and
+/// stack trace once the error reaches it
+#[macro_export]
+macro_rules! runtime_error {
+    ($kind:ident, $($arg:tt)*) => {{
+        Err($crate::vm::runtime_error::RuntimeError::new(
+            $crate::vm::runtime_error::RuntimeErrorKind::$kind,
+            format!($($arg)*),
+        ))
+    }}
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind, message: String) -> Self {
+        Self {
+            kind,
+            message,
+            line: 0,
+            stack_trace: vec![],
+            omitted_frames: 0,
+        }
+    }
+}
@@ -1,8 +1,11 @@
-use crate::{compiler::value::Value, parser::expression::VariableAccessor};
+use crate::{
+    compiler::{chunk::VariableSlot, value::Value},
+    parser::expression::VariableAccessor,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct StackValueWrapperCameFrom {
-    pub name: String,
+    pub slot: VariableSlot,
     pub accessors: Vec<VariableAccessor>,
 }
 
@@ -20,24 +23,24 @@ impl StackValueWrapper {
         }
     }
 
-    pub fn new_from_name(value: Value, name: String) -> Self {
+    pub fn new_from_slot(value: Value, slot: VariableSlot) -> Self {
         Self {
             value,
             came_from: Some(StackValueWrapperCameFrom {
-                name,
+                slot,
                 accessors: vec![],
             }),
         }
     }
 
-    pub fn new_from_name_and_accessors(
+    pub fn new_from_slot_and_accessors(
         value: Value,
-        name: String,
+        slot: VariableSlot,
         accessors: Vec<VariableAccessor>,
     ) -> Self {
         Self {
             value,
-            came_from: Some(StackValueWrapperCameFrom { name, accessors }),
+            came_from: Some(StackValueWrapperCameFrom { slot, accessors }),
         }
     }
 }
@@ -1,12 +1,18 @@
-use crate::compiler::value::{Object, Value};
+use std::collections::HashMap;
 
-use super::{heap::VmHeap, StackValueWrapper};
+use crate::{
+    compiler::value::{MapKey, Object, Value},
+    parser::r#type::Type,
+    runtime_error,
+};
+
+use super::{heap::VmHeap, runtime_error::RuntimeError, StackValueWrapper};
 
 fn extract_value(heap: &VmHeap, value: &StackValueWrapper) -> Value {
     let mut value = value.value.clone();
 
     match value {
-        Value::ObjectRef(index) => match heap.get_object(index) {
+        Value::ObjectRef(index) => match heap.get_object_ref(index) {
             Object::Value(v) => {
                 let v = v.clone();
                 value = v;
@@ -21,36 +27,60 @@ fn extract_value(heap: &VmHeap, value: &StackValueWrapper) -> Value {
     value
 }
 
+/// Get the value of a variable, which is behind an Object::Value if it's been
+/// captured or is a var parameter
+pub fn read_variable(heap: &VmHeap, value: Value) -> Value {
+    match value {
+        Value::ObjectRef(index) if heap.holds_value(index) => {
+            match heap.get_object_ref(index) {
+                Object::Value(v) => v.clone(),
+                _ => unreachable!(),
+            }
+        }
+
+        value => value,
+    }
+}
+
 pub fn add(
     heap: &VmHeap,
     first: &StackValueWrapper,
     second: &StackValueWrapper,
-) -> StackValueWrapper {
+) -> Result<StackValueWrapper, RuntimeError> {
     let first = extract_value(heap, first);
     let second = extract_value(heap, second);
 
     match (first, second) {
-        (Value::Int(a), Value::Int(b)) => StackValueWrapper {
-            value: Value::Int(a + b),
-            came_from: None,
+        (Value::Int(a), Value::Int(b)) => match a.checked_add(b) {
+            Some(result) => Ok(StackValueWrapper {
+                value: Value::Int(result),
+                came_from: None,
+            }),
+
+            None => runtime_error!(
+                IntegerOverflow,
+                "Integer overflow in {} + {}",
+                a,
+                b
+            ),
         },
 
-        (Value::Int(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Int(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Float(a as f64 + b),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Int(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Int(b)) => Ok(StackValueWrapper {
             value: Value::Float(a + b as f64),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Float(a + b),
             came_from: None,
-        },
+        }),
 
-        _ => panic!("Can only add numbers together"),
+        _ => runtime_error!(TypeMismatch, "Can only add numbers together"),
     }
 }
 
@@ -58,32 +88,43 @@ pub fn substract(
     heap: &VmHeap,
     first: &StackValueWrapper,
     second: &StackValueWrapper,
-) -> StackValueWrapper {
+) -> Result<StackValueWrapper, RuntimeError> {
     let first = extract_value(heap, first);
     let second = extract_value(heap, second);
 
     match (first, second) {
-        (Value::Int(a), Value::Int(b)) => StackValueWrapper {
-            value: Value::Int(a - b),
-            came_from: None,
+        (Value::Int(a), Value::Int(b)) => match a.checked_sub(b) {
+            Some(result) => Ok(StackValueWrapper {
+                value: Value::Int(result),
+                came_from: None,
+            }),
+
+            None => runtime_error!(
+                IntegerOverflow,
+                "Integer overflow in {} - {}",
+                a,
+                b
+            ),
         },
 
-        (Value::Int(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Int(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Float(a as f64 - b),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Int(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Int(b)) => Ok(StackValueWrapper {
             value: Value::Float(a - b as f64),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Float(a - b),
             came_from: None,
-        },
+        }),
 
-        _ => panic!("Can only substract numbers together"),
+        _ => {
+            runtime_error!(TypeMismatch, "Can only substract numbers together")
+        }
     }
 }
 
@@ -91,32 +132,41 @@ pub fn multiply(
     heap: &VmHeap,
     first: &StackValueWrapper,
     second: &StackValueWrapper,
-) -> StackValueWrapper {
+) -> Result<StackValueWrapper, RuntimeError> {
     let first = extract_value(heap, first);
     let second = extract_value(heap, second);
 
     match (first, second) {
-        (Value::Int(a), Value::Int(b)) => StackValueWrapper {
-            value: Value::Int(a * b),
-            came_from: None,
+        (Value::Int(a), Value::Int(b)) => match a.checked_mul(b) {
+            Some(result) => Ok(StackValueWrapper {
+                value: Value::Int(result),
+                came_from: None,
+            }),
+
+            None => runtime_error!(
+                IntegerOverflow,
+                "Integer overflow in {} * {}",
+                a,
+                b
+            ),
         },
 
-        (Value::Int(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Int(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Float(a as f64 * b),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Int(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Int(b)) => Ok(StackValueWrapper {
             value: Value::Float(a * b as f64),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Float(a * b),
             came_from: None,
-        },
+        }),
 
-        _ => panic!("Can only multiply numbers together"),
+        _ => runtime_error!(TypeMismatch, "Can only multiply numbers together"),
     }
 }
 
@@ -124,166 +174,208 @@ pub fn divide(
     heap: &VmHeap,
     first: &StackValueWrapper,
     second: &StackValueWrapper,
-) -> StackValueWrapper {
+) -> Result<StackValueWrapper, RuntimeError> {
     let first = extract_value(heap, first);
     let second = extract_value(heap, second);
 
     match (first, second) {
-        (Value::Int(a), Value::Int(b)) => StackValueWrapper {
-            value: Value::Int(a / b),
-            came_from: None,
+        (Value::Int(_), Value::Int(0)) => {
+            runtime_error!(DivisionByZero, "Division by zero")
+        }
+
+        (Value::Int(a), Value::Int(b)) => match a.checked_div(b) {
+            Some(result) => Ok(StackValueWrapper {
+                value: Value::Int(result),
+                came_from: None,
+            }),
+
+            None => runtime_error!(
+                IntegerOverflow,
+                "Integer overflow in {} / {}",
+                a,
+                b
+            ),
         },
 
-        (Value::Int(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Int(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Float(a as f64 / b),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Int(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Int(b)) => Ok(StackValueWrapper {
             value: Value::Float(a / b as f64),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Float(a / b),
             came_from: None,
-        },
+        }),
 
-        _ => panic!("Can only divide numbers together"),
+        _ => runtime_error!(TypeMismatch, "Can only divide numbers together"),
     }
 }
 
-pub fn negate(heap: &VmHeap, value: &StackValueWrapper) -> StackValueWrapper {
+pub fn negate(
+    heap: &VmHeap,
+    value: &StackValueWrapper,
+) -> Result<StackValueWrapper, RuntimeError> {
     let value = extract_value(heap, value);
 
     match value {
-        Value::Int(a) => StackValueWrapper {
-            value: Value::Int(-a),
-            came_from: None,
+        Value::Int(a) => match a.checked_neg() {
+            Some(result) => Ok(StackValueWrapper {
+                value: Value::Int(result),
+                came_from: None,
+            }),
+
+            None => {
+                runtime_error!(IntegerOverflow, "Integer overflow in -{}", a)
+            }
         },
 
-        Value::Float(a) => StackValueWrapper {
+        Value::Float(a) => Ok(StackValueWrapper {
             value: Value::Float(-a),
             came_from: None,
-        },
+        }),
 
-        Value::Bool(b) => StackValueWrapper {
+        Value::Bool(b) => Ok(StackValueWrapper {
             value: Value::Bool(!b),
             came_from: None,
-        },
+        }),
 
-        _ => unreachable!(),
+        _ => runtime_error!(TypeMismatch, "Can only negate numbers"),
     }
 }
 
-pub fn equal(
-    heap: &VmHeap,
-    first: &StackValueWrapper,
-    second: &StackValueWrapper,
-) -> StackValueWrapper {
-    let first = first.value.clone();
-    let second = second.value.clone();
-
-    let first_is_object = matches!(first, Value::ObjectRef(_));
-    let second_is_object = matches!(first, Value::ObjectRef(_));
-
-    let mut equal = false;
-
-    if first_is_object && second_is_object {
-        let first_index = match first {
-            Value::ObjectRef(index) => index,
-            _ => unreachable!(),
-        };
-
-        let second_index = match second {
-            Value::ObjectRef(index) => index,
-            _ => unreachable!(),
-        };
-
-        let first_object = heap.get_object(first_index);
-        let second_object = heap.get_object(second_index);
-
-        equal = first_object == second_object;
-    }
-
-    if !first_is_object && second_is_object {
-        let second_index = match second {
-            Value::ObjectRef(index) => index,
-            _ => unreachable!(),
-        };
-
-        let second_object = heap.get_object(second_index);
-
-        match second_object {
-            Object::Value(second_value) => {
-                equal = first == second_value;
+/// Compare two values, looking inside of objects instead of just comparing
+/// the references to them
+fn values_equal(heap: &VmHeap, first: &Value, second: &Value) -> bool {
+    match (first, second) {
+        // values in an any are compared without their types
+        (Value::Any { value, .. }, other)
+        | (other, Value::Any { value, .. }) => values_equal(heap, value, other),
+
+        (Value::ObjectRef(first_index), Value::ObjectRef(second_index)) => {
+            let first_object = heap.get_object_ref(*first_index);
+            let second_object = heap.get_object_ref(*second_index);
+
+            match (first_object, second_object) {
+                (Object::Array(first), Object::Array(second)) => {
+                    first.len() == second.len()
+                        && first
+                            .iter()
+                            .zip(second.iter())
+                            .all(|(a, b)| values_equal(heap, a, b))
+                }
+
+                (Object::Map(first), Object::Map(second)) => {
+                    first.len() == second.len()
+                        && first.iter().all(|(key, a)| match second.get(key) {
+                            Some(b) => values_equal(heap, a, b),
+                            None => false,
+                        })
+                }
+
+                (Object::Value(first), Object::Value(second)) => {
+                    values_equal(heap, first, second)
+                }
+
+                (first_object, second_object) => first_object == second_object,
             }
+        }
 
-            _ => {
-                equal = false;
+        (value, Value::ObjectRef(index)) | (Value::ObjectRef(index), value) => {
+            match heap.get_object_ref(*index) {
+                Object::Value(object_value) => {
+                    values_equal(heap, value, object_value)
+                }
+
+                _ => false,
             }
         }
-    }
 
-    if first_is_object && !second_is_object {
-        let first_index = match first {
-            Value::ObjectRef(index) => index,
-            _ => unreachable!(),
-        };
-
-        let first_object = heap.get_object(first_index);
+        (
+            Value::Enum {
+                variant: first_variant,
+                fields: first,
+            },
+            Value::Enum {
+                variant: second_variant,
+                fields: second,
+            },
+        ) => {
+            first_variant == second_variant
+                && values_equal(
+                    heap,
+                    &Value::Struct(first.clone()),
+                    &Value::Struct(second.clone()),
+                )
+        }
 
-        match first_object {
-            Object::Value(first_value) => {
-                equal = first_value == second;
-            }
+        (Value::Tuple(first), Value::Tuple(second)) => {
+            first.len() == second.len()
+                && first
+                    .iter()
+                    .zip(second.iter())
+                    .all(|(a, b)| values_equal(heap, a, b))
+        }
 
-            _ => {
-                equal = false;
-            }
+        (Value::Struct(first), Value::Struct(second)) => {
+            first.len() == second.len()
+                && first.iter().all(|(name, a)| match second.get(name) {
+                    Some(b) => values_equal(heap, a, b),
+                    None => false,
+                })
         }
-    }
 
-    if !first_is_object && !second_is_object {
-        equal = first == second;
+        (first, second) => first == second,
     }
+}
+
+pub fn equal(
+    heap: &VmHeap,
+    first: &StackValueWrapper,
+    second: &StackValueWrapper,
+) -> Result<StackValueWrapper, RuntimeError> {
+    let equal = values_equal(heap, &first.value, &second.value);
 
-    StackValueWrapper {
+    Ok(StackValueWrapper {
         value: Value::Bool(equal),
         came_from: None,
-    }
+    })
 }
 
 pub fn less_than(
     heap: &VmHeap,
     first: &StackValueWrapper,
     second: &StackValueWrapper,
-) -> StackValueWrapper {
+) -> Result<StackValueWrapper, RuntimeError> {
     let first = extract_value(heap, first);
     let second = extract_value(heap, second);
 
     match (first, second) {
-        (Value::Int(a), Value::Int(b)) => StackValueWrapper {
+        (Value::Int(a), Value::Int(b)) => Ok(StackValueWrapper {
             value: Value::Bool(a < b),
             came_from: None,
-        },
+        }),
 
-        (Value::Int(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Int(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Bool((a as f64) < b),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Int(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Int(b)) => Ok(StackValueWrapper {
             value: Value::Bool(a < (b as f64)),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Bool(a < b),
             came_from: None,
-        },
+        }),
 
-        _ => panic!("Can only compare numbers"),
+        _ => runtime_error!(TypeMismatch, "Can only compare numbers"),
     }
 }
 
@@ -291,32 +383,32 @@ pub fn less_than_or_equal(
     heap: &VmHeap,
     first: &StackValueWrapper,
     second: &StackValueWrapper,
-) -> StackValueWrapper {
+) -> Result<StackValueWrapper, RuntimeError> {
     let first = extract_value(heap, first);
     let second = extract_value(heap, second);
 
     match (first, second) {
-        (Value::Int(a), Value::Int(b)) => StackValueWrapper {
+        (Value::Int(a), Value::Int(b)) => Ok(StackValueWrapper {
             value: Value::Bool(a <= b),
             came_from: None,
-        },
+        }),
 
-        (Value::Int(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Int(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Bool((a as f64) <= b),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Int(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Int(b)) => Ok(StackValueWrapper {
             value: Value::Bool(a <= (b as f64)),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Bool(a <= b),
             came_from: None,
-        },
+        }),
 
-        _ => panic!("Can only compare numbers"),
+        _ => runtime_error!(TypeMismatch, "Can only compare numbers"),
     }
 }
 
@@ -324,32 +416,32 @@ pub fn greater_than(
     heap: &VmHeap,
     first: &StackValueWrapper,
     second: &StackValueWrapper,
-) -> StackValueWrapper {
+) -> Result<StackValueWrapper, RuntimeError> {
     let first = extract_value(heap, first);
     let second = extract_value(heap, second);
 
     match (first, second) {
-        (Value::Int(a), Value::Int(b)) => StackValueWrapper {
+        (Value::Int(a), Value::Int(b)) => Ok(StackValueWrapper {
             value: Value::Bool(a > b),
             came_from: None,
-        },
+        }),
 
-        (Value::Int(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Int(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Bool((a as f64) > b),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Int(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Int(b)) => Ok(StackValueWrapper {
             value: Value::Bool(a > (b as f64)),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Bool(a > b),
             came_from: None,
-        },
+        }),
 
-        _ => panic!("Can only compare numbers"),
+        _ => runtime_error!(TypeMismatch, "Can only compare numbers"),
     }
 }
 
@@ -357,41 +449,408 @@ pub fn greater_than_or_equal(
     heap: &VmHeap,
     first: &StackValueWrapper,
     second: &StackValueWrapper,
-) -> StackValueWrapper {
+) -> Result<StackValueWrapper, RuntimeError> {
     let first = extract_value(heap, first);
     let second = extract_value(heap, second);
 
     match (first, second) {
-        (Value::Int(a), Value::Int(b)) => StackValueWrapper {
+        (Value::Int(a), Value::Int(b)) => Ok(StackValueWrapper {
             value: Value::Bool(a >= b),
             came_from: None,
-        },
+        }),
 
-        (Value::Int(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Int(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Bool((a as f64) >= b),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Int(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Int(b)) => Ok(StackValueWrapper {
             value: Value::Bool(a >= (b as f64)),
             came_from: None,
-        },
+        }),
 
-        (Value::Float(a), Value::Float(b)) => StackValueWrapper {
+        (Value::Float(a), Value::Float(b)) => Ok(StackValueWrapper {
             value: Value::Bool(a >= b),
             came_from: None,
+        }),
+
+        _ => runtime_error!(TypeMismatch, "Can only compare numbers"),
+    }
+}
+
+pub fn is_true(
+    heap: &VmHeap,
+    value: &StackValueWrapper,
+) -> Result<bool, RuntimeError> {
+    let value = extract_value(heap, value);
+
+    match value {
+        Value::Bool(b) => Ok(b),
+
+        _ => runtime_error!(
+            TypeMismatch,
+            "Can't determine if non-boolean value is true"
+        ),
+    }
+}
+
+pub fn length(
+    heap: &VmHeap,
+    value: &StackValueWrapper,
+) -> Result<StackValueWrapper, RuntimeError> {
+    let value = extract_value(heap, value);
+
+    match value {
+        Value::ObjectRef(index) => match heap.get_object_ref(index) {
+            Object::String(s) => Ok(StackValueWrapper {
+                value: Value::Int(s.chars().count() as i64),
+                came_from: None,
+            }),
+
+            Object::Array(elements) => Ok(StackValueWrapper {
+                value: Value::Int(elements.len() as i64),
+                came_from: None,
+            }),
+
+            _ => runtime_error!(TypeMismatch, "Value has no length"),
         },
 
-        _ => panic!("Can only compare numbers"),
+        _ => runtime_error!(TypeMismatch, "Value has no length"),
     }
 }
 
-pub fn is_true(heap: &VmHeap, value: &StackValueWrapper) -> bool {
+/// Turn a value into the key it's stored under in a map, None if the value
+/// can't be used as a key
+pub fn map_key(heap: &VmHeap, value: &Value) -> Option<MapKey> {
+    match value {
+        Value::Int(v) => Some(MapKey::Int(*v)),
+        Value::Char(v) => Some(MapKey::Char(*v)),
+        Value::Bool(v) => Some(MapKey::Bool(*v)),
+
+        Value::ObjectRef(index) => match heap.get_object_ref(*index) {
+            Object::String(s) => Some(MapKey::String(s.clone())),
+            Object::Value(v) => map_key(heap, v),
+
+            _ => None,
+        },
+
+        _ => None,
+    }
+}
+
+pub fn get_index(
+    heap: &VmHeap,
+    value: &StackValueWrapper,
+    index: &StackValueWrapper,
+) -> Result<StackValueWrapper, RuntimeError> {
     let value = extract_value(heap, value);
+    let index = extract_value(heap, index);
+
+    let object = match value {
+        Value::ObjectRef(object_index) => heap.get_object_ref(object_index),
+
+        // the analyzer makes sure tuples are only indexed with literals which
+        // are in bounds
+        Value::Tuple(elements) => {
+            let element = match index {
+                Value::Int(i) => elements.get(i as usize),
+                _ => None,
+            };
+
+            return match element {
+                Some(v) => Ok(StackValueWrapper {
+                    value: v.clone(),
+                    came_from: None,
+                }),
+
+                None => runtime_error!(
+                    IndexOutOfBounds,
+                    "Index is out of bounds for a tuple with {} elements",
+                    elements.len()
+                ),
+            };
+        }
+
+        _ => return runtime_error!(TypeMismatch, "Value cannot be indexed"),
+    };
+
+    // looking up a key which isn't in the map gives null
+    if let Object::Map(entries) = object {
+        let key = match map_key(heap, &index) {
+            Some(key) => key,
+
+            None => {
+                return runtime_error!(
+                    TypeMismatch,
+                    "Value cannot be used as a map key"
+                )
+            }
+        };
+
+        return Ok(StackValueWrapper {
+            value: entries.get(&key).cloned().unwrap_or(Value::Null),
+            came_from: None,
+        });
+    }
+
+    let index = match index {
+        Value::Int(i) => i,
+
+        _ => return runtime_error!(TypeMismatch, "Index must be an int"),
+    };
+
+    match object {
+        Object::String(s) => {
+            let char = match usize::try_from(index) {
+                Ok(i) => s.chars().nth(i),
+                Err(_) => None,
+            };
+
+            match char {
+                Some(c) => Ok(StackValueWrapper {
+                    value: Value::Char(c),
+                    came_from: None,
+                }),
+
+                None => runtime_error!(
+                    IndexOutOfBounds,
+                    "Index {} is out of bounds for a string of length {}",
+                    index,
+                    s.chars().count()
+                ),
+            }
+        }
+
+        Object::Array(elements) => {
+            let element = match usize::try_from(index) {
+                Ok(i) => elements.get(i),
+                Err(_) => None,
+            };
+
+            match element {
+                Some(v) => Ok(StackValueWrapper {
+                    value: v.clone(),
+                    came_from: None,
+                }),
+
+                None => runtime_error!(
+                    IndexOutOfBounds,
+                    "Index {} is out of bounds for an array of length {}",
+                    index,
+                    elements.len()
+                ),
+            }
+        }
+
+        _ => runtime_error!(TypeMismatch, "Value cannot be indexed"),
+    }
+}
+
+/// Get the text a value is converted to when it's cast to a string
+pub fn value_to_string(heap: &VmHeap, value: &Value) -> String {
+    let join = |values: &mut dyn Iterator<Item = std::string::String>| {
+        values.collect::<Vec<_>>().join(", ")
+    };
 
     match value {
-        Value::Bool(b) => b,
+        Value::Null => "null".to_string(),
+        Value::Char(v) => v.to_string(),
+        Value::Int(v) => v.to_string(),
+        Value::Float(v) => v.to_string(),
+        Value::Bool(v) => v.to_string(),
+
+        Value::ObjectRef(index) => match heap.get_object_ref(*index) {
+            Object::String(s) => s.clone(),
+            Object::Value(v) => value_to_string(heap, v),
+
+            Object::Array(elements) => format!(
+                "[{}]",
+                join(&mut elements.iter().map(|v| value_to_string(heap, v)))
+            ),
+
+            // the entries are sorted so that the text is always the same
+            Object::Map(entries) => {
+                let mut entries: Vec<(&MapKey, &Value)> =
+                    entries.iter().collect();
+                entries.sort_by_key(|(key, _)| *key);
+
+                format!(
+                    "map {{ {} }}",
+                    join(&mut entries.iter().map(|(key, v)| {
+                        let key = match key {
+                            MapKey::String(k) => k.clone(),
+                            MapKey::Int(k) => k.to_string(),
+                            MapKey::Char(k) => k.to_string(),
+                            MapKey::Bool(k) => k.to_string(),
+                        };
+
+                        format!("{}: {}", key, value_to_string(heap, v))
+                    }))
+                )
+            }
+
+            Object::Closure { .. } => "function".to_string(),
+        },
+
+        Value::Function(_) | Value::NativeFunction(_) | Value::Closure(_) => {
+            "function".to_string()
+        }
+
+        Value::Struct(fields) => {
+            format!("{{ {} }}", fields_to_string(heap, fields))
+        }
+
+        Value::Enum { variant, fields } if fields.is_empty() => variant.clone(),
+        Value::Enum { variant, fields } => {
+            format!("{} {{ {} }}", variant, fields_to_string(heap, fields))
+        }
+
+        Value::Tuple(elements) => format!(
+            "<{}>",
+            join(&mut elements.iter().map(|v| value_to_string(heap, v)))
+        ),
+
+        Value::Any { value, .. } => value_to_string(heap, value),
+
+        Value::StructFieldName(name) => name.clone(),
+
+        // replaced by the parameter's default value before it can be used
+        Value::DefaultArgument => unreachable!(),
+    }
+}
+
+fn fields_to_string(heap: &VmHeap, fields: &HashMap<String, Value>) -> String {
+    let mut fields: Vec<(&String, &Value)> = fields.iter().collect();
+    fields.sort_by_key(|(name, _)| *name);
+
+    fields
+        .iter()
+        .map(|(name, v)| format!("{}: {}", name, value_to_string(heap, v)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Check if a value from an any which was stored with the given type can be
+/// cast to another type
+fn any_type_matches(stored_type: &Type, value: &Value, type_: &Type) -> bool {
+    if stored_type == type_ {
+        return true;
+    }
+
+    match (stored_type, type_) {
+        // a nullable value can be cast to its inner type if it isn't null
+        (Type::Nullable(inner), _) => match value {
+            Value::Null => any_type_matches(&Type::Null, value, type_),
+            _ => any_type_matches(inner, value, type_),
+        },
+
+        (_, Type::Nullable(inner)) => {
+            *stored_type == Type::Null
+                || any_type_matches(stored_type, value, inner)
+        }
+
+        (
+            Type::UserDefined(name),
+            Type::EnumVariant {
+                name: enum_name,
+                variant,
+            },
+        ) => {
+            name == enum_name
+                && matches!(value, Value::Enum { variant: v, .. } if v == variant)
+        }
+
+        _ => false,
+    }
+}
 
-        _ => panic!("Can't determine if non-boolean value is true"),
+/// Check if a value which isn't in a Value::Any has the given type. This only
+/// happens for values given by the host, so only its simple types are checked
+fn untyped_value_matches(heap: &VmHeap, value: &Value, type_: &Type) -> bool {
+    match (value, type_) {
+        (Value::Null, Type::Null | Type::Nullable(_))
+        | (Value::Char(_), Type::Char)
+        | (Value::Int(_), Type::Int)
+        | (Value::Float(_), Type::Float)
+        | (Value::Bool(_), Type::Bool) => true,
+
+        (Value::ObjectRef(index), Type::String) => {
+            matches!(heap.get_object_ref(*index), Object::String(_))
+        }
+
+        (value, Type::Nullable(inner)) => {
+            untyped_value_matches(heap, value, inner)
+        }
+
+        _ => false,
     }
 }
+
+/// The result of a cast. Casting to a string gives the text of the new string
+/// object, which the VM then allocates
+pub enum CastResult {
+    Value(Value),
+    String(String),
+}
+
+pub fn cast(
+    heap: &VmHeap,
+    value: &StackValueWrapper,
+    type_: &Type,
+) -> Result<CastResult, RuntimeError> {
+    let value = extract_value(heap, value);
+
+    let result = match (value, type_) {
+        (
+            Value::Any {
+                type_: stored_type,
+                value,
+            },
+            _,
+        ) => {
+            if !any_type_matches(&stored_type, &value, type_) {
+                return runtime_error!(
+                    InvalidCast,
+                    "Cannot cast a value of type {:?} to type {:?}",
+                    stored_type,
+                    type_
+                );
+            }
+
+            *value
+        }
+
+        (value, Type::String) => {
+            return Ok(CastResult::String(value_to_string(heap, &value)))
+        }
+
+        (Value::Int(v), Type::Float) => Value::Float(v as f64),
+        (Value::Float(v), Type::Int) => Value::Int(v as i64),
+        (Value::Char(v), Type::Int) => Value::Int(v as i64),
+
+        (Value::Int(v), Type::Char) => {
+            match u32::try_from(v).ok().and_then(char::from_u32) {
+                Some(c) => Value::Char(c),
+                None => {
+                    return runtime_error!(
+                        InvalidCast,
+                        "{} is not a valid character",
+                        v
+                    )
+                }
+            }
+        }
+
+        (value, _) if untyped_value_matches(heap, &value, type_) => value,
+
+        (_, _) => {
+            return runtime_error!(
+                InvalidCast,
+                "Cannot cast value to type {:?}",
+                type_
+            )
+        }
+    };
+
+    Ok(CastResult::Value(result))
+}
@@ -5,7 +5,14 @@ use kuon::{
     compiler::{self},
     lexer::Lexer,
     parser,
-    vm::{self, execution_result::ExecutionResult},
+    vm::{
+        self,
+        execution_result::ExecutionResult,
+        native_function::NativeFunctions,
+        runtime_error::{RuntimeError, RuntimeErrorKind, StackTraceEntry},
+        Vm,
+    },
+    Engine, KuonError,
 };
 
 fn eval(code: &str) -> ExecutionResult {
@@ -17,9 +24,62 @@ fn eval(code: &str) -> ExecutionResult {
 
     let chunk = compiler::compile_source(&ast).unwrap();
 
+    vm::execute(&chunk).unwrap()
+}
+
+fn eval_with_vm(
+    code: &str,
+    vm: &mut Vm,
+) -> Result<ExecutionResult, RuntimeError> {
+    let code_str = code.to_string();
+    let mut lexer = Lexer::from_string(&code_str);
+
+    let mut ast = parser::parse_source(&mut lexer).unwrap();
+    analyzer::validate(&mut ast).unwrap();
+
+    let chunk = compiler::compile_source(&ast).unwrap();
+
+    vm.run(&chunk)
+}
+
+fn eval_with_natives(
+    code: &str,
+    natives: &NativeFunctions,
+) -> Result<ExecutionResult, RuntimeError> {
+    let code_str = code.to_string();
+    let mut lexer = Lexer::from_string(&code_str);
+
+    let mut ast = parser::parse_source(&mut lexer).unwrap();
+    analyzer::validate_with_natives(&mut ast, natives).unwrap();
+
+    let chunk = compiler::compile_source_with_natives(&ast, natives).unwrap();
+
     vm::execute(&chunk)
 }
 
+/// Parse and validate code which has to be rejected, and check that the parser
+/// or the analyzer fails with a message containing the expected error
+fn assert_error(code: &str, error: &str) {
+    let code_str = code.to_string();
+    let mut lexer = Lexer::from_string(&code_str);
+
+    let message = match parser::parse_source(&mut lexer) {
+        Ok(mut ast) => match analyzer::validate(&mut ast) {
+            Ok(_) => panic!("Expected an error in {}", code),
+            Err(e) => e.message,
+        },
+        Err(e) => e.message,
+    };
+
+    assert!(
+        message.contains(error),
+        "Expected an error containing {:?} in {}, got {:?}",
+        error,
+        code,
+        message
+    );
+}
+
 #[test]
 fn test1() {
     let source = "1 + 2";
@@ -233,3 +293,2666 @@ fn test11() {
 
     assert_eq!(eval(source), ExecutionResult::Int(2));
 }
+
+#[test]
+fn test12() {
+    let source = r#"
+    fun makeGarbage(n int) int {
+        val s = "garbage";
+
+        if n == 0 {
+            0
+        } else {
+            makeGarbage(n - 1)
+        }
+    }
+
+    makeGarbage(50);
+    makeGarbage(50);
+    makeGarbage(50)
+    "#;
+
+    let mut vm = Vm::new();
+    vm.heap_mut().set_gc_threshold(0);
+
+    assert_eq!(eval_with_vm(source, &mut vm), Ok(ExecutionResult::Int(0)));
+
+    let stats = vm.heap().stats();
+    assert!(stats.collections > 0);
+    assert!(stats.live_objects < 150);
+}
+
+#[test]
+fn test13() {
+    let source = r#"
+    struct Person {
+        name string,
+        age int
+    }
+
+    fun rename(var p Person, name string) null {
+        "garbage";
+        p.name = name;
+    }
+
+    var p = Person { name: "idk", age: 20 };
+    rename(p, "kuon");
+    "garbage";
+    p
+    "#;
+
+    let mut vm = Vm::new();
+    vm.heap_mut().set_gc_threshold(0);
+
+    assert_eq!(
+        eval_with_vm(source, &mut vm),
+        Ok(ExecutionResult::Struct {
+            fields: HashMap::from([
+                (
+                    "name".to_string(),
+                    ExecutionResult::String("kuon".to_string())
+                ),
+                ("age".to_string(), ExecutionResult::Int(20)),
+            ])
+        })
+    );
+}
+
+#[test]
+fn test14() {
+    let source = r#"
+    val offset = 10;
+
+    fun addOffset(n int) int {
+        n + offset
+    }
+
+    addOffset(5)
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(15));
+}
+
+#[test]
+fn test15() {
+    let source = r#"
+    fun (n int):minus(m int) int {
+        n - m
+    }
+
+    val a = 10;
+    a:minus(4)
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(6));
+}
+
+#[test]
+fn test16() {
+    let source = r#"
+    fun sum(n int) int {
+        if n == 0 {
+            0
+        } else {
+            n + sum(n - 1)
+        }
+    }
+
+    sum(100000)
+    "#;
+
+    let mut vm = Vm::new();
+    vm.set_max_frames(1_000_000);
+
+    assert_eq!(
+        eval_with_vm(source, &mut vm),
+        Ok(ExecutionResult::Int(5000050000))
+    );
+}
+
+#[test]
+fn test17() {
+    let source = r#"
+    fun forever(n int) int {
+        forever(n + 1)
+    }
+
+    forever(0)
+    "#;
+
+    let mut vm = Vm::new();
+    vm.set_max_frames(100);
+
+    let error = eval_with_vm(source, &mut vm).unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::StackOverflow);
+
+    // the recursive calls collapse into a single entry
+    assert_eq!(
+        error.stack_trace,
+        vec![
+            StackTraceEntry {
+                function: Some("forever".to_string()),
+                line: 3,
+                repeated: 99
+            },
+            StackTraceEntry {
+                function: None,
+                line: 6,
+                repeated: 1
+            },
+        ]
+    );
+    assert_eq!(error.omitted_frames, 0);
+
+    // the VM can still be used after the error
+    assert_eq!(eval_with_vm("1 + 2", &mut vm), Ok(ExecutionResult::Int(3)));
+}
+
+#[test]
+fn test18() {
+    let source = r#"
+    fun modifyTwice(var n int) null {
+        n = 2;
+        n = n + 1;
+    }
+
+    var a = 1;
+    modifyTwice(a);
+    a
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(3));
+}
+
+#[test]
+fn test19() {
+    let source = r#"
+    fun divide(a int, b int) float {
+        a / b
+    }
+
+    fun run() float {
+        divide(1, 0)
+    }
+
+    run()
+    "#;
+
+    let error = eval_with_vm(source, &mut Vm::new()).unwrap_err();
+
+    assert_eq!(error.kind, RuntimeErrorKind::DivisionByZero);
+    assert_eq!(error.line, 3);
+    assert_eq!(
+        error.stack_trace,
+        vec![
+            StackTraceEntry {
+                function: Some("divide".to_string()),
+                line: 3,
+                repeated: 1
+            },
+            StackTraceEntry {
+                function: Some("run".to_string()),
+                line: 7,
+                repeated: 1
+            },
+            StackTraceEntry {
+                function: None,
+                line: 10,
+                repeated: 1
+            },
+        ]
+    );
+}
+
+#[test]
+fn test20() {
+    let source = r#"
+    fun isOne(var n int) bool {
+        n == 1
+    }
+
+    var a = 1;
+    isOne(a)
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Bool(true));
+}
+
+#[test]
+fn test21() {
+    let mut natives = NativeFunctions::new();
+    natives
+        .register("double", "fun (int) int", |args| match args {
+            [ExecutionResult::Int(n)] => Ok(ExecutionResult::Int(n * 2)),
+            _ => Err("expected an int".to_string()),
+        })
+        .unwrap();
+    natives
+        .register("hasLength", "fun (string, int) bool", |args| match args {
+            [ExecutionResult::String(s), ExecutionResult::Int(n)] => {
+                Ok(ExecutionResult::Bool(s.len() as i64 == *n))
+            }
+            _ => Err("expected a string and an int".to_string()),
+        })
+        .unwrap();
+
+    let source = r#"
+    fun quadruple(n int) int {
+        double(double(n))
+    }
+
+    val name = "kuon";
+    if hasLength(name, quadruple(1)) { double(21) } else { 0 }
+    "#;
+
+    assert_eq!(
+        eval_with_natives(source, &natives),
+        Ok(ExecutionResult::Int(42))
+    );
+}
+
+#[test]
+fn test22() {
+    let mut natives = NativeFunctions::new();
+    natives
+        .register("fail", "fun (string) null", |args| match args {
+            [ExecutionResult::String(s)] => Err(s.clone()),
+            _ => Err("expected a string".to_string()),
+        })
+        .unwrap();
+
+    let source = r#"
+    1 + 2;
+    fail("oops")
+    "#;
+
+    let error = eval_with_natives(source, &natives).unwrap_err();
+
+    assert_eq!(error.kind, RuntimeErrorKind::NativeError);
+    assert_eq!(error.line, 3);
+
+    assert!(natives
+        .register("broken", "fun (int", |_| Ok(ExecutionResult::Null))
+        .is_err());
+}
+
+#[test]
+fn test23() {
+    let mut engine = Engine::new();
+
+    let source = r#"
+    var counter = 0;
+
+    fun increment(by int) int {
+        counter = counter + by;
+        counter
+    }
+    "#;
+    engine.eval(source).unwrap();
+
+    assert_eq!(
+        engine
+            .call_function("increment", vec![ExecutionResult::Int(2)])
+            .unwrap(),
+        ExecutionResult::Int(2)
+    );
+    assert_eq!(
+        engine
+            .call_function("increment", vec![ExecutionResult::Int(3)])
+            .unwrap(),
+        ExecutionResult::Int(5)
+    );
+
+    assert_eq!(
+        engine.eval("increment(1) + counter").unwrap(),
+        ExecutionResult::Int(12)
+    );
+
+    let chunk = engine.compile("counter").unwrap();
+    assert_eq!(engine.run(&chunk).unwrap(), ExecutionResult::Int(6));
+}
+
+#[test]
+fn test24() {
+    let mut engine = Engine::new();
+
+    engine
+        .register_function("greet", "fun (string) string", |args| match args {
+            [ExecutionResult::String(s)] => {
+                Ok(ExecutionResult::String(format!("hello {}", s)))
+            }
+            _ => Err("expected a string".to_string()),
+        })
+        .unwrap();
+
+    assert_eq!(
+        engine.eval(r#"greet("kuon")"#).unwrap(),
+        ExecutionResult::String("hello kuon".to_string())
+    );
+    assert_eq!(
+        engine
+            .call_function(
+                "greet",
+                vec![ExecutionResult::String("host".to_string())]
+            )
+            .unwrap(),
+        ExecutionResult::String("hello host".to_string())
+    );
+
+    assert!(matches!(engine.eval("val a = "), Err(KuonError::Parser(_))));
+    assert!(matches!(
+        engine.eval("val a int = 'c';"),
+        Err(KuonError::Analyzer(_))
+    ));
+    assert!(matches!(
+        engine.call_function("missing", vec![]),
+        Err(KuonError::UnknownFunction(_))
+    ));
+
+    // nothing is kept from code which failed to compile
+    engine.eval("val b = 1; val c int = 'x';").unwrap_err();
+    assert_eq!(
+        engine.eval("val b = 2; b").unwrap(),
+        ExecutionResult::Int(2)
+    );
+
+    engine.eval("fun zero(n int) float { n / 0 }").unwrap();
+    match engine.call_function("zero", vec![ExecutionResult::Int(1)]) {
+        Err(KuonError::Runtime(error)) => {
+            assert_eq!(error.kind, RuntimeErrorKind::DivisionByZero);
+            assert_eq!(error.stack_trace.len(), 1);
+        }
+        result => panic!("Expected a runtime error, got {:?}", result),
+    }
+}
+
+#[test]
+fn test25() {
+    let mut engine = Engine::new();
+
+    let source = r#"
+    struct Player {
+        name string
+        health int
+    }
+
+    fun damage(amount int, critical bool) int {
+        if critical == true { amount * 2 } else { amount }
+    }
+
+    fun (p Player):describe(prefix string) string {
+        prefix
+    }
+
+    fun (p Player):isAlive() bool {
+        p.health > 0
+    }
+    "#;
+    engine.eval(source).unwrap();
+
+    let result = engine
+        .call_function("damage", vec![5.into(), true.into()])
+        .unwrap();
+    assert_eq!(i64::try_from(result), Ok(10));
+
+    let player = ExecutionResult::Struct {
+        fields: HashMap::from([
+            ("name".to_string(), "kuon".into()),
+            ("health".to_string(), 3.into()),
+        ]),
+    };
+    assert_eq!(
+        engine
+            .call_value_function("isAlive", player.clone(), vec![])
+            .unwrap(),
+        ExecutionResult::Bool(true)
+    );
+    assert_eq!(
+        engine
+            .call_value_function("describe", player, vec!["hero".into()])
+            .unwrap(),
+        ExecutionResult::String("hero".to_string())
+    );
+
+    assert!(matches!(
+        engine.call_function("damage", vec![5.into()]),
+        Err(KuonError::InvalidArguments(_))
+    ));
+    assert!(matches!(
+        engine.call_function("damage", vec![5.into(), 'c'.into()]),
+        Err(KuonError::InvalidArguments(_))
+    ));
+    assert!(matches!(
+        engine.call_value_function("isAlive", 5.into(), vec![]),
+        Err(KuonError::UnknownFunction(_))
+    ));
+}
+
+#[test]
+fn test26() {
+    let source = r#"
+    fun total(start int) int {
+        var count = start;
+        val increment = fun (by int) null {
+            count = count + by;
+        };
+
+        increment(2);
+        increment(3);
+        count
+    }
+
+    total(10)
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(15));
+}
+
+#[test]
+fn test27() {
+    let source = r#"
+    fun outer(a int, name string) string {
+        val middle = fun (b int) string {
+            val inner = fun (c int) string {
+                if a + b + c == 6 { name } else { "wrong" }
+            };
+            inner(3)
+        };
+
+        middle(2)
+    }
+
+    outer(1, "closure")
+    "#;
+
+    let mut vm = Vm::new();
+    vm.heap_mut().set_gc_threshold(0);
+
+    assert_eq!(
+        eval_with_vm(source, &mut vm),
+        Ok(ExecutionResult::String("closure".to_string()))
+    );
+}
+
+#[test]
+fn test28() {
+    let source = r#"
+    var i = 0;
+    var total = 0;
+
+    while i < 10 {
+        i = i + 1;
+
+        if i == 3 { continue; }
+        if i == 8 { break; }
+
+        total = total + i;
+    }
+
+    total
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(25));
+}
+
+#[test]
+fn test29() {
+    let source = r#"
+    fun firstSquareOver(limit int) int {
+        var n = 0;
+
+        loop {
+            n = n + 1;
+
+            if n * n > limit {
+                break n * n;
+            }
+        }
+    }
+
+    firstSquareOver(50)
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(64));
+
+    for source in [
+        "break;",
+        "while true { break 1; }",
+        "loop { if true { break 1; } else { break 'c'; } }",
+        "fun foo() null { break; }",
+    ] {
+        let code = source.to_string();
+        let mut lexer = Lexer::from_string(&code);
+        let mut ast = parser::parse_source(&mut lexer).unwrap();
+
+        assert!(analyzer::validate(&mut ast).is_err());
+    }
+}
+
+#[test]
+fn test30() {
+    let source = r#"
+    var count = 0;
+
+    for c in "hello world" {
+        if c == ' ' { continue; }
+
+        if c == 'l' {
+            count = count + 10;
+        } else {
+            count = count + 1;
+        }
+    }
+
+    var pairs = 0;
+    for a in "ab" {
+        for b in "xyz" {
+            pairs = pairs + 1;
+        }
+    }
+
+    count * 100 + pairs
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(3706));
+}
+
+#[test]
+fn test31() {
+    let source = r#"
+    fun boom() bool {
+        1 / 0 == 0.0
+    }
+
+    val a = false and boom();
+    val b = true or boom();
+    val c = true and false or not false;
+    val d = 1 != 2 and not 1 == 2;
+
+    if a == false and b and c and d { 1 } else { 0 }
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(1));
+
+    let source = r#"
+    fun boom() bool {
+        1 / 0 == 0.0
+    }
+
+    true and boom()
+    "#;
+
+    let error = eval_with_vm(source, &mut Vm::new()).unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::DivisionByZero);
+}
+
+#[test]
+fn test32() {
+    assert_eq!(eval("'a' != 'b'"), ExecutionResult::Bool(true));
+    assert_eq!(eval(r#""abc" != "abc""#), ExecutionResult::Bool(false));
+    assert_eq!(eval("not 3 < 2 or false"), ExecutionResult::Bool(true));
+
+    for source in ["1 and true", "not 1", "true or 'c'", "1 != 'c'"] {
+        let code = source.to_string();
+        let mut lexer = Lexer::from_string(&code);
+        let mut ast = parser::parse_source(&mut lexer).unwrap();
+
+        assert!(analyzer::validate(&mut ast).is_err());
+    }
+}
+
+#[test]
+fn test33() {
+    let source = r#"
+    struct Age {
+        years int,
+        months int
+    }
+
+    struct Person {
+        name string,
+        age Age
+    }
+
+    var p = Person {
+        name: "kuon",
+        age: mkstruct { years: 20, months: 3 }
+    };
+
+    var total = 0;
+    var i = 0;
+    while i < 5 {
+        i += 1;
+        total += i * 2;
+    }
+
+    var f = 10.0;
+    f /= 4;
+    f *= 2;
+
+    p.age.years -= 2;
+    p.age.months *= total;
+
+    if f == 5.0 { p.age.years * 100 + p.age.months } else { 0 }
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(1890));
+}
+
+#[test]
+fn test34() {
+    for (source, error) in [
+        ("val a = 1; a += 1;", "Cannot reassign constant variable a"),
+        (
+            r#"var s = "a"; s += "b";"#,
+            "Operator Plus only works on numbers",
+        ),
+        (
+            "var n = 4; n /= 2;",
+            "Operator /= cannot be used on n, which is an int",
+        ),
+        (
+            "var n = 4; n += 0.5;",
+            "Cannot use a float with an operator on n, which is an int",
+        ),
+        (
+            "var m = map { \"a\": 1 }; m[\"a\"] += 1;",
+            "Operator Plus only works on numbers",
+        ),
+        (
+            "struct P { age int } val p = P { age: 1 }; p.age += 1;",
+            "Cannot reassign constant variable p",
+        ),
+        (
+            "1 += 2;",
+            "Can only assign to a variable, a struct field or an array element",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test35() {
+    let source = r#"
+    struct Point {
+        x int,
+        y int
+    }
+
+    fun sum(xs array[int]) int {
+        var total = 0;
+        for x in xs {
+            total += x;
+        }
+
+        total
+    }
+
+    var xs = [1, 2, 3];
+    var ys = xs;
+    ys[0] = 10;
+
+    var grid array[array[int]] = [[1, 2], [3, 4],];
+    grid[1][0] = 30;
+
+    var points = [Point { x: 1, y: 2 }, mkstruct { x: 3, y: 4 }];
+    points[1].y = 40;
+
+    sum(xs) + grid[1][0] + points[1].y
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(85));
+
+    assert_eq!(
+        eval(r#"["a", "b"] == ["a", "b"]"#),
+        ExecutionResult::Bool(true)
+    );
+    assert_eq!(
+        eval(r#"val words = ["a", "b"]; words"#),
+        ExecutionResult::Array(vec![
+            ExecutionResult::String("a".to_string()),
+            ExecutionResult::String("b".to_string()),
+        ])
+    );
+}
+
+#[test]
+fn test36() {
+    for source in [
+        "var xs = [1, 2]; xs[2]",
+        "var xs = [1, 2]; xs[-1]",
+        "var xs = [1, 2]; xs[5] = 3;",
+    ] {
+        let error = eval_with_vm(source, &mut Vm::new()).unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::IndexOutOfBounds);
+    }
+
+    for source in [
+        "[1, 'a']",
+        "[]",
+        "var xs = [1]; xs['a']",
+        "val xs = [1]; xs[0] = 2;",
+        r#"var xs = [1]; xs[0] = "a";"#,
+        r#"var s = "abc"; s[0] = 'x';"#,
+        "var xs array[string] = [1];",
+    ] {
+        let code = source.to_string();
+        let mut lexer = Lexer::from_string(&code);
+        let mut ast = parser::parse_source(&mut lexer).unwrap();
+
+        assert!(analyzer::validate(&mut ast).is_err());
+    }
+}
+
+#[test]
+fn test37() {
+    let source = r#"
+    var ages = map {
+        "kuon": 20,
+        "yui": 19,
+    };
+    val other = ages;
+
+    val name = "kuon";
+    ages[name] = 21;
+    ages["new"] = 1;
+
+    other
+    "#;
+
+    assert_eq!(
+        eval(source),
+        ExecutionResult::Map(vec![
+            (ExecutionResult::from("kuon"), ExecutionResult::Int(21)),
+            (ExecutionResult::from("new"), ExecutionResult::Int(1)),
+            (ExecutionResult::from("yui"), ExecutionResult::Int(19)),
+        ])
+    );
+
+    assert_eq!(
+        eval(r#"val m = map { 'a': "x" }; m['a']"#),
+        ExecutionResult::from("x")
+    );
+    assert_eq!(
+        eval("val m = map { 1: true, 2: false }; m[3]"),
+        ExecutionResult::Null
+    );
+    assert_eq!(
+        eval("val m map[bool, array[int]] = map { true: [1] }; m[true]"),
+        ExecutionResult::Array(vec![ExecutionResult::Int(1)])
+    );
+    assert_eq!(
+        eval(r#"map { "a": 1 } == map { "a": 1 }"#),
+        ExecutionResult::Bool(true)
+    );
+}
+
+#[test]
+fn test38() {
+    for source in [
+        "map {}",
+        "map { 1.5: 1 }",
+        r#"map { "a": 1, 2: 2 }"#,
+        r#"map { "a": 1, "b": "2" }"#,
+        r#"var m = map { "a": 1 }; m[1]"#,
+        r#"var m = map { "a": 1 }; m["b"] = 'c';"#,
+        r#"var m = map { "a": 1 }; val n int = m["a"];"#,
+        r#"val m map[string, int] = map { "a": 'b' };"#,
+    ] {
+        let code = source.to_string();
+        let mut lexer = Lexer::from_string(&code);
+        let mut ast = parser::parse_source(&mut lexer).unwrap();
+
+        assert!(analyzer::validate(&mut ast).is_err());
+    }
+
+    assert_eq!(
+        eval(r#"var m = map { "a": 1 }; val n nullable int = m["a"]; n"#),
+        ExecutionResult::Int(1)
+    );
+}
+
+#[test]
+fn test39() {
+    let source = r#"
+    fun product_and_difference(a int, b int) <int, int> {
+        <a * b, a - b>
+    }
+
+    val <product, difference> = product_and_difference(5, 2);
+    var pair <string, <int, bool>> = <"one", <1, (1 < 2)>>;
+
+    if pair[1][1] and <product, difference> == <10, 3> {
+        pair[1][0] + product * 10 + difference
+    } else {
+        0
+    }
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(104));
+
+    assert_eq!(
+        eval(r#"<'a', "b", -1>"#),
+        ExecutionResult::Tuple(vec![
+            ExecutionResult::Char('a'),
+            ExecutionResult::from("b"),
+            ExecutionResult::Int(-1),
+        ])
+    );
+    assert_eq!(eval("<1, 2> == <1, 3>"), ExecutionResult::Bool(false));
+}
+
+#[test]
+fn test40() {
+    for source in [
+        "val <a, b> = <1, 2, 3>;",
+        "val <a, a> = <1, 2>;",
+        "val <a, b> = 1;",
+        "val <a, b> <int, int> = <1, 'c'>;",
+        "val t = <1, 2>; t[2]",
+        "val t = <1, 2>; val i = 0; t[i]",
+        "var t = <1, 2>; t[0] = 3;",
+        "<1, 2> == <1, 2, 3>",
+    ] {
+        let code = source.to_string();
+        let mut lexer = Lexer::from_string(&code);
+        let mut ast = parser::parse_source(&mut lexer).unwrap();
+
+        assert!(analyzer::validate(&mut ast).is_err());
+    }
+}
+
+#[test]
+fn test41() {
+    let source = r#"
+    enum Element {
+        Empty,
+        Text {
+            value string
+        },
+        Number {
+            value int
+        },
+    }
+
+    fun double(n Element:Number) int {
+        n.value * 2
+    }
+
+    fun number(value int) Element {
+        Element:Number { value: value }
+    }
+
+    val elements = [Element:Empty, Element:Text { value: "lorem" }, number(4)];
+
+    var n Element:Number = elements[2];
+    n.value += 1;
+
+    if elements[0] == Element:Empty and elements[1] != elements[0] {
+        double(n) + double(number(10))
+    } else {
+        0
+    }
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(30));
+
+    assert_eq!(
+        eval("enum E { A, B { x int } } val e = E:B { x: 1 }; e"),
+        ExecutionResult::Enum {
+            variant: "B".to_string(),
+            fields: HashMap::from([("x".to_string(), ExecutionResult::Int(1))]),
+        }
+    );
+}
+
+#[test]
+fn test42() {
+    let source = r#"
+    enum Element {
+        Empty,
+        Text { value string }
+    }
+
+    fun text(e Element:Text) string {
+        e.value
+    }
+
+    val e = Element:Empty;
+    text(e)
+    "#;
+
+    let error = eval_with_vm(source, &mut Vm::new()).unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::InvalidCast);
+    assert_eq!(error.line, 12);
+
+    for (source, error) in [
+        ("enum E { A } E:B", "Enum E has no variant called B"),
+        (
+            "enum E { A, B { x int } } E:B { x: 'c' }",
+            "Field x should have value of type Int, got value of type Char",
+        ),
+        ("enum E { A, B { x int } } E:B", "Field x is missing"),
+        (
+            "enum E { A, B { x int } } val e = E:B { x: 1 }; e.x",
+            "is not a struct, cannot access a field on it",
+        ),
+        (
+            "enum E { A } enum F { A } val e F:A = E:A;",
+            "to type EnumVariant { name: \"F\", variant: \"A\" }",
+        ),
+        (
+            "enum E { A } struct E { x int }",
+            "A type with the name E already exists",
+        ),
+        (
+            "fun f() { enum E { A } }",
+            "Unrecognized type: LeftParenCurly",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test43() {
+    let source = r#"
+    struct Person {
+        name string,
+        age int
+    }
+
+    enum Element {
+        Empty,
+        Text { value string },
+        Number { value int }
+    }
+
+    fun describe(e Element) int {
+        match e {
+            Element:Empty -> 0,
+            Element::Number { value: 0 } -> 1,
+            Element:Number { value } -> value,
+            Element:Text { ... } -> 100,
+        }
+    }
+
+    fun person_age(p Person) int {
+        match p {
+            Person { name: "Kuon", age } -> age * 2,
+            Person { age, ... } -> age,
+        }
+    }
+
+    fun sum(numbers array[int]) int {
+        match numbers {
+            [] -> 0,
+            [only] -> only,
+            [first, ..., last] -> first + last,
+        }
+    }
+
+    fun same(t <int, string, int>) bool {
+        match t {
+            <a, b, a> -> true,
+            else -> false,
+        }
+    }
+
+    val elements = describe(Element:Empty) + describe(Element:Number { value: 0 })
+        + describe(Element:Number { value: 7 }) + describe(Element:Text { value: "a" });
+    val people = person_age(Person { name: "Kuon", age: 10 })
+        + person_age(Person { name: "Someone", age: 30 });
+    val sums = sum([1, 2, 3, 4]) + sum([5]) + sum([2, 9]);
+
+    if same(<1, "x", 1>) and not same(<1, "x", 2>) {
+        elements + people + sums
+    } else {
+        -1
+    }
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(108 + 50 + 21));
+
+    assert_eq!(
+        eval("val t = <1, <2, 3>>; match t { <0, <z, w>> -> z, <x, <w, y>> -> x + y }"),
+        ExecutionResult::Int(4)
+    );
+}
+
+#[test]
+fn test44() {
+    for (source, error) in [
+        (
+            "enum E { A, B } val e = E:A; match e { E:A -> 1 }",
+            "Match is not exhaustive, {\"B\"} aren't covered",
+        ),
+        (
+            "enum E { A, B { x int } } val e = E:A; match e { E:A -> 1, E:B { x: 1 } -> 2 }",
+            "Match is not exhaustive, {\"B\"} aren't covered",
+        ),
+        (
+            "match 1 { 1 -> 1, 2 -> 2 }",
+            "Match is not exhaustive, add an else arm",
+        ),
+        (
+            "match 1 { 'c' -> 1, else -> 2 }",
+            "Pattern Char('c') cannot match a value of type Int",
+        ),
+        (
+            "match 1 { 1 -> 1, else -> 'c' }",
+            "All arms of a match must have the same type, expected Int but got Char",
+        ),
+        (
+            "match <1, 'c'> { <a, a> -> 1, else -> 2 }",
+            "Variable a is bound to values of different types",
+        ),
+        (
+            "struct P { x int, y int } val p = P { x: 1, y: 2 }; match p { P { x } -> x }",
+            "Pattern is missing some fields, use ... to ignore the rest",
+        ),
+        (
+            "val x = 1; match 2 { x -> x }",
+            "Variable with name x already exists",
+        ),
+        ("match 1 { else -> 1, 2 -> 2 }", "else must be the last arm"),
+    ] {
+        assert_error(source, error);
+    }
+
+    assert_eq!(
+        eval("match true { true -> 1, false -> 2 }"),
+        ExecutionResult::Int(1)
+    );
+}
+
+#[test]
+fn test45() {
+    let source = r#"
+    struct Person {
+        name string,
+        age nullable int
+    }
+
+    fun age_or(p Person, default int) int {
+        if p.age? {
+            p.age!
+        } else {
+            default
+        }
+    }
+
+    var a nullable int = null;
+    val was_null = not a?;
+    a = 5;
+
+    var total = 0;
+    if a? {
+        total += a!;
+    }
+
+    val ages = map { "kuon": 17 };
+    val kuon = Person { name: "Kuon", age: ages["kuon"] };
+    val someone = Person { name: "Someone", age: null };
+
+    val big = if total > 3 { total };
+
+    if was_null and a != null and big? {
+        total + age_or(kuon, 0) + age_or(someone, 100) + big!
+    } else {
+        0
+    }
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(5 + 17 + 100 + 5));
+
+    let error = eval_with_vm(
+        "\nval m = map { 1: 'a' };\nval c = m[2]!;\n",
+        &mut Vm::new(),
+    )
+    .unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::NullUnwrap);
+    assert_eq!(error.line, 3);
+}
+
+#[test]
+fn test46() {
+    for (source, error) in [
+        ("val a int = null;", "to type Int"),
+        ("val a nullable int = 1; val b int = a;", "to type Int"),
+        (
+            "val a = 1; a?",
+            "Postfix operator ? can only work on nullable values, got value of type Int",
+        ),
+        (
+            "val a = 1; a!",
+            "Postfix operator ! can only work on nullable values, got value of type Int",
+        ),
+        (
+            "fun f(a int) int { a } f(null)",
+            "Expected value of type Int, got value of type Null instead",
+        ),
+        (
+            "struct P { x int } P { x: null }",
+            "Field x should have value of type Int, got value of type Null",
+        ),
+        (
+            "val a nullable int = 1; if a? { a = null; }",
+            "Cannot reassign constant variable a",
+        ),
+        (
+            "val a nullable int = 1; val b = if a? { 1 }; b + 1",
+            "Operator Plus only works on numbers",
+        ),
+        (
+            "var a nullable int = 1; if a? { a + 1 }",
+            "Operator Plus only works on numbers",
+        ),
+        (
+            "fun clear(var x nullable int) null { x = null; } var a nullable int = 1; if a? { clear(a); a + 1 }",
+            "Operator Plus only works on numbers",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test47() {
+    let source = r#"
+    struct Point {
+        x int,
+        y int
+    }
+
+    enum Shape {
+        Circle { radius float },
+        Square { side int }
+    }
+
+    fun describe(value any) string {
+        value as string
+    }
+
+    val half = 7 as float / 2.0;
+    val truncated = 3.9 as int;
+    val code = 'a' as int;
+    val letter = (code + 1) as char;
+
+    val things = [5 as any, "five" as any, Point { x: 1, y: 2 } as any];
+    val point = things[2] as Point;
+
+    val shape any = Shape:Square { side: 4 };
+    val square = shape as Shape:Square;
+
+    val text = 12 as string;
+    val same = describe("text") == "text";
+
+    if half == 3.5 and letter == 'b' and text == "12" and same {
+        truncated + point.y + square.side + (things[0] as int)
+    } else {
+        0
+    }
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(3 + 2 + 4 + 5));
+
+    assert_eq!(
+        eval("val t = <1, 'c', [true, false]>; t as string"),
+        ExecutionResult::String("<1, c, [true, false]>".to_string())
+    );
+}
+
+#[test]
+fn test48() {
+    let source = r#"
+    val a any = 5;
+    val b = a as string;
+    "#;
+
+    let error = eval_with_vm(source, &mut Vm::new()).unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::InvalidCast);
+    assert_eq!(error.line, 3);
+
+    let error = eval_with_vm(
+        "enum E { A, B } val e any = E:A; e as E:B",
+        &mut Vm::new(),
+    )
+    .unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::InvalidCast);
+
+    for (source, error) in [
+        (
+            "val a = \"1\" as int;",
+            "Cannot cast value of type String to type Int, there's no conversion function for it",
+        ),
+        (
+            "val a = true as int;",
+            "Cannot cast value of type Bool to type Int, there's no conversion function for it",
+        ),
+        (
+            "struct P { x int } val a = P { x: 1 } as int;",
+            "Cannot cast value of type UserDefined(\"P\") to type Int, there's no conversion function for it",
+        ),
+        (
+            "val a = 1 as bool;",
+            "Cannot cast value of type Int to type Bool, there's no conversion function for it",
+        ),
+        ("val a any = 1; val b int = a;", "to type Int"),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test49() {
+    let source = r#"
+    struct Person {
+        name string,
+        age int
+    }
+
+    enum Level {
+        Low,
+        High
+    }
+
+    fun (val name string) as Person {
+        Person { name: name, age: if name == "Kuon" { 40 } else { 10 } }
+    }
+
+    fun (val p Person) as Level {
+        if p.age > 30 {
+            Level:High
+        } else {
+            Level:Low
+        }
+    }
+
+    fun (val age nullable int) as Person {
+        Person { name: "Unknown", age: if age? { age } else { 0 } }
+    }
+
+    val kuon = "Kuon" as Person;
+    val level = kuon as Level;
+    val unknown = 5 as nullable int as Person;
+
+    if level == Level:High and unknown.name == "Unknown" {
+        kuon.age + unknown.age
+    } else {
+        0
+    }
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(45));
+}
+
+#[test]
+fn test50() {
+    for (source, error) in [
+        (
+            "struct P { x int } val p = 1 as P;",
+            "Cannot cast value of type Int to type UserDefined(\"P\"), there's no conversion function for it",
+        ),
+        (
+            "fun (val i int) as float { 1.0 }",
+            "Values of type Int can already be cast to type Float",
+        ),
+        (
+            "fun (val i int) as string { \"\" }",
+            "Values of type Int can already be cast to type String",
+        ),
+        ("struct P { x int } fun (val s string) as P { P { x: 1 } } \
+         fun (val s string) as P { P { x: 2 } }", "Conversion from type String to type UserDefined(\"P\") already exists"),
+        ("struct P { x int } fun (val s string) as P { P { x: 1 } } \
+         fun (val s nullable string) as P { P { x: 2 } } val p = \"a\" as P;", "Cast of value of type String to type UserDefined(\"P\") is ambiguous, it could use conversion functions from types [String, Nullable(String)]"),
+        (
+            "fun foo(val s string) as int { 1 }",
+            "The function name goes after the pre-parameter",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test51() {
+    let source = r#"
+    type Flag = bool;
+    type Point = struct { x int, y int };
+    type Points = array[Point];
+    type Op = fun (int, int) int;
+    type MaybeInt = nullable int;
+
+    enum Shape {
+        Dot,
+        Square { side int }
+    }
+
+    type Figure = Shape;
+
+    fun (p Point):sum() int {
+        p.x + p.y
+    }
+
+    fun apply(op Op, a int, b int) int {
+        op(a, b)
+    }
+
+    val done Flag = true;
+    val points Points = [Point { x: 1, y: 2 }, mkstruct { x: 3, y: 4 }];
+    val add Op = fun (a int, b int) int { a + b };
+    val missing MaybeInt = null;
+    val figure Figure = Figure:Square { side: 5 };
+
+    val side = match figure {
+        Figure:Square { side } -> side,
+        Shape:Dot -> 0
+    };
+
+    if done and not missing? {
+        apply(add, points[0]:sum(), points[1]:sum()) + side
+    } else {
+        0
+    }
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(15));
+}
+
+#[test]
+fn test52() {
+    for (source, error) in [
+        ("type A = A;", "Type alias A refers to itself"),
+        (
+            "type A = nullable B; type B = array[A];",
+            "Type alias B refers to itself",
+        ),
+        (
+            "type P = struct { next nullable P };",
+            "Type alias P refers to itself",
+        ),
+        (
+            "type A = int; type A = bool;",
+            "A type with the name A already exists",
+        ),
+        (
+            "struct P { x int } type P = int;",
+            "A type with the name P already exists",
+        ),
+        (
+            "type P = int; struct P { x int }",
+            "A type with the name P already exists",
+        ),
+        ("type Flag = bool; val f Flag = 1;", "to type Bool"),
+        (
+            "fun foo() null { type A = int; }",
+            "Type aliases are only allowed at the top level",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test53() {
+    let source = r#"
+    type Transform = fun (int) int;
+
+    fun twice(f fun (int) int, n int) int {
+        f(f(n))
+    }
+
+    fun compose(f Transform, g fun (int) int) fun (int) int {
+        val composed = fun (n int) int { g(f(n)) };
+        composed
+    }
+
+    fun applyAll(fs array[fun (int) int], n int) int {
+        var result = n;
+        for f in fs {
+            result = f(result);
+        }
+        result
+    }
+
+    fun modify(f fun (var int) null, var n int) null {
+        f(n);
+    }
+
+    val increment = fun (n int) int { n + 1 };
+    val double = fun (n int) int { n * 2 };
+    val bump = fun (var n int) null { n += 100; };
+
+    var counter = 0;
+    modify(bump, counter);
+
+    twice(increment, 1) + compose(increment, double)(2) + applyAll([increment, double], 3) + counter
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(117));
+}
+
+#[test]
+fn test54() {
+    for (source, error) in [
+        ("fun twice(f fun (int) int, n int) int { f(f(n)) } \
+         twice(fun (n string) int { 1 }, 1);", "Expected value of type Function { param_types: [Int], var_params: [false], return_type: Int }, got value of type Function { param_types: [String], var_params: [false], return_type: Int } instead"),
+        ("fun twice(f fun (int) int, n int) int { f(f(n)) } \
+         twice(fun (n int) string { \"\" }, 1);", "Expected value of type Function { param_types: [Int], var_params: [false], return_type: Int }, got value of type Function { param_types: [Int], var_params: [false], return_type: String } instead"),
+        ("fun twice(f fun (int) int, n int) int { f(f(n)) } \
+         twice(fun (var n int) int { n }, 1);", "Expected value of type Function { param_types: [Int], var_params: [false], return_type: Int }, got value of type Function { param_types: [Int], var_params: [true], return_type: Int } instead"),
+        (
+            "fun call(f fun (int, int) int) int { f(1) }",
+            "Expected 2 arguments, 1 provided",
+        ),
+        (
+            "val f fun (int) int = fun (a int, b int) int { a };",
+            "to type Function { param_types: [Int], var_params: [false], return_type: Int }",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test55() {
+    let source = r#"
+    fun defaultAge() int {
+        20
+    }
+
+    struct Person {
+        name string = "Kuon",
+        age int = defaultAge(),
+        nickname nullable string = null,
+        friends int
+    }
+
+    type Character = Person;
+
+    fun makePerson(friends int) Person {
+        Person { friends: friends }
+    }
+
+    val kuon = makePerson(3);
+    val eruruu = Character { name: "Eruruu", friends: 5, nickname: "Eru" };
+
+    if kuon.name == "Kuon" and not kuon.nickname? and eruruu.nickname! == "Eru" {
+        kuon.age + kuon.friends + eruruu.age + eruruu.friends
+    } else {
+        0
+    }
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(48));
+}
+
+#[test]
+fn test56() {
+    for (source, error) in [
+        (
+            "struct P { x int = \"a\" }",
+            "Default value of field x should have type Int, got value of type String",
+        ),
+        ("val a = 1; struct P { x int = a }", "Unknown variable: a"),
+        (
+            "struct P { x int = 1, y int } val p = P { x: 2 };",
+            "Field y is missing",
+        ),
+        (
+            "struct P { x int = 1 } val p = P { y: 2 };",
+            "Field y does not exist",
+        ),
+        (
+            "enum E { A { x int = 1 } }",
+            "Only named structs can have default values",
+        ),
+        (
+            "val p struct { x int = 1 } = mkstruct { x: 1 };",
+            "Only named structs can have default values",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test57() {
+    let source = r#"
+    fun digits(a int, b int = 2, c int = 3) int {
+        a * 100 + b * 10 + c
+    }
+
+    fun title(name string, honorific string = "Princess") string {
+        honorific
+    }
+
+    fun sum(start int, ...numbers int) int {
+        var total = start;
+        for n in numbers {
+            total += n;
+        }
+        total
+    }
+
+    fun area(width int, height int = width) int {
+        width * height
+    }
+
+    fun (val n int):clamp(low int = 0, high int = 10) int {
+        if n < low {
+            low
+        } else if n > high {
+            high
+        } else {
+            n
+        }
+    }
+
+    val a = digits(1);
+    val b = digits(1, c: 5);
+    val c = digits(c: 7, a: 4);
+
+    if title("Kuon") == "Princess" and title("Eruruu", honorific: "Healer") == "Healer" {
+        a + b + c + sum(1) + sum(1, 2, 3) + area(3) + area(2, height: 5) + 20:clamp() + (-5):clamp(low: -2)
+    } else {
+        0
+    }
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(709));
+}
+
+#[test]
+fn test58() {
+    for (source, error) in [
+        (
+            "fun f(a int = \"x\") int { 1 }",
+            "Default value of parameter a should have type Int, got value of type String",
+        ),
+        (
+            "fun f(a int = 1, b int) int { 1 }",
+            "Parameter b needs a default value since the parameters before it have one",
+        ),
+        (
+            "fun f(...a int, b int) int { 1 }",
+            "The rest parameter has to be the last one",
+        ),
+        (
+            "fun f(var a int = 1) int { 1 }",
+            "A var parameter can't have a default value or be a rest parameter",
+        ),
+        (
+            "fun f(a int) int { a } val x = f(b: 1);",
+            "Function f has no parameter called b",
+        ),
+        (
+            "fun f(a int) int { a } val x = f(1, a: 1);",
+            "Parameter a is given more than once",
+        ),
+        (
+            "fun f(a int, b int) int { a } val x = f(a: 1, 2);",
+            "Positional arguments can't come after keyword arguments",
+        ),
+        (
+            "fun f(a int, b int = 1) int { a } val x = f(b: 2);",
+            "Missing argument for parameter a",
+        ),
+        (
+            "fun f(...a int) int { 1 } val x = f(1, \"b\");",
+            "Expected value of type Int, got value of type String instead",
+        ),
+        (
+            "fun f(a int) int { a } val g = f; val x = g(a: 1);",
+            "Keyword arguments can only be used when calling a function by its name",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test59() {
+    let source = r#"
+    struct Person {
+        name string,
+        age int
+    }
+
+    type Character = Person;
+
+    enum Shape {
+        Square { side int },
+        Rectangle { width int, height int }
+    }
+
+    fun Person#new(name string, age int = 20) Person {
+        Person { name: name, age: age }
+    }
+
+    fun Character#age(p Person) int {
+        p.age
+    }
+
+    fun Shape#square(side int) Shape {
+        Shape:Square { side: side }
+    }
+
+    fun Shape#factorial(n int) int {
+        if n <= 1 {
+            1
+        } else {
+            n * Shape#factorial(n - 1)
+        }
+    }
+
+    fun new(n int) int {
+        n
+    }
+
+    val kuon = Person#new("Kuon");
+    val eruruu = Character#new(age: 19, name: "Eruruu");
+    val make = Person#new;
+
+    val area = match Shape#square(3) {
+        Shape:Square { side } -> side * side,
+        Shape:Rectangle { width, height } -> width * height
+    };
+
+    Person#age(kuon) + Person#age(eruruu) + make("Aruruu", 10).age + area + Shape#factorial(4) + new(1)
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(83));
+}
+
+#[test]
+fn test60() {
+    for (source, error) in [
+        ("fun Person#new() int { 1 }", "Type Person does not exist"),
+        (
+            "struct P { x int } val a = P#new();",
+            "Type function P#new does not exist",
+        ),
+        (
+            "struct P { x int } fun P#new() int { 1 } fun P#new() int { 2 }",
+            "Function P#new already exists",
+        ),
+        (
+            "struct P { x int } fun P#new() int { 1 } val a = Q#new();",
+            "Type Q does not exist",
+        ),
+        (
+            "struct P { x int } fun P#new(a int) int { a } val a = P#new(\"a\");",
+            "Expected value of type Int, got value of type String instead",
+        ),
+        (
+            "struct P { x int } fun (val p P):P#new() int { 1 }",
+            "Expected (, got Hash",
+        ),
+        (
+            "struct P { x int } val f = fun P#new() int { 1 };",
+            "Only top level functions can have a name",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test61() {
+    let source = r#"
+    interface Number {
+        fun (val n this):negate() this
+        fun (val n this):toInt() int
+    }
+
+    struct Fraction {
+        top int,
+        bottom int
+    }
+
+    fun (val f Fraction):negate() Fraction {
+        Fraction { top: -f.top, bottom: f.bottom }
+    }
+
+    fun (val f Fraction):toInt() int {
+        f.top - f.bottom
+    }
+
+    fun (val n int):negate() int {
+        -n
+    }
+
+    fun (val n int):toInt() int {
+        n
+    }
+
+    fun negatedInt(n Number) int {
+        n:negate():toInt()
+    }
+
+    fun (val n Number):twice() int {
+        n:toInt() * 2
+    }
+
+    val f = Fraction { top: 9, bottom: 3 };
+    val g Number = 5;
+    val back = g:negate() as int;
+
+    100 + negatedInt(f) + negatedInt(7) + g:twice() + back + (f as Number):twice()
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(98));
+}
+
+#[test]
+fn test62() {
+    for (source, error) in [
+        (
+            "interface I { fun (val n this):f() int } val x I = 1;",
+            "to type Interface(\"I\")",
+        ),
+        (
+            "interface I { fun (val n int):f() int }",
+            "The pre-parameter of an interface function has to be val and have the type this",
+        ),
+        (
+            "interface I { fun (var n this):f() int }",
+            "The pre-parameter of an interface function has to be val and have the type this",
+        ),
+        (
+            "interface I { fun (val n this):f(a array[this]) int }",
+            "Function f of interface I can only use this as the type of a parameter or its return type, not inside of another type",
+        ),
+        (
+            "interface I { fun (val n this):f(var a this) int }",
+            "A parameter of function f of interface I with the type this cannot be var",
+        ),
+        (
+            "interface I { fun (val n this):f() int } fun (val n int):f() string { \"a\" } val x I = 1;",
+            "to type Interface(\"I\")",
+        ),
+        (
+            "interface I { fun (val n this):f() int } fun (val n I):g() int { n:h() }",
+            "Cannot find function Some(Interface(\"I\")):h",
+        ),
+        (
+            "struct I { a int } interface I { fun (val n this):f() int }",
+            "A type with the name I already exists",
+        ),
+        (
+            "fun f() int { interface I { fun (val n this):f() int } 1 }",
+            "Interface definitions are only allowed at the top level",
+        ),
+        (
+            "interface I { fun (val n this):f() int } fun (var n int):f() int { n } val x I = 1;",
+            "to type Interface(\"I\")",
+        ),
+        (
+            "interface Neg { fun (val n this):negate() this } struct P { x int } fun (var p P):negate() P { p } val x Neg = P { x: 1 };",
+            "to type Interface(\"Neg\")",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test63() {
+    let source = r#"
+    var xs = [1, 2];
+    val ys = xs;
+    xs = [3];
+    ys
+    "#;
+
+    assert_eq!(
+        eval(source),
+        ExecutionResult::Array(vec![
+            ExecutionResult::Int(1),
+            ExecutionResult::Int(2)
+        ])
+    );
+
+    let source = r#"
+    fun replace(var arr array[int]) null {
+        arr = [5];
+    }
+
+    var xs = [1, 2];
+    val ys = xs;
+    replace(xs);
+
+    var s = "a";
+    val setS = fun() null { s = "b"; };
+    setS();
+
+    xs[0] + ys[1] + (if s == "b" { 100 } else { 0 })
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(107));
+}
+
+#[test]
+fn test64() {
+    let mut engine = Engine::new();
+
+    // nothing is kept from code which failed to run either
+    assert!(matches!(
+        engine.eval("val x = [1][5];"),
+        Err(KuonError::Runtime(_))
+    ));
+    assert!(matches!(engine.eval("x"), Err(KuonError::Analyzer(_))));
+    assert_eq!(
+        engine.eval("val x = 3; x").unwrap(),
+        ExecutionResult::Int(3)
+    );
+
+    // running a chunk without the code before it which set the global
+    let mut engine = Engine::new();
+    engine.compile("val y = [1][5];").unwrap();
+    let chunk = engine.compile("y").unwrap();
+
+    match engine.run(&chunk) {
+        Err(KuonError::Runtime(error)) => {
+            assert_eq!(error.kind, RuntimeErrorKind::UndefinedVariable)
+        }
+        result => panic!("Expected a runtime error, got {:?}", result),
+    }
+}
+
+#[test]
+fn test65() {
+    let source = r#"
+    struct Bag {
+        xs array[int]
+    }
+
+    var b = Bag { xs: [1, 2, 3] };
+    b.xs[1] = 20;
+    b.xs[0] += 4;
+
+    b.xs[0] + b.xs[1] - b.xs[2] + -b.xs[2]
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(19));
+}
+
+#[test]
+fn test66() {
+    let source = r#"
+    struct Stack {
+        items array[int] = []
+    }
+
+    fun total(xs array[int], extra array[int] = []) int {
+        var sum = 0;
+        for x in xs {
+            sum += x;
+        }
+        for x in extra {
+            sum += x;
+        }
+        sum
+    }
+
+    var xs array[int] = [];
+    val s = Stack {};
+    val t = Stack { items: [] };
+    val n nullable array[int] = [];
+
+    xs = [];
+    xs = [1, 2];
+
+    total(xs) + total([]) + total(xs, extra: [3]) + total(s.items) + total(t.items) + total(n!)
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(9));
+
+    for (source, error) in [
+        (
+            "val xs = [];",
+            "Cannot determine the type of an empty array",
+        ),
+        (
+            "val xs any = [];",
+            "Cannot determine the type of an empty array",
+        ),
+        (
+            "val xs int = [];",
+            "Cannot determine the type of an empty array",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test67() {
+    let source = r#"
+    struct Registry {
+        scores map[string, int] = map {}
+    }
+
+    fun has(m map[string, int], key string) int {
+        if m[key]? { 1 } else { 0 }
+    }
+
+    var m map[string, int] = map {};
+    m["a"] = 1;
+    val r = Registry {};
+
+    has(m, "a") * 10 + has(map {}, "a") + has(r.scores, "a")
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(10));
+
+    for (source, error) in [
+        (
+            "val m = map {};",
+            "Cannot determine the type of an empty map",
+        ),
+        (
+            "val m map[float, int] = map {};",
+            "Values of type Float cannot be used as map keys",
+        ),
+        (
+            "val m array[int] = map {};",
+            "Cannot determine the type of an empty map",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test68() {
+    let source = r#"
+    fun (val n int):inc(x int) int {
+        n + x
+    }
+
+    fun double(x int) int {
+        x * 2
+    }
+
+    fun foo(a int = 0, n int = 0) int {
+        a * 100 + n
+    }
+
+    val n = 1;
+
+    foo(n: double(5)) + foo((n:inc(2))) + foo((n:inc(3)), n: 4)
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(714));
+
+    for (source, error) in [
+        (
+            "fun (val n int):inc(x int) int { n + x } fun foo(a int = 0, n int = 0) int { a } val n = 1; foo(n: inc(5));",
+            "Unknown variable: inc",
+        ),
+        (
+            "fun (val n int):inc(x int) int { n + x } fun foo(a int) int { a } val n = 1; foo(n:inc(5));",
+            "Function foo has no parameter called n (to pass a value function call on n as an argument, put it in parentheses)",
+        ),
+        (
+            "fun (val n int):inc(x int) int { n + x } fun foo(a int, b int) int { a } val n = 1; foo(n:inc(5), 2);",
+            "Positional arguments can't come after keyword arguments",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test69() {
+    let mut engine = Engine::new();
+
+    let source = r#"
+    fun g(a int, b int = 2) int {
+        a * 10 + b
+    }
+
+    fun sum(start int, ...numbers int) int {
+        var total = start;
+        for n in numbers {
+            total += n;
+        }
+        total
+    }
+
+    fun (val n int):scale(by int = 3) int {
+        n * by
+    }
+    "#;
+    engine.eval(source).unwrap();
+
+    assert_eq!(
+        engine
+            .call_function("g", vec![ExecutionResult::Int(1)])
+            .unwrap(),
+        ExecutionResult::Int(12)
+    );
+    assert_eq!(
+        engine
+            .call_function(
+                "g",
+                vec![ExecutionResult::Int(1), ExecutionResult::Int(5)]
+            )
+            .unwrap(),
+        ExecutionResult::Int(15)
+    );
+    assert_eq!(
+        engine
+            .call_function("sum", vec![ExecutionResult::Int(1)])
+            .unwrap(),
+        ExecutionResult::Int(1)
+    );
+    assert_eq!(
+        engine
+            .call_function(
+                "sum",
+                vec![
+                    ExecutionResult::Int(1),
+                    ExecutionResult::Int(2),
+                    ExecutionResult::Int(3)
+                ]
+            )
+            .unwrap(),
+        ExecutionResult::Int(6)
+    );
+    assert_eq!(
+        engine
+            .call_value_function("scale", ExecutionResult::Int(2), vec![])
+            .unwrap(),
+        ExecutionResult::Int(6)
+    );
+
+    for arguments in [
+        vec![],
+        vec![
+            ExecutionResult::Int(1),
+            ExecutionResult::Int(2),
+            ExecutionResult::Int(3),
+        ],
+    ] {
+        assert!(matches!(
+            engine.call_function("g", arguments),
+            Err(KuonError::InvalidArguments(_))
+        ));
+    }
+    assert!(matches!(
+        engine.call_function(
+            "sum",
+            vec![ExecutionResult::Int(1), ExecutionResult::Bool(true)]
+        ),
+        Err(KuonError::InvalidArguments(_))
+    ));
+}
+
+#[test]
+fn test70() {
+    let source = r#"
+    fun clear(var x nullable int) null {
+        x = null;
+    }
+
+    var a nullable int = 1;
+    var cleared = false;
+
+    if a? {
+        clear(a);
+        a = null;
+        cleared = not a?;
+    }
+
+    val b nullable int = 2;
+    val c = if b? { b + 1 } else { 0 };
+
+    if cleared { c } else { 0 }
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(3));
+}
+
+#[test]
+fn test71() {
+    let mut engine = Engine::new();
+
+    engine
+        .register_function("wrong", "fun () int", |_| {
+            Ok(ExecutionResult::String("not an int".to_string()))
+        })
+        .unwrap();
+    engine
+        .register_function("numbers", "fun () nullable array[int]", |_| {
+            Ok(ExecutionResult::Array(vec![ExecutionResult::Int(4)]))
+        })
+        .unwrap();
+    engine
+        .register_function("mixed", "fun () array[int]", |_| {
+            Ok(ExecutionResult::Array(vec![
+                ExecutionResult::Int(4),
+                ExecutionResult::Float(1.5),
+            ]))
+        })
+        .unwrap();
+
+    assert_eq!(
+        engine.eval("numbers()![0]").unwrap(),
+        ExecutionResult::Int(4)
+    );
+
+    for source in ["wrong() + 1", "mixed()"] {
+        match engine.eval(source) {
+            Err(KuonError::Runtime(error)) => {
+                assert_eq!(error.kind, RuntimeErrorKind::NativeError)
+            }
+            result => panic!("Expected a runtime error, got {:?}", result),
+        }
+    }
+}
+
+#[test]
+fn test72() {
+    let mut engine = Engine::new();
+
+    let bad_map = ExecutionResult::Map(vec![(
+        ExecutionResult::Float(1.0),
+        ExecutionResult::Int(1),
+    )]);
+
+    let returned = bad_map.clone();
+    engine
+        .register_function(
+            "badMap",
+            "fun () any",
+            move |_| Ok(returned.clone()),
+        )
+        .unwrap();
+    engine.eval("fun f(x any) int { 1 }").unwrap();
+
+    for result in [
+        engine.eval("badMap()"),
+        engine.call_function("f", vec![bad_map]),
+    ] {
+        match result {
+            Err(KuonError::Runtime(error)) => {
+                assert_eq!(error.kind, RuntimeErrorKind::TypeMismatch)
+            }
+            result => panic!("Expected a runtime error, got {:?}", result),
+        }
+    }
+}
+
+#[test]
+fn test73() {
+    for source in [
+        "9223372036854775807 + 1",
+        "-9223372036854775807 - 2",
+        "9223372036854775807 * 2",
+        "val a = -9223372036854775807 - 1; -a",
+        "val a = -9223372036854775807 - 1; a / -1",
+        "var a = 9223372036854775807; a += 1; a",
+    ] {
+        let error = eval_with_vm(source, &mut Vm::new()).unwrap_err();
+        assert_eq!(error.kind, RuntimeErrorKind::IntegerOverflow, "{}", source);
+    }
+
+    assert_eq!(
+        eval("-9223372036854775807 - 1 + 9223372036854775807"),
+        ExecutionResult::Int(-1)
+    );
+}
+
+#[test]
+fn test74() {
+    let source = r#"
+    struct Line {
+        points array[<int, int>]
+    }
+
+    enum Shape {
+        Empty,
+        Path { line Line }
+    }
+
+    fun span(s Shape) int {
+        match s {
+            Shape:Empty -> 0,
+            Shape:Path { line: Line { points: [] } } -> -1,
+            Shape:Path { line: Line { points: [<x, y>, ..., <x, w>] } } -> w - y,
+            Shape:Path { line: Line { points: [<a, b>, ..., <c, d>] } } -> c - a + d - b,
+            else -> 1000,
+        }
+    }
+
+    val flat = Shape:Path { line: Line { points: [<1, 2>, <5, 5>, <1, 9>] } };
+    val diagonal = Shape:Path { line: Line { points: [<0, 0>, <3, 4>] } };
+    val empty = Shape:Path { line: Line { points: [] } };
+
+    span(flat) * 100 + span(diagonal) + span(empty) + span(Shape:Empty)
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(706));
+}
+
+#[test]
+fn test75() {
+    let source = r#"
+    val a = 1;
+    val b = 2;
+    val t = <(a < b), (a == b), (a < b and b > 0), a + b>;
+
+    if t[0] and not t[1] and t[2] { t[3] } else { 0 }
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(3));
+}
+
+#[test]
+fn test76() {
+    // var arguments and closures are allocated without any literal in the
+    // loop, so they have to be able to trigger a collection by themselves
+    let source = r#"
+    fun inc(var n int) null {
+        n += 1;
+    }
+
+    var x = 0;
+    var i = 0;
+    while i < 5000 {
+        inc(x);
+        i += 1;
+    }
+    x
+    "#;
+
+    let mut vm = Vm::new();
+    vm.heap_mut().set_gc_threshold(1024);
+
+    assert_eq!(
+        eval_with_vm(source, &mut vm),
+        Ok(ExecutionResult::Int(5000))
+    );
+
+    let stats = vm.heap().stats();
+    assert!(stats.collections > 0);
+    assert!(stats.live_objects < 100);
+
+    let source = r#"
+    fun sum(count int) int {
+        var total = 0;
+        var i = 0;
+        while i < count {
+            val n = i;
+            val get = fun() int { n };
+            total += get();
+            i += 1;
+        }
+        total
+    }
+
+    sum(5000)
+    "#;
+
+    let mut vm = Vm::new();
+    vm.heap_mut().set_gc_threshold(1024);
+
+    assert_eq!(
+        eval_with_vm(source, &mut vm),
+        Ok(ExecutionResult::Int(12497500))
+    );
+
+    let stats = vm.heap().stats();
+    assert!(stats.collections > 0);
+    assert!(stats.live_objects < 100);
+}
+
+#[test]
+fn test77() {
+    // indexing and storing into a big array works on the array in place, so
+    // going over every element stays linear
+    let elements: Vec<String> = (0..20000).map(|i| i.to_string()).collect();
+    let source = format!(
+        r#"
+        var xs = [{}];
+        val ys = xs;
+
+        var i = 0;
+        while i < 20000 {{
+            xs[i] = xs[i] * 2;
+            i += 1;
+        }}
+
+        var total = 0;
+        for y in ys {{
+            total += y;
+        }}
+        total
+        "#,
+        elements.join(", ")
+    );
+
+    assert_eq!(eval(&source), ExecutionResult::Int(399980000));
+}
+
+#[test]
+fn test78() {
+    let source = r#"
+    fun first(m map[string, nullable int]) nullable int {
+        m["a"]!
+    }
+
+    var m map[string, any] = map {
+        "a": 1,
+        "b": "...",
+        "c": false,
+    };
+    m["d"] = 2.5;
+
+    val nested map[int, map[string, any]] = map {
+        1: map { "x": 1, "y": "z" }
+    };
+
+    <(m["b"]! as string), (m["d"]! as float), first(map { "a": 1, "b": null }),
+        (nested[1]!["y"]! as string)>
+    "#;
+
+    assert_eq!(
+        eval(source),
+        ExecutionResult::Tuple(vec![
+            ExecutionResult::String("...".to_string()),
+            ExecutionResult::Float(2.5),
+            ExecutionResult::Int(1),
+            ExecutionResult::String("z".to_string()),
+        ])
+    );
+
+    // reading and storing into a map updates it in place, so this doesn't
+    // copy the whole map every time
+    let source = r#"
+    var m = map { 0: 0 };
+    var i = 1;
+    while i < 20000 {
+        m[i] = i;
+        i += 1;
+    }
+
+    var total = 0;
+    i = 0;
+    while i < 20000 {
+        total += m[i]!;
+        i += 1;
+    }
+    total
+    "#;
+
+    assert_eq!(eval(source), ExecutionResult::Int(199990000));
+
+    for (source, error) in [
+        (
+            "val m map[string, int] = map { \"a\": 1, \"b\": false };",
+            "Expected map value of type Int, got value of type Bool instead",
+        ),
+        (
+            "val m map[string, int] = map { \"a\": 1, 2: 2 };",
+            "Expected map key of type String, got value of type Int instead",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test79() {
+    let source = r#"
+    fun count(xs array[nullable int]) int {
+        var total = 0;
+        for x in xs {
+            if x? {
+                total += 1;
+            }
+        }
+        total
+    }
+
+    val xs array[nullable int] = [1, null];
+    var ys array[any] = [1, "a"];
+    ys = [false, 2.5, null];
+    val zs array[array[nullable int]] = [[null], [1, 2, null]];
+
+    <count(xs), count([1, 2]), count(zs[1]), (ys[1] as float)>
+    "#;
+
+    assert_eq!(
+        eval(source),
+        ExecutionResult::Tuple(vec![
+            ExecutionResult::Int(1),
+            ExecutionResult::Int(2),
+            ExecutionResult::Int(2),
+            ExecutionResult::Float(2.5),
+        ])
+    );
+
+    for (source, error) in [
+        (
+            "val xs array[nullable int] = [1, \"a\"];",
+            "Expected array element of type Nullable(Int), got value of type \
+             String instead",
+        ),
+        (
+            "fun f(xs array[int]) int { 1 } f([1, null]);",
+            "Expected array element of type Int, got value of type Null \
+             instead",
+        ),
+    ] {
+        assert_error(source, error);
+    }
+}
+
+#[test]
+fn test80() {
+    // the index of a compound assignment is only evaluated once
+    let source = r#"
+    struct P { xs array[int] }
+
+    var calls = 0;
+    fun next() int {
+        calls += 1;
+        calls - 1
+    }
+
+    fun run() int {
+        var grid = [[1, 2], [3, 4]];
+        grid[next() - 2][next() - 2] += 10;
+        grid[0][1]
+    }
+
+    var xs = [1, 2, 3];
+    xs[next()] *= 5;
+
+    var p = P { xs: [1, 2, 3] };
+    p.xs[next()] -= 1;
+
+    var f = 9.0;
+    f /= 2;
+
+    val result = <calls, xs[0], p.xs[1], run(), calls, f>;
+    result
+    "#;
+
+    assert_eq!(
+        eval(source),
+        ExecutionResult::Tuple(vec![
+            ExecutionResult::Int(2),
+            ExecutionResult::Int(5),
+            ExecutionResult::Int(1),
+            ExecutionResult::Int(12),
+            ExecutionResult::Int(4),
+            ExecutionResult::Float(4.5),
+        ])
+    );
+}
+
+#[test]
+fn test81() {
+    // recursion which alternates between two lines doesn't collapse, so only
+    // the ends of the stack trace are kept
+    let source = r#"
+    fun bounce(up bool) int {
+        if up {
+            bounce(false)
+        } else {
+            bounce(true)
+        }
+    }
+
+    bounce(true)
+    "#;
+
+    let mut vm = Vm::new();
+    vm.set_max_frames(1000);
+
+    let error = eval_with_vm(source, &mut vm).unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::StackOverflow);
+    assert_eq!(error.stack_trace.len(), vm::MAX_STACK_TRACE_ENTRIES);
+    assert_eq!(error.omitted_frames, 1000 - vm::MAX_STACK_TRACE_ENTRIES);
+    assert_eq!(
+        error.stack_trace.last(),
+        Some(&StackTraceEntry {
+            function: None,
+            line: 10,
+            repeated: 1
+        })
+    );
+}
+
+#[test]
+fn test82() {
+    // the groups between two ... can be anywhere in the array, in order
+    let source = r#"
+    fun which(xs array[int]) int {
+        match xs {
+            [1, 2, ...] -> 1,
+            [..., 3, ...] -> 2,
+            [..., 4, 5] -> 3,
+            else -> 4,
+        }
+    }
+
+    fun pairs(xs array[int]) int {
+        match xs {
+            [first, ..., a, a, ..., b, b, ..., last] ->
+                first * 1000 + a * 100 + b * 10 + last,
+            [..., x, ...] -> x,
+            [] -> 0,
+        }
+    }
+
+    fun nested(xss array[array[int]]) int {
+        match xss {
+            [..., [..., 7, n, ...], ...] -> n,
+            else -> -1,
+        }
+    }
+
+    val r = <
+        which([1, 2, 3, 4, 5]), which([9, 3, 9]), which([0, 4, 5]),
+        which([3]), which([0, 1, 2]), which([]),
+        pairs([1, 2, 2, 7, 3, 3, 4]), pairs([1, 2, 2, 3, 3]), pairs([]),
+        nested([[1, 7], [2, 7, 8]]), nested([[7]])
+    >;
+    r
+    "#;
+
+    assert_eq!(
+        eval(source),
+        ExecutionResult::Tuple(vec![
+            ExecutionResult::Int(1),
+            ExecutionResult::Int(2),
+            ExecutionResult::Int(3),
+            ExecutionResult::Int(2),
+            ExecutionResult::Int(4),
+            ExecutionResult::Int(4),
+            ExecutionResult::Int(1234),
+            ExecutionResult::Int(1),
+            ExecutionResult::Int(0),
+            ExecutionResult::Int(8),
+            ExecutionResult::Int(-1),
+        ])
+    );
+
+    assert_error(
+        "match [1] { [..., ...] -> 1 }",
+        "An array pattern cannot have two ... in a row",
+    );
+}
+
+#[test]
+fn test83() {
+    // interface functions can take other values of the type implementing them
+    let source = r#"
+    interface SupportsArithmetic {
+        fun (val n this):negate() this
+        fun (val a this):add(b this) this
+        fun (val a this):multiply(b this) this
+    }
+
+    struct CustomNumber { value int }
+
+    fun (val n CustomNumber):negate() CustomNumber {
+        CustomNumber { value: -n.value }
+    }
+    fun (val a CustomNumber):add(b CustomNumber) CustomNumber {
+        CustomNumber { value: a.value + b.value }
+    }
+    fun (val a CustomNumber):multiply(b CustomNumber) CustomNumber {
+        CustomNumber { value: a.value * b.value }
+    }
+
+    fun (val n float):negate() float { -n }
+    fun (val a float):add(b float) float { a + b }
+    fun (val a float):multiply(b float) float { a * b }
+
+    fun square_minus(x SupportsArithmetic, y SupportsArithmetic) SupportsArithmetic {
+        x:multiply(x):add(b: (y:negate()))
+    }
+
+    val a = square_minus(CustomNumber { value: 3 }, CustomNumber { value: 2 });
+    val b = square_minus(1.5, 0.25);
+    val r = <(a as CustomNumber).value, b as float>;
+    r
+    "#;
+
+    assert_eq!(
+        eval(source),
+        ExecutionResult::Tuple(vec![
+            ExecutionResult::Int(7),
+            ExecutionResult::Float(2.0),
+        ])
+    );
+
+    // both values need to have the same actual type
+    let source = r#"
+    interface Addable {
+        fun (val a this):add(b this) this
+    }
+
+    fun (val a int):add(b int) int { a + b }
+    fun (val a string):add(b string) string { a }
+
+    fun add_any(a Addable, b Addable) Addable {
+        a:add(b)
+    }
+
+    add_any(1, "a")
+    "#;
+
+    let error = eval_with_vm(source, &mut Vm::new()).unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::TypeMismatch);
+    assert_eq!(error.line, 10);
+}